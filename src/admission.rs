@@ -0,0 +1,155 @@
+// SPDX-FileCopyrightText: © 2025 Claudio Cicconetti <c.cicconetti@iit.cnr.it>
+// SPDX-License-Identifier: MIT
+
+/// Policy to accept or reject the requests of the applications at their
+/// source node, before they are queued.
+#[derive(Debug, Clone, Copy, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum AdmissionPolicy {
+    /// All the requests are accepted.
+    #[default]
+    AcceptAll,
+    /// A request is rejected if the EPR pairs pending towards the next hop,
+    /// including those requested, would take longer than `max_delay`, in s,
+    /// to be generated at the capacity of the bottleneck of the path.
+    PathCapacity { max_delay: f64 },
+    /// A request is rejected if the EPR pairs pending towards the next hop,
+    /// including those requested, would be more than `max_pending`.
+    QueueLength { max_pending: usize },
+    /// Every application has a bucket of up to `burst` tokens, refilled at
+    /// `rate` tokens per second, and a request is rejected unless there is
+    /// one token per EPR pair requested.
+    TokenBucket { rate: f64, burst: f64 },
+}
+
+impl AdmissionPolicy {
+    /// Check that the parameters of the policy are valid.
+    pub fn validate(&self) -> anyhow::Result<()> {
+        match self {
+            AdmissionPolicy::AcceptAll => {}
+            AdmissionPolicy::PathCapacity { max_delay } => {
+                anyhow::ensure!(*max_delay > 0.0, "vanishing admission maximum delay")
+            }
+            AdmissionPolicy::QueueLength { max_pending } => {
+                anyhow::ensure!(*max_pending > 0, "vanishing admission maximum queue length")
+            }
+            AdmissionPolicy::TokenBucket { rate, burst } => {
+                anyhow::ensure!(*rate > 0.0, "vanishing admission token rate");
+                anyhow::ensure!(*burst >= 1.0, "admission token bucket smaller than one");
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Token bucket of an application.
+#[derive(Debug)]
+struct Bucket {
+    /// Number of tokens available.
+    tokens: f64,
+    /// Time when the tokens were last updated, in ns.
+    last_update: u64,
+}
+
+/// Admission control of the requests of the applications on a node.
+#[derive(Debug, Default)]
+pub struct AdmissionControl {
+    /// Admission policy.
+    policy: AdmissionPolicy,
+    /// Token buckets of the applications, by port, with `TokenBucket`.
+    buckets: std::collections::HashMap<u16, Bucket>,
+}
+
+impl AdmissionControl {
+    pub fn new(policy: AdmissionPolicy) -> Self {
+        Self {
+            policy,
+            buckets: std::collections::HashMap::new(),
+        }
+    }
+
+    /// Decide whether to accept a request, consuming the tokens of the
+    /// application if accepted.
+    ///
+    /// Parameters:
+    /// - `now`: the current time, in ns
+    /// - `port`: the port of the application issuing the request
+    /// - `number_of_pairs`: the number of EPR pairs requested
+    /// - `pending`: the number of EPR pairs pending towards the next hop
+    /// - `capacity`: the capacity of the bottleneck of the path, in EPR/s
+    pub fn admit(
+        &mut self,
+        now: u64,
+        port: u16,
+        number_of_pairs: u32,
+        pending: usize,
+        capacity: f64,
+    ) -> bool {
+        let backlog = pending + number_of_pairs as usize;
+        match self.policy {
+            AdmissionPolicy::AcceptAll => true,
+            AdmissionPolicy::PathCapacity { max_delay } => {
+                capacity > 0.0 && backlog as f64 / capacity <= max_delay
+            }
+            AdmissionPolicy::QueueLength { max_pending } => backlog <= max_pending,
+            AdmissionPolicy::TokenBucket { rate, burst } => {
+                let bucket = self.buckets.entry(port).or_insert(Bucket {
+                    tokens: burst,
+                    last_update: now,
+                });
+                bucket.tokens = burst
+                    .min(bucket.tokens + rate * crate::utils::to_seconds(now - bucket.last_update));
+                bucket.last_update = now;
+                if bucket.tokens >= number_of_pairs as f64 {
+                    bucket.tokens -= number_of_pairs as f64;
+                    true
+                } else {
+                    false
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AdmissionControl, AdmissionPolicy};
+
+    #[test]
+    fn test_admission_control_backlog() {
+        let mut admission = AdmissionControl::default();
+        assert!(admission.admit(0, 1, 100, 100, 0.0));
+
+        let mut admission = AdmissionControl::new(AdmissionPolicy::QueueLength { max_pending: 4 });
+        assert!(admission.admit(0, 1, 1, 3, 10.0));
+        assert!(!admission.admit(0, 1, 2, 3, 10.0));
+
+        // With a capacity of 10 EPR/s, 5 pairs take 0.5 s.
+        let mut admission = AdmissionControl::new(AdmissionPolicy::PathCapacity { max_delay: 0.5 });
+        assert!(admission.admit(0, 1, 1, 4, 10.0));
+        assert!(!admission.admit(0, 1, 2, 4, 10.0));
+        assert!(!admission.admit(0, 1, 1, 0, 0.0));
+    }
+
+    #[test]
+    fn test_admission_control_token_bucket() {
+        let mut admission = AdmissionControl::new(AdmissionPolicy::TokenBucket {
+            rate: 2.0,
+            burst: 3.0,
+        });
+        let s = crate::utils::to_nanoseconds;
+
+        // The bucket is full initially.
+        assert!(admission.admit(0, 1, 2, 0, 0.0));
+        assert!(admission.admit(0, 1, 1, 0, 0.0));
+        assert!(!admission.admit(0, 1, 1, 0, 0.0));
+
+        // Every application has its own bucket.
+        assert!(admission.admit(0, 2, 3, 0, 0.0));
+
+        // The bucket is refilled at the given rate, up to the burst size.
+        assert!(!admission.admit(s(0.25), 1, 1, 0, 0.0));
+        assert!(admission.admit(s(0.5), 1, 1, 0, 0.0));
+        assert!(!admission.admit(s(10.0), 1, 4, 0, 0.0));
+        assert!(admission.admit(s(10.0), 1, 3, 0, 0.0));
+    }
+}
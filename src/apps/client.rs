@@ -17,6 +17,8 @@ struct EprRequest {
     local_operations_done: bool,
     /// True if the remote operations have been done.
     remote_operations_done: bool,
+    /// True if the EPR pair was lost because a qubit could not be read.
+    lost: bool,
     /// Timestamp of when the request was created.
     created: u64,
 }
@@ -97,6 +99,9 @@ impl Client {
         let epr_request = self.pending.remove(&request_id);
 
         if let Some(epr_request) = epr_request {
+            if epr_request.lost {
+                return vec![self.pending_len_trace()];
+            }
             vec![
                 Sample::Series(
                     "client-latency".to_string(),
@@ -138,6 +143,14 @@ impl Client {
                 target_node_id: self.peer_node_id,
                 target_port: self.peer_port,
                 request_id: self.next_request_id,
+                request_type: RequestType::CreateAndKeep,
+                number_of_pairs: 1,
+                delivery: Delivery::Sequential,
+                index: 0,
+                timeout: None,
+                max_retries: None,
+                min_fidelity: None,
+                class: 0,
             })),
         ));
 
@@ -147,6 +160,7 @@ impl Client {
                 memory_cell: None,
                 local_operations_done: false,
                 remote_operations_done: false,
+                lost: false,
                 created: now,
             },
         );
@@ -187,7 +201,7 @@ impl Client {
             // Start timer for local operations.
             events.push(Event::new(
                 self.rv_local_ops.sample(&mut self.rng),
-                EventType::AppEvent(AppEventData::LocalComplete(data.epr)),
+                EventType::AppEvent(AppEventData::LocalComplete(data.epr, true)),
             ));
         } else {
             self.pending.remove(&data.epr.request_id);
@@ -220,6 +234,7 @@ impl Client {
                 req_app_port: this_port,
                 consume_node_id: this_node_id,
                 memory_cell_id,
+                epr: epr.clone(),
             })),
         ));
 
@@ -230,6 +245,15 @@ impl Client {
         }
     }
 
+    fn handle_epr_lost(&mut self, epr: EprFiveTuple) -> (Vec<Event>, Vec<Sample>) {
+        // The request is not removed until both the local and remote
+        // operations are done, but its latency is not reported.
+        if let Some(request) = self.pending.get_mut(&epr.request_id) {
+            request.lost = true;
+        }
+        (vec![], vec![])
+    }
+
     fn handle_remote_complete(&mut self, now: u64, epr: EprFiveTuple) -> (Vec<Event>, Vec<Sample>) {
         let request = self.get_request(&epr);
 
@@ -256,8 +280,10 @@ impl EventHandler for Client {
                     self.handle_epr_request(now, node_id, port)
                 }
                 AppEventData::EprResponse(data) => self.handle_epr_response(data),
-                AppEventData::LocalComplete(epr) => self.handle_local_complete(now, epr),
+                AppEventData::LocalComplete(epr, _) => self.handle_local_complete(now, epr),
                 AppEventData::RemoteComplete(epr) => self.handle_remote_complete(now, epr),
+                AppEventData::EprLost(epr, _is_source) => self.handle_epr_lost(epr),
+                _ => panic!("invalid application event received by a client: {data:?}"),
             },
             _ => panic!("invalid event {:?} received by a client", event.event_type),
         }
@@ -304,12 +330,8 @@ mod tests {
     }
 
     fn is_local_complete(event: &EventType, expected_five_tuple: &EprFiveTuple) -> bool {
-        if let EventType::AppEvent(data) = event {
-            if let AppEventData::LocalComplete(actual_five_tuple) = data {
-                expected_five_tuple == actual_five_tuple
-            } else {
-                false
-            }
+        if let EventType::AppEvent(AppEventData::LocalComplete(actual_five_tuple, _)) = event {
+            expected_five_tuple == actual_five_tuple
         } else {
             false
         }
@@ -375,6 +397,7 @@ mod tests {
                         local_pair_id: 0,
                     }),
                     is_source: true,
+                    outcome: None,
                 })),
             ))
             .0;
@@ -384,7 +407,7 @@ mod tests {
         let events = client
             .handle(Event::new(
                 1.0,
-                EventType::AppEvent(AppEventData::LocalComplete(five_tuple.clone())),
+                EventType::AppEvent(AppEventData::LocalComplete(five_tuple.clone(), true)),
             ))
             .0;
         assert_eq!(1, events.len());
@@ -398,4 +421,68 @@ mod tests {
             .0;
         assert!(events.is_empty());
     }
+
+    #[test]
+    fn test_client_epr_lost() {
+        let mut client = Client::new(0, 50000, 1, 8080, 42, 1.0, 0.1);
+
+        let events = client
+            .handle(Event::new(
+                1.0,
+                EventType::AppEvent(AppEventData::EprRequest(0, 50000)),
+            ))
+            .0;
+        let five_tuple = if let EventType::NodeEvent(NodeEventData::EprRequestApp(five_tuple)) =
+            &events[0].event_type
+        {
+            five_tuple.clone()
+        } else {
+            panic!("wrong event type")
+        };
+
+        client.handle(Event::new(
+            1.0,
+            EventType::AppEvent(AppEventData::EprResponse(EprResponseData {
+                epr: five_tuple.clone(),
+                memory_cell: Some(MemoryCellId {
+                    neighbor_node_id: 1,
+                    role: nic::Role::Master,
+                    local_pair_id: 0,
+                }),
+                is_source: true,
+                outcome: None,
+            })),
+        ));
+
+        // The qubit at the server could not be read from the memory.
+        let (events, samples) = client.handle(Event::new(
+            1.0,
+            EventType::AppEvent(AppEventData::EprLost(five_tuple.clone(), true)),
+        ));
+        assert!(events.is_empty());
+        assert!(samples.is_empty());
+
+        client.handle(Event::new(
+            1.0,
+            EventType::AppEvent(AppEventData::LocalComplete(five_tuple.clone(), true)),
+        ));
+        let (_events, samples) = client.handle(Event::new(
+            1.0,
+            EventType::AppEvent(AppEventData::RemoteComplete(five_tuple.clone())),
+        ));
+
+        // The request is removed without reporting its latency.
+        assert!(client.pending.is_empty());
+        assert!(!samples.iter().any(
+            |sample| matches!(sample, crate::output::Sample::Series(name, _, _) if name == "client-latency")
+        ));
+
+        // A late notification is ignored.
+        let (events, samples) = client.handle(Event::new(
+            1.0,
+            EventType::AppEvent(AppEventData::EprLost(five_tuple, true)),
+        ));
+        assert!(events.is_empty());
+        assert!(samples.is_empty());
+    }
 }
@@ -0,0 +1,89 @@
+// SPDX-FileCopyrightText: © 2025 Claudio Cicconetti <c.cicconetti@iit.cnr.it>
+// SPDX-License-Identifier: MIT
+
+use crate::event::*;
+use crate::output::Sample;
+
+/// Create-and-measure application, e.g., for QKD, which measures the EPR
+/// pairs of a memoryless logical link as soon as they are detected.
+/// The pairs are measured until a maximum is reached.
+#[derive(Debug)]
+pub struct Measurer {
+    /// Node ID.
+    this_node_id: u32,
+    /// Port.
+    this_port: u16,
+    /// Peer node ID of the memoryless logical link.
+    peer_node_id: u32,
+    /// Maximum number of EPR pairs measured. 0 means infinite.
+    max_pairs: u64,
+    /// Number of EPR pairs measured so far.
+    measured: u64,
+}
+
+impl Measurer {
+    /// Create a new measurer application.
+    ///
+    /// Parameters:
+    /// - `this_node_id`: Node ID.
+    /// - `this_port`: Port.
+    /// - `peer_node_id`: Peer node ID of the memoryless logical link.
+    /// - `max_pairs`: Maximum number of EPR pairs measured.
+    pub fn new(this_node_id: u32, this_port: u16, peer_node_id: u32, max_pairs: u64) -> Self {
+        Self {
+            this_node_id,
+            this_port,
+            peer_node_id,
+            max_pairs,
+            measured: 0,
+        }
+    }
+
+    fn handle_epr_measured(&mut self, data: EprMeasuredData) -> (Vec<Event>, Vec<Sample>) {
+        assert_eq!(self.this_node_id, data.this_node_id);
+        assert_eq!(self.this_port, data.this_port);
+        assert_eq!(
+            self.peer_node_id, data.peer_node_id,
+            "received EPR pair from an unexpected peer"
+        );
+
+        // The EPR pair cannot be stored, hence it is lost when not measured.
+        if self.max_pairs > 0 && self.measured >= self.max_pairs {
+            return (vec![], vec![]);
+        }
+        self.measured += 1;
+
+        (
+            vec![Event::new(
+                0.0,
+                EventType::NetworkEvent(NetworkEventData::EprMeasure(EprMeasureData {
+                    req_app_node_id: self.this_node_id,
+                    req_app_port: self.this_port,
+                    measure_node_id: self.this_node_id,
+                    epr_pair_id: data.epr_pair_id,
+                    response: None,
+                })),
+            )],
+            vec![],
+        )
+    }
+}
+
+impl EventHandler for Measurer {
+    fn handle(&mut self, event: Event) -> (Vec<Event>, Vec<Sample>) {
+        match event.event_type {
+            EventType::AppEvent(data) => match data {
+                AppEventData::EprMeasured(data) => self.handle_epr_measured(data),
+                _ => panic!("invalid application event received by a measurer: {data:?}"),
+            },
+            _ => panic!(
+                "invalid event {:?} received by a measurer",
+                event.event_type
+            ),
+        }
+    }
+
+    fn initial(&mut self) -> Vec<Event> {
+        vec![]
+    }
+}
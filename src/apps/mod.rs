@@ -2,6 +2,8 @@
 // SPDX-License-Identifier: MIT
 
 pub mod client;
+pub mod measurer;
 pub mod pinger;
 pub mod ponger;
+pub mod reserver;
 pub mod server;
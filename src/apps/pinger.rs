@@ -7,6 +7,8 @@ use crate::output::Sample;
 /// Application that requests an EPR towards another node and measures the
 /// fidelity immediately after it is established.
 /// The requests are issued back-to-back until a maximum is reached.
+/// Every request may be for multiple EPR pairs, in which case the next
+/// request is issued when all of them have been delivered or failed.
 #[derive(Debug)]
 pub struct Pinger {
     /// Source node ID.
@@ -19,6 +21,24 @@ pub struct Pinger {
     peer_port: u16,
     /// Maximum number of requests. 0 means infinite.
     max_requests: u64,
+    /// Type of the requests.
+    request_type: RequestType,
+    /// Number of EPR pairs per request.
+    number_of_pairs: u32,
+    /// Delivery of the EPR pairs of a request.
+    delivery: Delivery,
+    /// Number of EPR pairs delivered for the last request.
+    delivered: u32,
+    /// Deadline of the requests, in ns.
+    timeout: Option<u64>,
+    /// Maximum number of retries of the requests.
+    max_retries: Option<u32>,
+    /// Minimum fidelity of the EPR pairs.
+    min_fidelity: Option<MinFidelity>,
+    /// Traffic class.
+    class: u32,
+    /// True if an EPR pair of the last request has failed.
+    failed: bool,
     /// ID of the next request.
     next_request_id: u64,
     /// Timestamp of when the last request was created.
@@ -34,12 +54,14 @@ impl Pinger {
     /// - `peer_node_id`: Target node ID.
     /// - `peer_port`: Target port.
     /// - `max_requests`: Maximum number of requests.
+    /// - `request_type`: Type of the requests.
     pub fn new(
         this_node_id: u32,
         this_port: u16,
         peer_node_id: u32,
         peer_port: u16,
         max_requests: u64,
+        request_type: RequestType,
     ) -> Self {
         Self {
             this_node_id,
@@ -48,10 +70,55 @@ impl Pinger {
             peer_port,
             next_request_id: 0,
             max_requests,
+            request_type,
+            number_of_pairs: 1,
+            delivery: Delivery::Sequential,
+            delivered: 0,
+            timeout: None,
+            max_retries: None,
+            min_fidelity: None,
+            class: 0,
+            failed: false,
             created: 0,
         }
     }
 
+    /// Request multiple EPR pairs at a time.
+    ///
+    /// Parameters:
+    /// - `number_of_pairs`: Number of EPR pairs per request.
+    /// - `delivery`: Delivery of the EPR pairs of a request.
+    pub fn set_batch(&mut self, number_of_pairs: u32, delivery: Delivery) {
+        self.number_of_pairs = number_of_pairs;
+        self.delivery = delivery;
+    }
+
+    /// Make the requests fail after a deadline or a number of retries.
+    ///
+    /// Parameters:
+    /// - `timeout`: Deadline of the requests, in s.
+    /// - `max_retries`: Maximum number of retries of the requests.
+    pub fn set_limits(&mut self, timeout: Option<f64>, max_retries: Option<u32>) {
+        self.timeout = timeout.map(crate::utils::to_nanoseconds);
+        self.max_retries = max_retries;
+    }
+
+    /// Require a minimum fidelity for the EPR pairs.
+    ///
+    /// Parameters:
+    /// - `min_fidelity`: Minimum end-to-end fidelity of the EPR pairs.
+    pub fn set_min_fidelity(&mut self, min_fidelity: Option<f64>) {
+        self.min_fidelity = min_fidelity.map(MinFidelity);
+    }
+
+    /// Set the traffic class of the application.
+    ///
+    /// Parameters:
+    /// - `class`: Traffic class, 0 having the highest priority.
+    pub fn set_class(&mut self, class: u32) {
+        self.class = class;
+    }
+
     fn handle_epr_request(
         &mut self,
         now: u64,
@@ -62,6 +129,8 @@ impl Pinger {
         assert!(self.this_port == port);
 
         self.created = now;
+        self.delivered = 0;
+        self.failed = false;
         let event = Event::new(
             0.0,
             EventType::NodeEvent(NodeEventData::EprRequestApp(EprFiveTuple {
@@ -70,6 +139,14 @@ impl Pinger {
                 target_node_id: self.peer_node_id,
                 target_port: self.peer_port,
                 request_id: self.next_request_id,
+                request_type: self.request_type,
+                number_of_pairs: self.number_of_pairs,
+                delivery: self.delivery,
+                index: 0,
+                timeout: self.timeout,
+                max_retries: self.max_retries,
+                min_fidelity: self.min_fidelity,
+                class: self.class,
             })),
         );
         self.next_request_id += 1;
@@ -93,18 +170,30 @@ impl Pinger {
 
         let mut data = data;
 
-        // Compute the fidelity on the local end of this EPR.
-        let memory_cell_id = std::mem::take(&mut data.memory_cell)
-            .unwrap_or_else(|| panic!("empty memory cell received"));
-        events.push(Event::new(
-            0.0,
-            EventType::NetworkEvent(NetworkEventData::EprConsume(EprConsumeData {
-                req_app_node_id: self.this_node_id,
-                req_app_port: self.this_port,
-                consume_node_id: self.this_node_id,
-                memory_cell_id,
-            })),
-        ));
+        // Compute the fidelity on the local end of this EPR, unless already
+        // measured by the node or the request failed.
+        if data.memory_cell.is_none() && data.outcome.is_none() {
+            self.failed = true;
+        } else if let RequestType::CreateAndKeep = data.epr.request_type {
+            let memory_cell_id = std::mem::take(&mut data.memory_cell)
+                .unwrap_or_else(|| panic!("empty memory cell received"));
+            events.push(Event::new(
+                0.0,
+                EventType::NetworkEvent(NetworkEventData::EprConsume(EprConsumeData {
+                    req_app_node_id: self.this_node_id,
+                    req_app_port: self.this_port,
+                    consume_node_id: self.this_node_id,
+                    memory_cell_id,
+                    epr: data.epr.clone(),
+                })),
+            ));
+        }
+
+        // Wait for the other EPR pairs of the same request.
+        self.delivered += 1;
+        if self.delivered < self.number_of_pairs {
+            return (events, vec![]);
+        }
 
         // Terminate if the maximum number of requests was exceeded.
         if self.max_requests == 0 || self.next_request_id < self.max_requests {
@@ -114,6 +203,9 @@ impl Pinger {
             ));
         }
 
+        if self.failed {
+            return (events, vec![]);
+        }
         (
             events,
             vec![Sample::Series(
@@ -123,6 +215,15 @@ impl Pinger {
             )],
         )
     }
+
+    fn handle_epr_lost(&mut self, epr: EprFiveTuple) -> (Vec<Event>, Vec<Sample>) {
+        // Mark the last request as failed if it is still waiting for the
+        // other EPR pairs, otherwise its latency has been reported already.
+        if epr.request_id + 1 == self.next_request_id && self.delivered < self.number_of_pairs {
+            self.failed = true;
+        }
+        (vec![], vec![])
+    }
 }
 
 impl EventHandler for Pinger {
@@ -134,6 +235,7 @@ impl EventHandler for Pinger {
                     self.handle_epr_request(now, node_id, port)
                 }
                 AppEventData::EprResponse(data) => self.handle_epr_response(now, data),
+                AppEventData::EprLost(epr, _is_source) => self.handle_epr_lost(epr),
                 _ => panic!("invalid application event received by a pinger: {data:?}"),
             },
             _ => panic!("invalid event {:?} received by a pinger", event.event_type),
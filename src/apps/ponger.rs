@@ -42,6 +42,11 @@ impl Ponger {
             "received EPR response not addressed to this port"
         );
 
+        // The qubit has been already measured by the node.
+        if let RequestType::MeasureDirectly(_) = data.epr.request_type {
+            return (vec![], vec![]);
+        }
+
         if let Some(memory_cell_id) = data.memory_cell {
             (
                 vec![Event::new(
@@ -51,6 +56,7 @@ impl Ponger {
                         req_app_port: data.epr.source_port,
                         consume_node_id: self.this_node_id,
                         memory_cell_id,
+                        epr: data.epr.clone(),
                     })),
                 )],
                 vec![],
@@ -69,6 +75,8 @@ impl EventHandler for Ponger {
         match event.event_type {
             EventType::AppEvent(data) => match data {
                 AppEventData::EprResponse(data) => self.handle_epr_response(data),
+                // Nothing to do: the half EPR pair has been consumed already.
+                AppEventData::EprLost(_epr, _is_source) => (vec![], vec![]),
                 _ => panic!("invalid event received by a ponger: {data:?}"),
             },
             _ => panic!("invalid event {:?} received by a ponger", event.event_type),
@@ -0,0 +1,175 @@
+// SPDX-FileCopyrightText: © 2025 Claudio Cicconetti <c.cicconetti@iit.cnr.it>
+// SPDX-License-Identifier: MIT
+
+use crate::event::*;
+use crate::output::Sample;
+
+/// Application that periodically books EPR pairs towards another node for a
+/// future time window and measures the fidelity of those delivered
+/// immediately.
+#[derive(Debug)]
+pub struct Reserver {
+    /// Source node ID.
+    this_node_id: u32,
+    /// Source port.
+    this_port: u16,
+    /// Target node ID.
+    peer_node_id: u32,
+    /// Target port.
+    peer_port: u16,
+    /// Maximum number of reservations. 0 means infinite.
+    max_reservations: u64,
+    /// Number of EPR pairs per reservation.
+    number_of_pairs: u32,
+    /// Time between a reservation and the start of its window, in s.
+    lead_time: f64,
+    /// Duration of the time window of the reservations, in s.
+    window: f64,
+    /// Time between consecutive reservations, in s.
+    interval: f64,
+    /// ID of the next reservation.
+    next_request_id: u64,
+}
+
+impl Reserver {
+    /// Create a new reserver application.
+    ///
+    /// Parameters:
+    /// - `this_node_id`: Source node ID.
+    /// - `this_port`: Source port.
+    /// - `peer_node_id`: Target node ID.
+    /// - `peer_port`: Target port.
+    /// - `max_reservations`: Maximum number of reservations.
+    /// - `number_of_pairs`: Number of EPR pairs per reservation.
+    pub fn new(
+        this_node_id: u32,
+        this_port: u16,
+        peer_node_id: u32,
+        peer_port: u16,
+        max_reservations: u64,
+        number_of_pairs: u32,
+    ) -> Self {
+        Self {
+            this_node_id,
+            this_port,
+            peer_node_id,
+            peer_port,
+            max_reservations,
+            number_of_pairs,
+            lead_time: 0.0,
+            window: 1.0,
+            interval: 1.0,
+            next_request_id: 0,
+        }
+    }
+
+    /// Set the timing of the reservations.
+    ///
+    /// Parameters:
+    /// - `lead_time`: Time between a reservation and the start of its window, in s.
+    /// - `window`: Duration of the time window of the reservations, in s.
+    /// - `interval`: Time between consecutive reservations, in s.
+    pub fn set_timing(&mut self, lead_time: f64, window: f64, interval: f64) {
+        self.lead_time = lead_time;
+        self.window = window;
+        self.interval = interval;
+    }
+
+    fn handle_epr_request(
+        &mut self,
+        now: u64,
+        node_id: u32,
+        port: u16,
+    ) -> (Vec<Event>, Vec<Sample>) {
+        assert!(self.this_node_id == node_id);
+        assert!(self.this_port == port);
+
+        let start = now + crate::utils::to_nanoseconds(self.lead_time);
+        let mut events = vec![Event::new(
+            0.0,
+            EventType::NodeEvent(NodeEventData::EprReservationApp(ReservationData {
+                epr: EprFiveTuple {
+                    number_of_pairs: self.number_of_pairs,
+                    ..EprFiveTuple::new(
+                        self.this_node_id,
+                        self.this_port,
+                        self.peer_node_id,
+                        self.peer_port,
+                        self.next_request_id,
+                    )
+                },
+                start,
+                end: start + crate::utils::to_nanoseconds(self.window),
+            })),
+        )];
+        self.next_request_id += 1;
+
+        // Terminate if the maximum number of reservations was exceeded.
+        if self.max_reservations == 0 || self.next_request_id < self.max_reservations {
+            events.push(Event::new(
+                self.interval,
+                EventType::AppEvent(AppEventData::EprRequest(self.this_node_id, self.this_port)),
+            ));
+        }
+
+        (events, vec![])
+    }
+
+    fn handle_epr_response(&mut self, data: EprResponseData) -> (Vec<Event>, Vec<Sample>) {
+        assert!(
+            data.is_source,
+            "received EPR response addressed to the ponger at reserver {}:{}",
+            self.this_node_id, self.this_port
+        );
+
+        // Compute the fidelity on the local end of this EPR, unless the
+        // reserved EPR pair was not delivered by the end of the window.
+        if let Some(memory_cell_id) = data.memory_cell {
+            (
+                vec![Event::new(
+                    0.0,
+                    EventType::NetworkEvent(NetworkEventData::EprConsume(EprConsumeData {
+                        req_app_node_id: self.this_node_id,
+                        req_app_port: self.this_port,
+                        consume_node_id: self.this_node_id,
+                        memory_cell_id,
+                        epr: data.epr.clone(),
+                    })),
+                )],
+                vec![],
+            )
+        } else {
+            (vec![], vec![])
+        }
+    }
+}
+
+impl EventHandler for Reserver {
+    fn handle(&mut self, event: Event) -> (Vec<Event>, Vec<Sample>) {
+        let now = event.time();
+        match event.event_type {
+            EventType::AppEvent(data) => match data {
+                AppEventData::EprRequest(node_id, port) => {
+                    self.handle_epr_request(now, node_id, port)
+                }
+                AppEventData::EprResponse(data) => self.handle_epr_response(data),
+                // The EPR pairs of accepted reservations are delivered
+                // without further action by the application.
+                AppEventData::ReservationResponse(_data, _accepted) => (vec![], vec![]),
+                AppEventData::EprLost(_epr, _is_source) => (vec![], vec![]),
+                _ => panic!("invalid application event received by a reserver: {data:?}"),
+            },
+            _ => panic!(
+                "invalid event {:?} received by a reserver",
+                event.event_type
+            ),
+        }
+    }
+
+    fn initial(&mut self) -> Vec<Event> {
+        vec![Event::new(
+            0.0,
+            EventType::AppEvent(AppEventData::EprRequest(self.this_node_id, self.this_port)),
+        )]
+    }
+}
@@ -98,7 +98,7 @@ impl Server {
             // Start timer for local operations.
             vec![Event::new(
                 self.rv_local_ops.sample(&mut self.rng),
-                EventType::AppEvent(AppEventData::LocalComplete(data.epr)),
+                EventType::AppEvent(AppEventData::LocalComplete(data.epr, false)),
             )],
             // Trace the queue of local operations.
             vec![self.pending_len_trace()],
@@ -128,6 +128,7 @@ impl Server {
                 req_app_port: request.client_port,
                 consume_node_id: self.this_node_id,
                 memory_cell_id,
+                epr: epr.clone(),
             })),
         ));
 
@@ -149,7 +150,10 @@ impl EventHandler for Server {
         match event.event_type {
             EventType::AppEvent(data) => match data {
                 AppEventData::EprResponse(data) => self.handle_epr_response(data),
-                AppEventData::LocalComplete(epr) => self.handle_local_complete(now, epr),
+                AppEventData::LocalComplete(epr, _) => self.handle_local_complete(now, epr),
+                // The local operations complete anyway, the memory cell
+                // holding the half EPR pair is released by the network.
+                AppEventData::EprLost(_epr, _is_source) => (vec![], vec![]),
                 _ => panic!("invalid event received by a server: {data:?}"),
             },
             _ => panic!(
@@ -168,6 +172,7 @@ impl EventHandler for Server {
 mod tests {
 
     use crate::event::AppEventData;
+    use crate::event::Delivery;
     use crate::event::EprFiveTuple;
     use crate::event::EprResponseData;
     use crate::event::Event;
@@ -175,29 +180,22 @@ mod tests {
     use crate::event::EventType;
     use crate::event::MemoryCellId;
     use crate::event::NetworkEventData;
+    use crate::event::RequestType;
     use crate::nic;
 
     use super::Server;
 
     fn is_local_complete(event: &EventType, expected_five_tuple: &EprFiveTuple) -> bool {
-        if let EventType::AppEvent(data) = event {
-            if let AppEventData::LocalComplete(actual_five_tuple) = data {
-                expected_five_tuple == actual_five_tuple
-            } else {
-                false
-            }
+        if let EventType::AppEvent(AppEventData::LocalComplete(actual_five_tuple, _)) = event {
+            expected_five_tuple == actual_five_tuple
         } else {
             false
         }
     }
 
     fn is_remote_complete(event: &EventType, expected_five_tuple: &EprFiveTuple) -> bool {
-        if let EventType::AppEvent(data) = event {
-            if let AppEventData::RemoteComplete(actual_five_tuple) = data {
-                expected_five_tuple == actual_five_tuple
-            } else {
-                false
-            }
+        if let EventType::AppEvent(AppEventData::RemoteComplete(actual_five_tuple)) = event {
+            expected_five_tuple == actual_five_tuple
         } else {
             false
         }
@@ -223,11 +221,19 @@ mod tests {
         assert!(server.initial().is_empty());
 
         let five_tuple = EprFiveTuple {
-            source_node_id: peer_node_id.clone(),
-            source_port: peer_port.clone(),
-            target_node_id: this_node_id.clone(),
-            target_port: this_port.clone(),
+            source_node_id: peer_node_id,
+            source_port: peer_port,
+            target_node_id: this_node_id,
+            target_port: this_port,
             request_id,
+            request_type: RequestType::CreateAndKeep,
+            number_of_pairs: 1,
+            delivery: Delivery::Sequential,
+            index: 0,
+            timeout: None,
+            max_retries: None,
+            min_fidelity: None,
+            class: 0,
         };
         let events = server
             .handle(Event::new(
@@ -240,6 +246,7 @@ mod tests {
                         local_pair_id: 0,
                     }),
                     is_source: false,
+                    outcome: None,
                 })),
             ))
             .0;
@@ -249,7 +256,7 @@ mod tests {
         let events = server
             .handle(Event::new(
                 1.0,
-                EventType::AppEvent(AppEventData::LocalComplete(five_tuple.clone())),
+                EventType::AppEvent(AppEventData::LocalComplete(five_tuple.clone(), false)),
             ))
             .0;
         assert_eq!(2, events.len());
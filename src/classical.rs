@@ -0,0 +1,316 @@
+// SPDX-FileCopyrightText: © 2025 Claudio Cicconetti <c.cicconetti@iit.cnr.it>
+// SPDX-License-Identifier: MIT
+
+use rand::{Rng, SeedableRng};
+
+use crate::event::{EventType, NodeEventData, Trasfer};
+
+/// Size of the control-plane messages, in bytes.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct MessageSizes {
+    /// Request to perform entanglement swapping, which carries the full path.
+    pub es_request: u32,
+    /// Successful response to a request to perform entanglement swapping.
+    pub es_success: u32,
+    /// Failed response to a request to perform entanglement swapping.
+    pub es_failure: u32,
+    /// Notification that the end-to-end EPR creation failed.
+    pub es_remote_failed: u32,
+    /// Notification that the end-to-end EPR creation completed.
+    pub es_remote_complete: u32,
+    /// Abort of the entanglement swapping for a failed request.
+    pub es_abort: u32,
+    /// Messages exchanged between applications.
+    pub app: u32,
+}
+
+impl Default for MessageSizes {
+    fn default() -> Self {
+        Self {
+            es_request: 128,
+            es_success: 64,
+            es_failure: 64,
+            es_remote_failed: 64,
+            es_remote_complete: 64,
+            es_abort: 64,
+            app: 64,
+        }
+    }
+}
+
+impl MessageSizes {
+    /// Return the size of the message carrying the given event, in bytes.
+    pub fn size(&self, event_type: &EventType) -> u32 {
+        match event_type {
+            EventType::NodeEvent(data) => match data {
+                NodeEventData::EsRequest(_) => self.es_request,
+                NodeEventData::EsSuccess(_) => self.es_success,
+                NodeEventData::EsFailure(_) => self.es_failure,
+                NodeEventData::EsRemoteFailed(_) => self.es_remote_failed,
+                NodeEventData::EsRemoteComplete(_) => self.es_remote_complete,
+                NodeEventData::EsAbort(_) => self.es_abort,
+                NodeEventData::EprRequestApp(_)
+                | NodeEventData::EprRequestTimeout(_)
+                | NodeEventData::EprReservationApp(_)
+                | NodeEventData::ReservationStart(_)
+                | NodeEventData::ReservationEnd(_)
+                | NodeEventData::EsMemoryRead(_)
+                | NodeEventData::EsLocalComplete(_) => {
+                    panic!("no message carries a local event {:?}", data)
+                }
+            },
+            EventType::AppEvent(_) => self.app,
+            _ => panic!("no message carries an event {:?}", event_type),
+        }
+    }
+}
+
+/// Classical network used by the control plane, where the messages are
+/// forwarded along the physical links with minimum latency.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ClassicalParams {
+    /// Bandwidth of fiber links, in b/s.
+    pub fiber_bandwidth: f64,
+    /// Bandwidth of free-space links, in b/s.
+    pub free_space_bandwidth: f64,
+    /// Probability that a message is lost on a fiber link.
+    pub fiber_loss_prob: f64,
+    /// Probability that a message is lost on a free-space link.
+    pub free_space_loss_prob: f64,
+    /// Time after which a lost message is retransmitted, in s.
+    pub retransmission_timeout: f64,
+    /// Time to process a message before forwarding it at every hop, in s.
+    pub processing_delay: f64,
+    /// Size of the messages.
+    pub message_sizes: MessageSizes,
+}
+
+impl Default for ClassicalParams {
+    fn default() -> Self {
+        Self {
+            fiber_bandwidth: 1e9,
+            free_space_bandwidth: 1e8,
+            fiber_loss_prob: 0.0,
+            free_space_loss_prob: 0.01,
+            retransmission_timeout: 0.01,
+            processing_delay: 1e-5,
+            message_sizes: MessageSizes::default(),
+        }
+    }
+}
+
+impl ClassicalParams {
+    fn valid(&self) -> anyhow::Result<()> {
+        anyhow::ensure!(
+            self.fiber_bandwidth > 0.0 && self.free_space_bandwidth > 0.0,
+            "invalid bandwidth of fiber ({}) or free-space ({}) links",
+            self.fiber_bandwidth,
+            self.free_space_bandwidth
+        );
+        anyhow::ensure!(
+            (0.0..1.0).contains(&self.fiber_loss_prob)
+                && (0.0..1.0).contains(&self.free_space_loss_prob),
+            "invalid loss probability of fiber ({}) or free-space ({}) links",
+            self.fiber_loss_prob,
+            self.free_space_loss_prob
+        );
+        anyhow::ensure!(
+            self.retransmission_timeout >= 0.0,
+            "invalid retransmission timeout: {} < 0",
+            self.retransmission_timeout
+        );
+        anyhow::ensure!(
+            self.processing_delay >= 0.0,
+            "invalid processing delay: {} < 0",
+            self.processing_delay
+        );
+        Ok(())
+    }
+}
+
+/// Forwarding of messages along the classical network.
+///
+/// Every link serves the messages in each direction in FIFO order, one at
+/// a time, and a lost message is retransmitted over the same hop until it
+/// is received (stop-and-wait).
+#[derive(Debug)]
+pub struct ClassicalNetwork {
+    /// Configuration parameters.
+    params: ClassicalParams,
+    /// Paths from source to destination, by pair of end nodes.
+    routes: std::collections::HashMap<(u32, u32), Vec<u32>>,
+    /// Time when each link becomes idle, by pair of end nodes, in ns.
+    busy_until: std::collections::HashMap<(u32, u32), u64>,
+    /// Pseudo-random number generator.
+    rng: rand::rngs::StdRng,
+}
+
+impl ClassicalNetwork {
+    pub fn new(params: ClassicalParams, seed: u64) -> anyhow::Result<Self> {
+        params.valid()?;
+        Ok(Self {
+            params,
+            routes: std::collections::HashMap::new(),
+            busy_until: std::collections::HashMap::new(),
+            rng: rand::rngs::StdRng::seed_from_u64(seed),
+        })
+    }
+
+    /// Forward a message from its current hop to the next one.
+    ///
+    /// Return the time until the message is received by the next hop, in ns,
+    /// and the number of times it has been lost.
+    ///
+    /// Parameters:
+    /// - `now`: the current time, in ns
+    /// - `transfer`: the message, whose current hop is updated
+    /// - `event_type`: the event carried by the message
+    /// - `physical_topology`: the physical topology
+    pub fn forward(
+        &mut self,
+        now: u64,
+        transfer: &mut Trasfer,
+        event_type: &EventType,
+        physical_topology: &crate::physical_topology::PhysicalTopology,
+    ) -> (u64, u32) {
+        let route = self
+            .routes
+            .entry((transfer.src_node_id, transfer.dst_node_id))
+            .or_insert_with(|| {
+                physical_topology
+                    .latency_path(transfer.src_node_id, transfer.dst_node_id)
+                    .expect("cannot find a path between two nodes")
+            });
+        let pos = route
+            .iter()
+            .position(|x| *x == transfer.hop)
+            .expect("the current hop is not in the path of a message");
+        let next_hop = route[pos + 1];
+
+        let link = physical_topology
+            .link(transfer.hop, next_hop)
+            .expect("no link between two consecutive hops");
+        let (bandwidth, loss_prob) = match link.link() {
+            crate::physical_topology::LinkType::Fiber(_) => {
+                (self.params.fiber_bandwidth, self.params.fiber_loss_prob)
+            }
+            crate::physical_topology::LinkType::FreeSpace => (
+                self.params.free_space_bandwidth,
+                self.params.free_space_loss_prob,
+            ),
+        };
+        let transmission_time = crate::utils::to_nanoseconds(
+            self.params.message_sizes.size(event_type) as f64 * 8.0 / bandwidth,
+        );
+
+        // Wait for the link to be idle, then transmit until the message
+        // is received.
+        let ready = now + crate::utils::to_nanoseconds(self.params.processing_delay);
+        let busy_until = self.busy_until.entry((transfer.hop, next_hop)).or_default();
+        let mut end = ready.max(*busy_until) + transmission_time;
+        let mut losses = 0;
+        while self.rng.gen::<f64>() < loss_prob {
+            losses += 1;
+            end += crate::utils::to_nanoseconds(self.params.retransmission_timeout)
+                + transmission_time;
+        }
+        *busy_until = end;
+
+        transfer.hop = next_hop;
+        (
+            end - now + crate::utils::to_nanoseconds(link.latency()),
+            losses,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::event::{EprFiveTuple, EventType, NodeEventData, Trasfer};
+
+    use super::{ClassicalNetwork, ClassicalParams, MessageSizes};
+
+    #[test]
+    fn test_classical_forward() {
+        // 0 (OGS) -- 2 (SAT) -- 1 (OGS)
+        let topo = crate::physical_topology::PhysicalTopology::from_chain_static(
+            crate::physical_topology::ChainParams {
+                orbit_to_orbit_distance: 0.0,
+                ground_to_orbit_distance: 299792458.0 / 1000.0,
+                num_repeaters: 1,
+                uplink_loss: 0.0,
+            },
+            crate::physical_topology::NodeWeight::default_sat(),
+            crate::physical_topology::NodeWeight::default_ogs(),
+            crate::physical_topology::StaticFidelities::default(),
+        )
+        .unwrap();
+        let event_type = EventType::NodeEvent(NodeEventData::EsRemoteComplete(EprFiveTuple::new(
+            0, 0, 1, 0, 0,
+        )));
+
+        // 1 ms to transmit, 1 ms to propagate, 1 ms to process.
+        let params = ClassicalParams {
+            free_space_bandwidth: 1000.0 * 8.0,
+            free_space_loss_prob: 0.0,
+            processing_delay: 0.001,
+            message_sizes: MessageSizes {
+                es_remote_complete: 1,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let mut classical = ClassicalNetwork::new(params.clone(), 42).unwrap();
+        let mut transfer = Trasfer::new(0, 1);
+        assert_eq!(
+            (3_000_000, 0),
+            classical.forward(0, &mut transfer, &event_type, &topo)
+        );
+        assert_eq!(2, transfer.hop);
+        assert_eq!(
+            (3_000_000, 0),
+            classical.forward(3_000_000, &mut transfer, &event_type, &topo)
+        );
+        assert_eq!(1, transfer.hop);
+
+        // The second message waits for the first one to be transmitted.
+        let mut transfer = Trasfer::new(0, 1);
+        assert_eq!(
+            (3_000_000, 0),
+            classical.forward(9_000_000, &mut transfer, &event_type, &topo)
+        );
+        let mut transfer = Trasfer::new(0, 1);
+        assert_eq!(
+            (4_000_000, 0),
+            classical.forward(9_000_000, &mut transfer, &event_type, &topo)
+        );
+
+        // Lost messages are retransmitted after a timeout.
+        let mut classical = ClassicalNetwork::new(
+            ClassicalParams {
+                free_space_loss_prob: 0.5,
+                ..params
+            },
+            42,
+        )
+        .unwrap();
+        let mut tot_losses = 0;
+        for i in 0..100 {
+            let mut transfer = Trasfer::new(0, 1);
+            let now = i * 1_000_000_000;
+            let (delay, losses) = classical.forward(now, &mut transfer, &event_type, &topo);
+            assert_eq!(3_000_000 + losses as u64 * 11_000_000, delay);
+            tot_losses += losses;
+        }
+        assert!(tot_losses > 50 && tot_losses < 150, "{}", tot_losses);
+
+        assert!(ClassicalNetwork::new(
+            ClassicalParams {
+                fiber_loss_prob: 1.0,
+                ..Default::default()
+            },
+            42
+        )
+        .is_err());
+    }
+}
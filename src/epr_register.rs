@@ -8,10 +8,16 @@ pub struct EprPair {
     alice_id: Option<u32>,
     /// Identifier of the other node holding the EPR pair or None if consumed.
     bob_id: Option<u32>,
+    /// Local pair identifier of the qubit held by `alice_id`.
+    alice_pair_id: u64,
+    /// Local pair identifier of the qubit held by `bob_id`.
+    bob_pair_id: u64,
     /// Time when the fidelity was last updated.
     updated: u64,
     /// Fidelity the EPR pair at `updated` time.
     fidelity: f64,
+    /// Outcome of the measurement of the qubit consumed first, if measured.
+    outcome: Option<crate::event::MeasureOutcome>,
 }
 
 impl EprPair {
@@ -38,11 +44,36 @@ impl EprPair {
             self.alice_id.is_none() && self.bob_id.is_none(),
         ))
     }
+
+    /// Return the local pair identifier of the qubit held by a node, if any.
+    fn local_pair_id(&self, node_id: u32) -> Option<u64> {
+        if self.alice_id == Some(node_id) {
+            Some(self.alice_pair_id)
+        } else if self.bob_id == Some(node_id) {
+            Some(self.bob_pair_id)
+        } else {
+            None
+        }
+    }
+
+    /// Return the node holding the only qubit not consumed yet, if any, with
+    /// its local pair identifier.
+    fn remaining(&self) -> Option<(u32, u64)> {
+        match (self.alice_id, self.bob_id) {
+            (Some(alice_id), None) => Some((alice_id, self.alice_pair_id)),
+            (None, Some(bob_id)) => Some((bob_id, self.bob_pair_id)),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Default)]
 pub struct EprRegister {
     epr_pairs: std::collections::HashMap<u64, EprPair>,
+    /// Local pair identifiers of the qubits held by the end-points of the
+    /// EPR pairs established by entanglement swapping, mapped to the
+    /// identifier of the EPR pair they belong to.
+    aliases: std::collections::HashMap<u64, u64>,
     last_epr_pair_id: u64,
 }
 
@@ -56,8 +87,11 @@ impl EprRegister {
             EprPair {
                 alice_id: Some(alice_id),
                 bob_id: Some(bob_id),
+                alice_pair_id: epr_pair_id,
+                bob_pair_id: epr_pair_id,
                 updated,
                 fidelity,
+                outcome: None,
             },
         );
         assert!(
@@ -69,10 +103,52 @@ impl EprRegister {
         epr_pair_id
     }
 
+    /// Return the identifier of the EPR pair to which a local pair belongs.
+    fn resolve(&self, local_pair_id: u64) -> u64 {
+        self.aliases
+            .get(&local_pair_id)
+            .copied()
+            .unwrap_or(local_pair_id)
+    }
+
+    /// Depolarize an EPR pair with given ID, e.g., because of a noisy
+    /// operation on one of its qubits. Do nothing if it does not exist.
+    pub fn depolarize(&mut self, epr_pair_id: u64, operation_fidelity: f64) {
+        let epr_pair_id = self.resolve(epr_pair_id);
+        if let Some(epr_pair) = self.epr_pairs.get_mut(&epr_pair_id) {
+            epr_pair.fidelity = 0.25 + operation_fidelity * (epr_pair.fidelity - 0.25);
+        }
+    }
+
+    /// Return the outcome of the measurement of a qubit of an EPR pair with
+    /// given ID, if it exists and one of its qubits was measured.
+    pub fn outcome(&self, epr_pair_id: u64) -> Option<crate::event::MeasureOutcome> {
+        self.epr_pairs
+            .get(&self.resolve(epr_pair_id))
+            .and_then(|epr_pair| epr_pair.outcome)
+    }
+
+    /// Record the outcome of the measurement of a qubit of an EPR pair with
+    /// given ID. Do nothing if it does not exist.
+    pub fn set_outcome(&mut self, epr_pair_id: u64, outcome: crate::event::MeasureOutcome) {
+        let epr_pair_id = self.resolve(epr_pair_id);
+        if let Some(epr_pair) = self.epr_pairs.get_mut(&epr_pair_id) {
+            epr_pair.outcome = Some(outcome);
+        }
+    }
+
     /// Consume an EPR pair with given ID at a node.
     /// Remove the EPR pair if both end-points consumed it.
+    ///
+    /// The ID is the local pair identifier of the qubit held by the node,
+    /// which differs from that of the EPR pair after an entanglement swapping.
     pub fn consume(&mut self, epr_pair_id: u64, node_id: u32) -> Option<(u64, f64)> {
-        let epr_pair = self.epr_pairs.get_mut(&epr_pair_id);
+        let local_pair_id = epr_pair_id;
+        let epr_pair_id = self.resolve(local_pair_id);
+        let epr_pair = self
+            .epr_pairs
+            .get_mut(&epr_pair_id)
+            .filter(|epr_pair| epr_pair.local_pair_id(node_id) == Some(local_pair_id));
         let ret = if let Some(epr_pair) = epr_pair {
             epr_pair.consume(node_id)
         } else {
@@ -80,6 +156,7 @@ impl EprRegister {
         };
 
         if let Some((updated, fidelity, remove)) = ret {
+            self.aliases.remove(&local_pair_id);
             if remove {
                 self.epr_pairs.remove(&epr_pair_id);
             }
@@ -88,6 +165,83 @@ impl EprRegister {
             None
         }
     }
+
+    /// Remove an EPR pair with given ID, e.g., because one of its qubits
+    /// was lost. Return the nodes that have not consumed it yet, with the
+    /// local pair identifiers of their qubits.
+    pub fn remove(&mut self, epr_pair_id: u64) -> Vec<(u32, u64)> {
+        let holders: Vec<(u32, u64)> = self
+            .epr_pairs
+            .remove(&self.resolve(epr_pair_id))
+            .map(|epr_pair| {
+                epr_pair
+                    .alice_id
+                    .map(|alice_id| (alice_id, epr_pair.alice_pair_id))
+                    .into_iter()
+                    .chain(epr_pair.bob_id.map(|bob_id| (bob_id, epr_pair.bob_pair_id)))
+                    .collect()
+            })
+            .unwrap_or_default();
+        for (_, local_pair_id) in &holders {
+            self.aliases.remove(local_pair_id);
+        }
+        holders
+    }
+
+    /// Return the time when the fidelity of an EPR pair with given ID was
+    /// last updated, and its fidelity at that time, if it exists.
+    pub fn fidelity(&self, epr_pair_id: u64) -> Option<(u64, f64)> {
+        self.epr_pairs
+            .get(&self.resolve(epr_pair_id))
+            .map(|epr_pair| (epr_pair.updated, epr_pair.fidelity))
+    }
+
+    /// Swap two EPR pairs with given IDs at a node, which consumes its qubits
+    /// of both. They are joined into one EPR pair between their other
+    /// end-points, which keeps the ID of the first one, with the given
+    /// fidelity at `updated` time.
+    ///
+    /// If either EPR pair has been already consumed at its other end-point,
+    /// then the other one is left as is, with its remaining qubit only.
+    pub fn swap(
+        &mut self,
+        node_id: u32,
+        first_id: u64,
+        second_id: u64,
+        updated: u64,
+        fidelity: f64,
+    ) {
+        let first_root = self.resolve(first_id);
+        let second_root = self.resolve(second_id);
+        if self.consume(first_id, node_id).is_none() || self.consume(second_id, node_id).is_none() {
+            return;
+        }
+        let Some((bob_id, bob_pair_id)) = self
+            .epr_pairs
+            .get(&second_root)
+            .and_then(|epr_pair| epr_pair.remaining())
+        else {
+            return;
+        };
+        let Some(epr_pair) = self
+            .epr_pairs
+            .get_mut(&first_root)
+            .filter(|epr_pair| epr_pair.remaining().is_some())
+        else {
+            return;
+        };
+        if epr_pair.alice_id.is_none() {
+            epr_pair.alice_id = Some(bob_id);
+            epr_pair.alice_pair_id = bob_pair_id;
+        } else {
+            epr_pair.bob_id = Some(bob_id);
+            epr_pair.bob_pair_id = bob_pair_id;
+        }
+        epr_pair.updated = updated;
+        epr_pair.fidelity = fidelity;
+        self.epr_pairs.remove(&second_root);
+        self.aliases.insert(bob_pair_id, first_root);
+    }
 }
 
 #[cfg(test)]
@@ -99,8 +253,11 @@ mod tests {
         let mut epr_pair = EprPair {
             alice_id: Some(1),
             bob_id: Some(2),
+            alice_pair_id: 0,
+            bob_pair_id: 0,
             updated: 999,
             fidelity: 0.5,
+            outcome: None,
         };
 
         assert!(epr_pair.consume(42).is_none());
@@ -134,9 +291,11 @@ mod tests {
         assert_eq!(990, updated);
         assert_float_eq::assert_f64_near!(0.42, fidelity);
 
+        register.depolarize(0, 0.5);
+        register.depolarize(1, 0.5);
         let (updated, fidelity) = register.consume(0, 2).unwrap();
         assert_eq!(990, updated);
-        assert_float_eq::assert_f64_near!(0.42, fidelity);
+        assert_float_eq::assert_f64_near!(0.335, fidelity);
 
         assert!(register.consume(0, 1).is_none());
         assert!(register.consume(0, 2).is_none());
@@ -169,4 +328,57 @@ mod tests {
         assert!(register.consume(0, 1).is_none());
         assert!(register.consume(99, 1).is_none());
     }
+
+    #[test]
+    fn test_epr_pair_register_remove() {
+        let mut register = EprRegister::default();
+        assert_eq!(0, register.new_epr_pair(1, 2, 990, 0.42));
+        assert_eq!(1, register.new_epr_pair(1, 2, 990, 0.42));
+
+        assert!(register.remove(99).is_empty());
+
+        assert_eq!(vec![(1, 0), (2, 0)], register.remove(0));
+        assert!(register.consume(0, 1).is_none());
+
+        assert!(register.consume(1, 1).is_some());
+        assert_eq!(vec![(2, 1)], register.remove(1));
+        assert!(register.consume(1, 2).is_none());
+        assert!(register.remove(1).is_empty());
+    }
+
+    #[test]
+    fn test_epr_pair_register_swap() {
+        let mut register = EprRegister::default();
+        assert_eq!(0, register.new_epr_pair(1, 2, 990, 0.9));
+        assert_eq!(1, register.new_epr_pair(2, 3, 995, 0.8));
+        assert_eq!(2, register.new_epr_pair(3, 4, 995, 0.8));
+
+        // Node 2 swaps the pairs shared with nodes 1 and 3.
+        register.swap(2, 0, 1, 1000, 0.7);
+        assert_eq!(Some((1000, 0.7)), register.fidelity(1));
+        assert!(register.consume(1, 2).is_none());
+
+        // Node 3 swaps the resulting pair with that shared with node 4.
+        register.swap(3, 1, 2, 1010, 0.6);
+        assert_eq!(1, register.epr_pairs.len());
+        assert!(register.consume(1, 3).is_none());
+
+        // The end-points consume the same pair with their local identifiers.
+        let (updated, fidelity) = register.consume(2, 4).unwrap();
+        assert_eq!(1010, updated);
+        assert_float_eq::assert_f64_near!(0.6, fidelity);
+        assert_eq!(vec![(1, 0)], register.remove(0));
+        assert!(register.epr_pairs.is_empty());
+        assert!(register.aliases.is_empty());
+
+        // A pair discarded at the other end-point is not swapped.
+        assert_eq!(3, register.new_epr_pair(1, 2, 990, 0.9));
+        assert_eq!(4, register.new_epr_pair(2, 3, 995, 0.8));
+        assert!(register.consume(3, 1).is_some());
+        register.swap(2, 3, 4, 1000, 0.7);
+        assert!(register.fidelity(3).is_none());
+        assert_eq!(Some((995, 0.8)), register.fidelity(4));
+        assert_eq!(vec![(3, 4)], register.remove(4));
+        assert!(register.aliases.is_empty());
+    }
 }
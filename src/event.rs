@@ -18,6 +18,15 @@ pub struct EprNotifiedData {
     pub epr_pair_id: u64,
 }
 
+#[derive(Debug, PartialEq, Eq)]
+pub struct EprBatchNotifiedData {
+    pub this_node_id: u32,
+    pub peer_node_id: u32,
+    pub role: crate::nic::Role,
+    /// Identifiers of the EPR pairs generated in the same batch.
+    pub epr_pair_ids: Vec<u64>,
+}
+
 /// Quantum memory cell identifier.
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct MemoryCellId {
@@ -29,6 +38,14 @@ pub struct MemoryCellId {
     pub local_pair_id: u64,
 }
 
+/// Minimum fidelity required for an EPR pair.
+///
+/// The value is always finite, hence it can be compared for equality.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct MinFidelity(pub f64);
+
+impl Eq for MinFidelity {}
+
 #[derive(Debug, PartialEq, Eq)]
 pub struct EprConsumeData {
     /// ID of the node running the application that requested the EPR.
@@ -40,6 +57,70 @@ pub struct EprConsumeData {
     pub consume_node_id: u32,
     /// Quantum memory cell identifier.
     pub memory_cell_id: MemoryCellId,
+    /// EPR request served, used to notify both the applications if the
+    /// qubit cannot be read from the memory, and to report if the EPR
+    /// consumed met its minimum fidelity, and the fidelity of its traffic
+    /// class.
+    pub epr: EprFiveTuple,
+}
+
+/// Half EPR pair discarded by a node without being consumed.
+#[derive(Debug, PartialEq, Eq)]
+pub struct EprDiscardData {
+    /// ID of the node that discards the EPR.
+    pub node_id: u32,
+    /// Identifier of the EPR pair.
+    pub epr_pair_id: u64,
+}
+
+/// Entanglement swapping of the half EPR pairs held by a node towards the
+/// previous and next hops, which are joined into one EPR pair.
+#[derive(Debug, PartialEq, Eq)]
+pub struct EprSwapData {
+    /// ID of the node that swaps the EPR pairs.
+    pub node_id: u32,
+    /// Memory cell holding the half EPR pair shared with the previous hop.
+    pub prev_memory_cell_id: MemoryCellId,
+    /// Memory cell holding the half EPR pair shared with the next hop.
+    pub next_memory_cell_id: MemoryCellId,
+}
+
+/// Measurement of an EPR pair on a memoryless logical link.
+#[derive(Debug, PartialEq, Eq)]
+pub struct EprMeasureData {
+    /// ID of the node running the application that measures the EPR.
+    pub req_app_node_id: u32,
+    /// Port of the application that measures the EPR.
+    pub req_app_port: u16,
+    /// ID of the node that measures the EPR.
+    pub measure_node_id: u32,
+    /// Identifier of the EPR pair.
+    pub epr_pair_id: u64,
+    /// Response notified to the application with the outcome of the
+    /// measurement, for measure-directly requests.
+    pub response: Option<EprResponseData>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct FaultData {
+    /// Element that fails or is repaired.
+    pub target: crate::faults::FaultTarget,
+    /// Index of the random failure process, None for scripted outages.
+    pub process: Option<usize>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct TransmitterData {
+    /// ID of the node whose transmitter is time-shared.
+    pub tx_node_id: u32,
+    /// True at the start of a slot, false at its end.
+    pub start: bool,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct WeatherData {
+    /// ID of the OGS whose weather changes.
+    pub ogs_node_id: u32,
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -48,14 +129,98 @@ pub enum NetworkEventData {
     EprGenerated(EprGeneratedData),
     /// EPR pair notified at a node.
     EprNotified(EprNotifiedData),
+    /// Batch of EPR pairs generated by a multiplexed tx notified at a node.
+    EprBatchNotified(EprBatchNotifiedData),
+    /// Half EPR pair transferred from a communication qubit into storage.
+    EprStored(EprNotifiedData),
     /// Consume a half EPR pair and measure its fidelity.
     EprConsume(EprConsumeData),
+    /// Discard a half EPR pair, e.g., because the request failed.
+    EprDiscard(EprDiscardData),
+    /// Swap two half EPR pairs at an intermediate node of a path.
+    EprSwap(EprSwapData),
+    /// Half EPR pair detected at a memoryless logical link, in coincidence
+    /// with the other half.
+    EprDetected(EprNotifiedData),
+    /// Measure a half EPR pair detected at a memoryless logical link.
+    EprMeasure(EprMeasureData),
+    /// An element of the network fails.
+    Failure(FaultData),
+    /// An element of the network is repaired.
+    Repair(FaultData),
+    /// The weather changes over an OGS.
+    WeatherChange(WeatherData),
+    /// A time-shared transmitter starts or ends serving a logical link.
+    TransmitterSlot(TransmitterData),
+}
+
+/// Basis in which the qubits of an EPR pair are measured.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum Basis {
+    #[default]
+    Z,
+    X,
+    /// Z or X drawn at random, independently at the two ends, as in BB84.
+    Random,
+}
+
+/// Type of an EPR request, as in the service definitions of the link layer
+/// of the quantum internet.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum RequestType {
+    /// The EPR pair is kept in memory until consumed by the applications.
+    #[default]
+    CreateAndKeep,
+    /// The qubits are measured in the given basis as soon as the EPR pair is
+    /// established, hence the memory is released immediately and the
+    /// applications only receive the outcomes.
+    MeasureDirectly(Basis),
+}
+
+/// Delivery of the EPR pairs of a request for multiple pairs to the
+/// applications.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum Delivery {
+    /// Every EPR pair is delivered as soon as it is established.
+    #[default]
+    Sequential,
+    /// The EPR pairs are delivered together when all of them are established.
+    AllAtOnce,
+    /// The EPR pairs are delivered together when at least the given number
+    /// of them is established, then the others are delivered sequentially.
+    AtLeast(u32),
+}
+
+impl Delivery {
+    /// Return the number of EPR pairs that must be established before the
+    /// first one is delivered, given the number of pairs requested.
+    pub fn threshold(&self, number_of_pairs: u32) -> u32 {
+        match self {
+            Self::Sequential => 1,
+            Self::AllAtOnce => number_of_pairs,
+            Self::AtLeast(min_pairs) => (*min_pairs).clamp(1, number_of_pairs.max(1)),
+        }
+    }
+}
+
+/// Outcome of the measurement of a qubit of an EPR pair.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MeasureOutcome {
+    /// Basis of the measurement, never `Random`.
+    pub basis: Basis,
+    /// Value measured.
+    pub bit: bool,
 }
 
 /// Every EPR request is uniquely identified by the five-tuple:
 /// - source node ID and port
 /// - target node ID and port
 /// - request ID
+///
+/// The request also carries its type and the number of EPR pairs requested,
+/// with the index of every pair assigned by the source node, the limits
+/// after which the request fails, the minimum fidelity required, and the
+/// traffic class of the application.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct EprFiveTuple {
     /// Source node ID.
@@ -68,9 +233,28 @@ pub struct EprFiveTuple {
     pub target_port: u16,
     /// Request ID
     pub request_id: u64,
+    /// Request type.
+    pub request_type: RequestType,
+    /// Number of EPR pairs requested.
+    pub number_of_pairs: u32,
+    /// Delivery of the EPR pairs, if more than one is requested.
+    pub delivery: Delivery,
+    /// Index of the EPR pair, from 0 to `number_of_pairs` - 1.
+    pub index: u32,
+    /// Maximum time to serve the request since it is received by the source
+    /// node, in ns. None means no deadline.
+    pub timeout: Option<u64>,
+    /// Maximum number of times that an EPR pair is requested again after a
+    /// failure. None means unlimited.
+    pub max_retries: Option<u32>,
+    /// Minimum end-to-end fidelity of the EPR pairs. None means any.
+    pub min_fidelity: Option<MinFidelity>,
+    /// Traffic class of the application, 0 having the highest priority.
+    pub class: u32,
 }
 
 impl EprFiveTuple {
+    /// Create a create-and-keep request for a single EPR pair.
     pub fn new(
         source_node_id: u32,
         source_port: u16,
@@ -84,6 +268,14 @@ impl EprFiveTuple {
             target_node_id,
             target_port,
             request_id,
+            request_type: RequestType::CreateAndKeep,
+            number_of_pairs: 1,
+            delivery: Delivery::Sequential,
+            index: 0,
+            timeout: None,
+            max_retries: None,
+            min_fidelity: None,
+            class: 0,
         }
     }
 }
@@ -98,7 +290,11 @@ impl std::fmt::Display for EprFiveTuple {
             self.target_node_id,
             self.target_port,
             self.request_id
-        )
+        )?;
+        if self.number_of_pairs > 1 {
+            write!(f, " pair {}/{}", self.index, self.number_of_pairs)?;
+        }
+        Ok(())
     }
 }
 
@@ -120,6 +316,39 @@ pub struct EsRequestData {
     pub path: Vec<u32>,
     /// Local pair identifier.
     pub local_pair_id: u64,
+    /// Time when the request was received at the source node, used to
+    /// schedule it at the intermediate nodes.
+    pub received: u64,
+    /// True if the EPR pair has been reserved in advance, in which case it
+    /// is served first at the intermediate nodes, too.
+    pub reserved: bool,
+}
+
+/// Entanglement swapping abort body.
+#[derive(Debug, PartialEq, Eq)]
+pub struct EsAbortData {
+    /// EPR identifier.
+    pub epr: EprFiveTuple,
+    /// Node along the path that has to abort the entanglement swapping.
+    pub node_id: u32,
+    /// Previous hop of the node in the path.
+    pub prev_hop: u32,
+    /// Full path of the ES.
+    pub path: Vec<u32>,
+    /// True if the ES has not gone past the previous hop, in which case the
+    /// target node has not received it.
+    pub pending: bool,
+}
+
+/// Advance reservation of EPR pairs for a future time window.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReservationData {
+    /// Five-tuple of the EPR request, with the number of EPR pairs booked.
+    pub epr: EprFiveTuple,
+    /// Start of the time window, in ns.
+    pub start: u64,
+    /// End of the time window, in ns.
+    pub end: u64,
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -132,6 +361,12 @@ pub enum NodeEventData {
     /// Created by a node, consumed by the logical peer that is the next hop
     /// towards the destination.
     EsRequest(EsRequestData),
+    /// Qubit of an EPR pair retrieved from the memory for an entanglement
+    /// swapping.
+    /// Created by a node when an ES request is received, consumed by the
+    /// same node after the read latency of its memory, when the local
+    /// operation (BSM or X/Z correction) is submitted.
+    EsMemoryRead(EsRequestData),
     /// Local operations completed for an entanglement swapping.
     /// Created by a node when a local operation (BSM or X/Z correction) is
     /// required, consumed by the same node when the operation is completed
@@ -153,29 +388,70 @@ pub enum NodeEventData {
     /// Entanglement swapping operation completed successfully.
     /// Created by the destination node. Consumed by the source node.
     EsRemoteComplete(EprFiveTuple),
+    /// The request of an EPR pair failed at the source node.
+    /// Created by the source node, consumed by the other nodes along the
+    /// path, one hop at a time, which free the resources held for the EPR
+    /// pair.
+    EsAbort(EsAbortData),
+    /// The deadline of an EPR request expires.
+    /// Created and consumed by the source node.
+    EprRequestTimeout(EprFiveTuple),
+    /// New advance reservation requested by an app.
+    /// Created by an application, consumed by the node where it is running.
+    EprReservationApp(ReservationData),
+    /// The time window of an advance reservation starts.
+    /// Created and consumed by the source node.
+    ReservationStart(ReservationData),
+    /// The time window of an advance reservation ends.
+    /// Created and consumed by the source node.
+    ReservationEnd(ReservationData),
 }
 
 impl NodeEventData {
     pub fn node_id(&self) -> u32 {
         match self {
             NodeEventData::EprRequestApp(data)
+            | NodeEventData::EprRequestTimeout(data)
             | NodeEventData::EsRemoteComplete(data)
             | NodeEventData::EsRemoteFailed(data) => data.source_node_id,
-            NodeEventData::EsRequest(data) | NodeEventData::EsLocalComplete(data) => data.next_hop,
+            NodeEventData::EprReservationApp(data)
+            | NodeEventData::ReservationStart(data)
+            | NodeEventData::ReservationEnd(data) => data.epr.source_node_id,
+            NodeEventData::EsRequest(data)
+            | NodeEventData::EsMemoryRead(data)
+            | NodeEventData::EsLocalComplete(data) => data.next_hop,
             NodeEventData::EsSuccess(data) | NodeEventData::EsFailure(data) => data.prev_hop,
+            NodeEventData::EsAbort(data) => data.node_id,
         }
     }
 }
 
 #[derive(Debug, PartialEq, Eq)]
 pub struct EprResponseData {
-    /// Five-tuple associated with this EPR.
+    /// Five-tuple associated with this EPR, which also carries the index
+    /// of the EPR pair delivered within the request.
     pub epr: EprFiveTuple,
     /// The boolean is true if the EPR response is associated with the source
     /// node; it is false if it is associated with the target, instead.
     pub is_source: bool,
-    /// Memory cell ID. If None then the request failed.
+    /// Memory cell ID. If None then the request failed, unless there is
+    /// an outcome.
     pub memory_cell: Option<MemoryCellId>,
+    /// Outcome of the measurement of the local qubit, for
+    /// measure-directly requests.
+    pub outcome: Option<MeasureOutcome>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct EprMeasuredData {
+    /// ID of the node running the application.
+    pub this_node_id: u32,
+    /// Port of the application.
+    pub this_port: u16,
+    /// ID of the peer node of the memoryless logical link.
+    pub peer_node_id: u32,
+    /// Identifier of the EPR pair.
+    pub epr_pair_id: u64,
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -188,12 +464,29 @@ pub enum AppEventData {
     /// (is_source == true) and by its peer (is_source == false).
     EprResponse(EprResponseData),
     /// Local operations complete for a given EPR request.
+    /// The boolean is true if the operations are done by the application
+    /// that requested the EPR, false if by its peer.
     /// Created by an application, consumed by the same application.
-    LocalComplete(EprFiveTuple),
+    LocalComplete(EprFiveTuple, bool),
     /// Remote operations complete for a given EPR request.
     /// The boolean is true if the network latency has to be added.
     /// /// Created by an application, consumed by its peer.
     RemoteComplete(EprFiveTuple),
+    /// EPR pair lost because one of its qubits could not be read from the
+    /// memory.
+    /// The boolean is true if addressed to the application that requested
+    /// the EPR, false if to its peer.
+    /// Created by the network, consumed by both the applications.
+    EprLost(EprFiveTuple, bool),
+    /// Response to an advance reservation, true if accepted.
+    /// Created by a node, consumed by the application that requested the
+    /// reservation.
+    ReservationResponse(ReservationData, bool),
+    /// Half EPR pair detected on a memoryless logical link, which must be
+    /// measured immediately.
+    /// Created by a node, consumed by the application that measures the EPR
+    /// pairs of the logical link.
+    EprMeasured(EprMeasuredData),
 }
 
 impl AppEventData {
@@ -207,8 +500,23 @@ impl AppEventData {
                     data.epr.target_node_id
                 }
             }
-            Self::LocalComplete(data) => data.source_node_id,
+            Self::LocalComplete(data, is_source) => {
+                if *is_source {
+                    data.source_node_id
+                } else {
+                    data.target_node_id
+                }
+            }
             Self::RemoteComplete(data) => data.source_node_id,
+            Self::EprLost(data, is_source) => {
+                if *is_source {
+                    data.source_node_id
+                } else {
+                    data.target_node_id
+                }
+            }
+            Self::ReservationResponse(data, _accepted) => data.epr.source_node_id,
+            Self::EprMeasured(data) => data.this_node_id,
         }
     }
 
@@ -222,7 +530,23 @@ impl AppEventData {
                     data.epr.target_port
                 }
             }
-            Self::LocalComplete(data) | Self::RemoteComplete(data) => data.source_port,
+            Self::LocalComplete(data, is_source) => {
+                if *is_source {
+                    data.source_port
+                } else {
+                    data.target_port
+                }
+            }
+            Self::RemoteComplete(data) => data.source_port,
+            Self::EprLost(data, is_source) => {
+                if *is_source {
+                    data.source_port
+                } else {
+                    data.target_port
+                }
+            }
+            Self::ReservationResponse(data, _accepted) => data.epr.source_port,
+            Self::EprMeasured(data) => data.this_port,
         }
     }
 }
@@ -249,6 +573,12 @@ pub struct Trasfer {
     pub src_node_id: u32,
     pub dst_node_id: u32,
     pub done: bool,
+    /// Node where the message currently is, if the classical network is
+    /// simulated hop by hop.
+    pub hop: u32,
+    /// Time when the message has been sent, in ns, if the classical network
+    /// is simulated hop by hop.
+    pub sent: Option<u64>,
 }
 
 impl Trasfer {
@@ -257,6 +587,8 @@ impl Trasfer {
             src_node_id,
             dst_node_id,
             done: false,
+            hop: src_node_id,
+            sent: None,
         }
     }
 }
@@ -5,8 +5,11 @@ use crate::event::Event;
 
 #[derive(Default)]
 pub struct EventQueue {
-    queue: std::collections::BinaryHeap<Event>,
+    /// Events with the same time are handled in the order they were added,
+    /// through the reverse of a sequence number.
+    queue: std::collections::BinaryHeap<(Event, std::cmp::Reverse<u64>)>,
     last_time: u64,
+    next_seq: u64,
 }
 
 impl EventQueue {
@@ -14,12 +17,13 @@ impl EventQueue {
     pub fn push(&mut self, event: Event) {
         let mut event = event;
         event.advance(self.last_time);
-        self.queue.push(event);
+        self.queue.push((event, std::cmp::Reverse(self.next_seq)));
+        self.next_seq += 1;
     }
 
     /// Return the next event, if any.
     pub fn pop(&mut self) -> Option<Event> {
-        let last_event = self.queue.pop();
+        let last_event = self.queue.pop().map(|(event, _seq)| event);
         if let Some(event) = &last_event {
             self.last_time = event.time();
         }
@@ -38,3 +42,32 @@ impl EventQueue {
         self.queue.is_empty()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::EventQueue;
+    use crate::event::{Event, EventType};
+
+    #[test]
+    fn test_event_queue_same_time() {
+        let mut queue = EventQueue::default();
+        queue.push(Event::new(1.0, EventType::ExperimentEnd));
+        queue.push(Event::new(1.0, EventType::WarmupPeriodEnd));
+        queue.push(Event::new(0.5, EventType::ExperimentEnd));
+        queue.push(Event::new(1.0, EventType::ExperimentEnd));
+
+        let mut popped = vec![];
+        while let Some(event) = queue.pop() {
+            popped.push((event.time(), event.event_type));
+        }
+        assert_eq!(
+            vec![
+                (500_000_000, EventType::ExperimentEnd),
+                (1_000_000_000, EventType::ExperimentEnd),
+                (1_000_000_000, EventType::WarmupPeriodEnd),
+                (1_000_000_000, EventType::ExperimentEnd),
+            ],
+            popped
+        );
+    }
+}
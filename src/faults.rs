@@ -0,0 +1,441 @@
+// SPDX-FileCopyrightText: © 2025 Claudio Cicconetti <c.cicconetti@iit.cnr.it>
+// SPDX-License-Identifier: MIT
+
+use petgraph::visit::EdgeRef;
+use rand::SeedableRng;
+use rand_distr::Distribution;
+
+use crate::event::{Event, EventType, FaultData, NetworkEventData};
+
+/// Element of the network that can fail.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub enum FaultTarget {
+    /// The whole node: it stops generating and receiving EPR pairs and
+    /// loses the content of all its memory cells.
+    Node(u32),
+    /// The transmitter of a node: the EPR pairs generated by this node
+    /// are interrupted, but the memory cells are not affected.
+    Transmitter(u32),
+    /// The logical link from a master to a slave node.
+    LogicalLink(u32, u32),
+}
+
+impl std::fmt::Display for FaultTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FaultTarget::Node(node_id) => write!(f, "node {}", node_id),
+            FaultTarget::Transmitter(node_id) => write!(f, "tx {}", node_id),
+            FaultTarget::LogicalLink(master, slave) => write!(f, "link {}->{}", master, slave),
+        }
+    }
+}
+
+/// Set of elements subject to random failures.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub enum FaultScope {
+    /// All the nodes.
+    Nodes,
+    /// All the nodes of a given type.
+    NodesOfType(crate::physical_topology::NodeType),
+    /// All the nodes that generate EPR pairs in the logical topology.
+    Transmitters,
+    /// All the links of the logical topology.
+    LogicalLinks,
+}
+
+/// Distribution of a random duration, in s.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub enum DurationDistribution {
+    /// Deterministic value.
+    Constant(f64),
+    /// Exponential distribution with given mean.
+    Exponential(f64),
+    /// Uniform distribution between a minimum and a maximum.
+    Uniform(f64, f64),
+    /// Weibull distribution with given scale and shape.
+    Weibull { scale: f64, shape: f64 },
+}
+
+impl DurationDistribution {
+    fn valid(&self) -> anyhow::Result<()> {
+        match self {
+            DurationDistribution::Constant(value) => {
+                anyhow::ensure!(*value > 0.0, "invalid constant duration: {} <= 0", value)
+            }
+            DurationDistribution::Exponential(mean) => {
+                anyhow::ensure!(*mean > 0.0, "invalid exponential mean: {} <= 0", mean)
+            }
+            DurationDistribution::Uniform(min, max) => anyhow::ensure!(
+                *min >= 0.0 && *min < *max,
+                "invalid uniform range: [{}, {}]",
+                min,
+                max
+            ),
+            DurationDistribution::Weibull { scale, shape } => anyhow::ensure!(
+                *scale > 0.0 && *shape > 0.0,
+                "invalid Weibull scale {} or shape {}",
+                scale,
+                shape
+            ),
+        }
+        Ok(())
+    }
+
+    /// Draw a random duration, in s.
+    pub fn sample(&self, rng: &mut rand::rngs::StdRng) -> f64 {
+        match self {
+            DurationDistribution::Constant(value) => *value,
+            DurationDistribution::Exponential(mean) => rand_distr::Exp::new(1.0 / mean)
+                .expect("could not create an expo rv")
+                .sample(rng),
+            DurationDistribution::Uniform(min, max) => {
+                rand_distr::Uniform::new(min, max).sample(rng)
+            }
+            DurationDistribution::Weibull { scale, shape } => {
+                rand_distr::Weibull::new(*scale, *shape)
+                    .expect("could not create a Weibull rv")
+                    .sample(rng)
+            }
+        }
+    }
+}
+
+/// Random failures of all the elements in a scope, each alternating
+/// independently between up and down periods.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct RandomFaults {
+    /// Elements that can fail.
+    pub scope: FaultScope,
+    /// Time to failure of an element, i.e., the MTBF is its mean.
+    pub time_to_failure: DurationDistribution,
+    /// Time to repair of an element, i.e., the MTTR is its mean.
+    pub time_to_repair: DurationDistribution,
+}
+
+/// Outage of a single element at a fixed time.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ScriptedFault {
+    /// Element that fails.
+    pub target: FaultTarget,
+    /// Time when the element fails, in s.
+    pub start: f64,
+    /// Duration of the outage, in s.
+    pub duration: f64,
+}
+
+/// Alternating up/down process of a single element.
+#[derive(Debug)]
+struct Process {
+    target: FaultTarget,
+    time_to_failure: DurationDistribution,
+    time_to_repair: DurationDistribution,
+}
+
+/// Generator of failure and repair events, which also keeps track of the
+/// elements that are currently down.
+///
+/// An element can be subject to multiple overlapping outages, e.g., from
+/// a scripted fault and a random process: it is down until all of them
+/// are repaired.
+#[derive(Debug)]
+pub struct FaultInjector {
+    /// Random failure processes.
+    processes: Vec<Process>,
+    /// Scripted outages.
+    scripted: Vec<ScriptedFault>,
+    /// Elements currently down, with the number of overlapping outages and
+    /// the time when they went down, in ns.
+    down: std::collections::HashMap<FaultTarget, (u32, u64)>,
+    /// Pseudo-random number generator.
+    rng: rand::rngs::StdRng,
+}
+
+impl Default for FaultInjector {
+    /// Return a fault injector without failures.
+    fn default() -> Self {
+        Self {
+            processes: vec![],
+            scripted: vec![],
+            down: std::collections::HashMap::new(),
+            rng: rand::rngs::StdRng::seed_from_u64(0),
+        }
+    }
+}
+
+impl FaultInjector {
+    /// Create a fault injector, where the scopes of random failures are
+    /// expanded to the individual elements of the given topologies.
+    pub fn new(
+        random: &[RandomFaults],
+        scripted: &[ScriptedFault],
+        physical_topology: &crate::physical_topology::PhysicalTopology,
+        logical_topology: &crate::logical_topology::LogicalTopology,
+        seed: u64,
+    ) -> anyhow::Result<Self> {
+        let num_nodes = physical_topology.graph().node_count() as u32;
+        let logical_graph = logical_topology.graph();
+        let transmitters = logical_graph
+            .edge_weights()
+            .map(|w| w.tx)
+            .collect::<std::collections::BTreeSet<u32>>();
+        let links = logical_graph
+            .edge_references()
+            .map(|e| FaultTarget::LogicalLink(e.source().index() as u32, e.target().index() as u32))
+            .collect::<Vec<_>>();
+
+        let mut processes = vec![];
+        for faults in random {
+            faults.time_to_failure.valid()?;
+            faults.time_to_repair.valid()?;
+            let targets = match &faults.scope {
+                FaultScope::Nodes => (0..num_nodes).map(FaultTarget::Node).collect(),
+                FaultScope::NodesOfType(node_type) => physical_topology
+                    .graph()
+                    .node_weights()
+                    .enumerate()
+                    .filter(|(_, w)| w.node_type == *node_type)
+                    .map(|(ndx, _)| FaultTarget::Node(ndx as u32))
+                    .collect(),
+                FaultScope::Transmitters => transmitters
+                    .iter()
+                    .map(|tx| FaultTarget::Transmitter(*tx))
+                    .collect(),
+                FaultScope::LogicalLinks => links.clone(),
+            };
+            for target in targets {
+                processes.push(Process {
+                    target,
+                    time_to_failure: faults.time_to_failure.clone(),
+                    time_to_repair: faults.time_to_repair.clone(),
+                });
+            }
+        }
+
+        for fault in scripted {
+            match fault.target {
+                FaultTarget::Node(node_id) | FaultTarget::Transmitter(node_id) => {
+                    anyhow::ensure!(
+                        node_id < num_nodes,
+                        "invalid {} in scripted fault, there are only {} nodes",
+                        fault.target,
+                        num_nodes
+                    )
+                }
+                FaultTarget::LogicalLink(master, slave) => anyhow::ensure!(
+                    logical_graph
+                        .find_edge(master.into(), slave.into())
+                        .is_some(),
+                    "invalid {} in scripted fault, not in the logical topology",
+                    fault.target
+                ),
+            }
+            anyhow::ensure!(
+                fault.start >= 0.0 && fault.duration > 0.0,
+                "invalid scripted fault of {} at {} s for {} s",
+                fault.target,
+                fault.start,
+                fault.duration
+            );
+        }
+
+        Ok(Self {
+            processes,
+            scripted: scripted.to_vec(),
+            down: std::collections::HashMap::new(),
+            rng: rand::rngs::StdRng::seed_from_u64(seed),
+        })
+    }
+
+    /// Return the initial failure and repair events.
+    pub fn initial(&mut self) -> Vec<Event> {
+        let mut events = vec![];
+        for process in 0..self.processes.len() {
+            events.push(self.next_failure(process));
+        }
+        for fault in &self.scripted {
+            let data = || FaultData {
+                target: fault.target,
+                process: None,
+            };
+            events.push(Event::new(
+                fault.start,
+                EventType::NetworkEvent(NetworkEventData::Failure(data())),
+            ));
+            events.push(Event::new(
+                fault.start + fault.duration,
+                EventType::NetworkEvent(NetworkEventData::Repair(data())),
+            ));
+        }
+        events
+    }
+
+    /// Record the failure of an element.
+    ///
+    /// Return true if the element was up before, and the repair event for
+    /// random failures.
+    pub fn fail(&mut self, now: u64, data: &FaultData) -> (bool, Vec<Event>) {
+        let entry = self.down.entry(data.target).or_insert((0, now));
+        entry.0 += 1;
+        let went_down = entry.0 == 1;
+        let events = match data.process {
+            Some(process) => vec![Event::new(
+                self.processes[process].time_to_repair.sample(&mut self.rng),
+                EventType::NetworkEvent(NetworkEventData::Repair(FaultData {
+                    target: data.target,
+                    process: Some(process),
+                })),
+            )],
+            None => vec![],
+        };
+        (went_down, events)
+    }
+
+    /// Record the repair of an element.
+    ///
+    /// Return how long the element has been down, in ns, if it is up now,
+    /// and the next failure event for random failures.
+    pub fn repair(&mut self, now: u64, data: &FaultData) -> (Option<u64>, Vec<Event>) {
+        let entry = self
+            .down
+            .get_mut(&data.target)
+            .unwrap_or_else(|| panic!("repair of {} that is not down", data.target));
+        entry.0 -= 1;
+        let downtime = if entry.0 == 0 {
+            let since = entry.1;
+            self.down.remove(&data.target);
+            Some(now - since)
+        } else {
+            None
+        };
+        let events = match data.process {
+            Some(process) => vec![self.next_failure(process)],
+            None => vec![],
+        };
+        (downtime, events)
+    }
+
+    /// Return true if the element is currently down.
+    pub fn is_down(&self, target: &FaultTarget) -> bool {
+        self.down.contains_key(target)
+    }
+
+    fn next_failure(&mut self, process: usize) -> Event {
+        Event::new(
+            self.processes[process]
+                .time_to_failure
+                .sample(&mut self.rng),
+            EventType::NetworkEvent(NetworkEventData::Failure(FaultData {
+                target: self.processes[process].target,
+                process: Some(process),
+            })),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+
+    use crate::event::{EventType, FaultData, NetworkEventData};
+
+    use super::{DurationDistribution, FaultInjector, FaultScope, FaultTarget, RandomFaults};
+
+    #[test]
+    fn test_faults_duration_distribution() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(42);
+        let distributions = [
+            DurationDistribution::Constant(2.0),
+            DurationDistribution::Exponential(2.0),
+            DurationDistribution::Uniform(1.0, 3.0),
+            DurationDistribution::Weibull {
+                scale: 2.0 / 0.886227,
+                shape: 2.0,
+            },
+        ];
+        for distribution in distributions {
+            distribution.valid().unwrap();
+            let avg = (0..10000)
+                .map(|_| distribution.sample(&mut rng))
+                .sum::<f64>()
+                / 10000.0;
+            assert!((avg - 2.0).abs() < 0.1, "{:?} avg {}", distribution, avg);
+        }
+        assert!(DurationDistribution::Constant(0.0).valid().is_err());
+        assert!(DurationDistribution::Uniform(3.0, 1.0).valid().is_err());
+    }
+
+    #[test]
+    fn test_faults_injector() {
+        let (physical_topology, logical_topology) = crate::tests::logical_topology_2_2();
+        let num_links = logical_topology.graph().edge_count();
+        let mut injector = FaultInjector::new(
+            &[RandomFaults {
+                scope: FaultScope::LogicalLinks,
+                time_to_failure: DurationDistribution::Constant(10.0),
+                time_to_repair: DurationDistribution::Constant(1.0),
+            }],
+            &[super::ScriptedFault {
+                target: FaultTarget::Node(0),
+                start: 5.0,
+                duration: 2.0,
+            }],
+            &physical_topology,
+            &logical_topology,
+            42,
+        )
+        .unwrap();
+        let events = injector.initial();
+        assert_eq!(num_links + 2, events.len());
+
+        // Overlapping outages.
+        let target = FaultTarget::Node(0);
+        let scripted = FaultData {
+            target,
+            process: None,
+        };
+        let (went_down, events) = injector.fail(100, &scripted);
+        assert!(went_down && events.is_empty());
+        let (went_down, _) = injector.fail(200, &scripted);
+        assert!(!went_down);
+        assert!(injector.is_down(&target));
+        assert_eq!(None, injector.repair(300, &scripted).0);
+        assert!(injector.is_down(&target));
+        assert_eq!(Some(300), injector.repair(400, &scripted).0);
+        assert!(!injector.is_down(&target));
+
+        // Random failures schedule the repair, and vice versa.
+        let random = FaultData {
+            target: FaultTarget::Node(1),
+            process: Some(0),
+        };
+        let (_, events) = injector.fail(0, &random);
+        assert_eq!(1_000_000_000, events[0].time());
+        assert!(matches!(
+            events[0].event_type,
+            EventType::NetworkEvent(NetworkEventData::Repair(_))
+        ));
+        let (_, events) = injector.repair(0, &random);
+        assert_eq!(10_000_000_000, events[0].time());
+
+        // Invalid scripted faults.
+        for (target, start, duration) in [
+            (FaultTarget::Node(99), 0.0, 1.0),
+            (FaultTarget::LogicalLink(0, 0), 0.0, 1.0),
+            (FaultTarget::Transmitter(0), -1.0, 1.0),
+            (FaultTarget::Transmitter(0), 0.0, 0.0),
+        ] {
+            assert!(FaultInjector::new(
+                &[],
+                &[super::ScriptedFault {
+                    target,
+                    start,
+                    duration
+                }],
+                &physical_topology,
+                &logical_topology,
+                42,
+            )
+            .is_err());
+        }
+    }
+}
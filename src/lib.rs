@@ -1,19 +1,30 @@
 // SPDX-FileCopyrightText: © 2025 Claudio Cicconetti <c.cicconetti@iit.cnr.it>
 // SPDX-License-Identifier: MIT
 
+pub mod admission;
 pub mod apps;
+pub mod classical;
 pub mod config;
 pub mod epr_register;
 pub mod event;
 pub mod event_queue;
+pub mod faults;
 pub mod logical_topology;
+pub mod memory_pool;
 pub mod network;
 pub mod nic;
 pub mod node;
 pub mod output;
 pub mod physical_topology;
+pub mod processor;
+pub mod request_scheduler;
+pub mod reservation;
 pub mod simulation;
 #[cfg(test)]
 pub mod tests;
+pub mod time_sharing;
+pub mod topology_generators;
+pub mod topology_import;
 pub mod user_config;
 pub mod utils;
+pub mod weather;
@@ -2,13 +2,16 @@
 // SPDX-License-Identifier: MIT
 
 use petgraph::visit::{EdgeRef, IntoNodeReferences};
-use shuffle::shuffler::Shuffler;
+use rand::seq::SliceRandom;
 
 const NEGLIGIBLE_AMOUNT: f64 = 1e-5;
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub enum PhysicalToLogicalPolicy {
     RandomGreedy,
+    /// Same as `RandomGreedy`, but the free-space logical links time-share
+    /// the transmitters and detectors, hence they do not reserve them.
+    RandomGreedyTimeShared,
 }
 
 #[derive(Debug, Clone, Copy, Default, PartialOrd, PartialEq)]
@@ -30,6 +33,15 @@ pub struct EdgeWeight {
     pub capacity: f64,
     /// Cost of the edge, to compute shortest distance.
     pub cost: usize,
+    /// True if the EPR pairs are generated by the heralded source of a fiber
+    /// link, in which case no transmitter of tx is used.
+    pub fiber: bool,
+    /// True if the transmitter of tx and the detectors are time-shared with
+    /// other logical links.
+    pub time_shared: bool,
+    /// True if an end-point is memoryless, in which case the EPR pairs are
+    /// measured as soon as they are detected and no memory qubits are used.
+    pub memoryless: bool,
 }
 
 impl std::fmt::Display for EdgeWeight {
@@ -49,6 +61,9 @@ impl petgraph::algo::FloatMeasure for EdgeWeight {
             memory_qubits: 0,
             capacity: 0.0,
             cost: 0,
+            fiber: false,
+            time_shared: false,
+            memoryless: false,
         }
     }
 
@@ -58,6 +73,9 @@ impl petgraph::algo::FloatMeasure for EdgeWeight {
             memory_qubits: 0,
             capacity: 0.0,
             cost: usize::MAX / 2,
+            fiber: false,
+            time_shared: false,
+            memoryless: false,
         }
     }
 }
@@ -71,6 +89,9 @@ impl std::ops::Add for EdgeWeight {
             memory_qubits: 0,
             capacity: 0.0,
             cost: self.cost + rhs.cost,
+            fiber: false,
+            time_shared: false,
+            memoryless: false,
         }
     }
 }
@@ -89,10 +110,14 @@ type Paths = std::collections::HashMap<
 /// a number of memory qubits.
 /// The egress node of the edge is the master, the ingress one is the slave.
 ///
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
 pub struct LogicalTopology {
     graph: Graph,
     paths: Paths,
+    /// Nodes that can be intermediate in a path.
+    relays: std::collections::HashSet<u32>,
+    /// Paths that avoid the links currently down, if any.
+    rerouted: Option<Paths>,
 }
 
 impl LogicalTopology {
@@ -103,6 +128,8 @@ impl LogicalTopology {
     /// Return the path between `src` and `dst` in the logical topology.
     /// Subsequent calls always return the same path for the same
     /// source/destination pair.
+    /// All the intermediate nodes in the path can perform entanglement
+    /// swapping, which may include OGS nodes acting as relays.
     pub fn path(&self, src: u32, dst: u32) -> Vec<u32> {
         assert!(
             src < self.graph.node_count() as u32,
@@ -117,27 +144,84 @@ impl LogicalTopology {
             self.graph.node_count()
         );
 
+        if let Some(rerouted) = &self.rerouted {
+            if let Some(path) = walk_path(rerouted, src, dst) {
+                return path;
+            }
+        }
+
         let paths = self.paths.get(&src);
         assert!(
             paths.is_some(),
             "could not find path from {src} to {dst} in the logical topology"
         );
-        let paths = paths.unwrap();
-        assert!(paths.predecessors.len() == self.graph.node_count());
-        let mut ret = vec![dst];
+        assert!(paths.unwrap().predecessors.len() == self.graph.node_count());
+        walk_path(&self.paths, src, dst)
+            .expect("invalid predecessor when finding a path in the logical topology")
+    }
 
-        let mut cur = dst as usize;
-        while cur != src as usize {
-            assert!(cur < paths.predecessors.len());
-            let prev = paths.predecessors[cur]
-                .expect("invalid predecessor when finding a path in the logical topology");
-            cur = prev.index();
-            ret.push(cur as u32);
-        }
+    /// Return true if there is a path from `src` to `dst` that EPR requests
+    /// can use, i.e., without memoryless logical links.
+    pub fn routable(&self, src: u32, dst: u32) -> bool {
+        walk_path(&self.paths, src, dst).is_some()
+    }
 
-        ret.reverse();
+    /// Return the capacity of the bottleneck of a path, i.e., the minimum
+    /// rate at which EPR pairs are generated on its logical links.
+    pub fn capacity(&self, path: &[u32]) -> f64 {
+        self.path_weights(path)
+            .map(|weight| weight.map_or(0.0, |weight| weight.capacity))
+            .fold(f64::INFINITY, f64::min)
+    }
+
+    /// Return the minimum number of memory qubits reserved for the logical
+    /// links of a path.
+    pub fn memory_qubits(&self, path: &[u32]) -> u32 {
+        self.path_weights(path)
+            .map(|weight| weight.map_or(0, |weight| weight.memory_qubits))
+            .min()
+            .unwrap_or(0)
+    }
+
+    /// Return the minimum number of memory qubits reserved for the first
+    /// and last logical links of a path, which hold the EPR pairs of a
+    /// request at its end-points until they are delivered.
+    pub fn end_memory_qubits(&self, path: &[u32]) -> u32 {
+        assert!(path.len() >= 2, "path too short: {path:?}");
+        self.memory_qubits(&path[..2])
+            .min(self.memory_qubits(&path[path.len() - 2..]))
+    }
+
+    /// Return the weights of the logical links of a path, in any direction,
+    /// or None for the missing ones.
+    fn path_weights<'a>(
+        &'a self,
+        path: &'a [u32],
+    ) -> impl Iterator<Item = Option<&'a EdgeWeight>> + 'a {
+        path.windows(2).map(|hop| {
+            let (u, v) = (hop[0].into(), hop[1].into());
+            self.graph
+                .find_edge(u, v)
+                .or_else(|| self.graph.find_edge(v, u))
+                .map(|edge| &self.graph[edge])
+        })
+    }
 
-        ret
+    /// Recompute the paths so that they do not traverse the given links,
+    /// identified by their (master, slave) nodes, which are down.
+    ///
+    /// If a destination cannot be reached without such links, the original
+    /// path is returned by `path()`.
+    pub fn reroute(
+        &mut self,
+        excluded: &std::collections::HashSet<(u32, u32)>,
+    ) -> anyhow::Result<()> {
+        self.rerouted = if excluded.is_empty() {
+            None
+        } else {
+            Some(find_paths_excluding(&self.graph, &self.relays, excluded)?)
+        };
+        Ok(())
     }
 
     /// Create the logical topology from a physical topology using algorithm
@@ -149,11 +233,20 @@ impl LogicalTopology {
     ) -> anyhow::Result<Self> {
         let graph = match policy {
             PhysicalToLogicalPolicy::RandomGreedy => {
-                physical_to_logical_random_greedy(physical_topology, rng)?
+                physical_to_logical_random_greedy(physical_topology, false, rng)?
+            }
+            PhysicalToLogicalPolicy::RandomGreedyTimeShared => {
+                physical_to_logical_random_greedy(physical_topology, true, rng)?
             }
         };
-        let paths = find_paths(&graph)?;
-        Ok(Self { graph, paths })
+        let relays = relay_set(physical_topology);
+        let paths = find_paths(&graph, &relays)?;
+        Ok(Self {
+            graph,
+            paths,
+            relays,
+            rerouted: None,
+        })
     }
 }
 
@@ -162,6 +255,8 @@ struct LogicalEdge {
     pub tx: u32,
     pub master: u32,
     pub slave: u32,
+    /// Capacity of the embedded source, if this is a fiber link.
+    pub fiber_capacity: Option<f64>,
 }
 
 impl LogicalEdge {
@@ -170,6 +265,7 @@ impl LogicalEdge {
             tx: self.tx,
             master: self.slave,
             slave: self.master,
+            fiber_capacity: self.fiber_capacity,
         }
     }
 }
@@ -188,11 +284,11 @@ macro_rules! weight {
 
 fn physical_to_logical_random_greedy(
     physical_topology: &crate::physical_topology::PhysicalTopology,
+    time_shared: bool,
     rng: &mut rand::rngs::StdRng,
 ) -> anyhow::Result<Graph> {
     let mut possible_logical_edges = find_possible_logical_edges(physical_topology);
-    let mut irs = shuffle::irs::Irs::default();
-    let _ = irs.shuffle(&mut possible_logical_edges, rng);
+    possible_logical_edges.shuffle(rng);
 
     let mut physical_graph = physical_topology.graph().clone();
 
@@ -203,8 +299,9 @@ fn physical_to_logical_random_greedy(
         logical_graph.add_node(NodeWeight {});
     }
 
-    // Save OGS nodes.
-    let ogs_nodes = physical_topology.ogs_indices();
+    // Save end nodes and those that can act as intermediate repeaters.
+    let end_nodes = physical_topology.end_node_indices();
+    let relays = relay_set(physical_topology);
 
     for logical_edge in possible_logical_edges {
         // Skip if master and slave are already connected by an edge.
@@ -215,9 +312,13 @@ fn physical_to_logical_random_greedy(
             continue;
         }
 
-        // Skip if end-points do not have each at least one  memory qubit.
-        if weight!(logical_edge.master, physical_graph).memory_qubits == 0
-            || weight!(logical_edge.slave, physical_graph).memory_qubits == 0
+        // Skip if end-points do not have each at least one  memory qubit,
+        // unless any of them is memoryless.
+        let memoryless = weight!(logical_edge.master, physical_graph).memoryless
+            || weight!(logical_edge.slave, physical_graph).memoryless;
+        if !memoryless
+            && (weight!(logical_edge.master, physical_graph).memory_qubits == 0
+                || weight!(logical_edge.slave, physical_graph).memory_qubits == 0)
         {
             continue;
         }
@@ -229,18 +330,28 @@ fn physical_to_logical_random_greedy(
             continue;
         }
 
-        // Skip if tx does not have a transmitter available.
-        if weight!(logical_edge.tx, physical_graph).transmitters == 0 {
+        // Skip if tx does not have a transmitter available, unless the EPR
+        // pairs are generated by the source embedded in a fiber link.
+        let fiber = logical_edge.fiber_capacity.is_some();
+        if !fiber && weight!(logical_edge.tx, physical_graph).transmitters == 0 {
             continue;
         }
 
-        // Reserve one memory qubit and one detector in the master/slave nodes
-        // and a transmitter in the tx node.
-        weight!(logical_edge.master, physical_graph).memory_qubits -= 1;
-        weight!(logical_edge.slave, physical_graph).memory_qubits -= 1;
-        weight!(logical_edge.master, physical_graph).detectors -= 1;
-        weight!(logical_edge.slave, physical_graph).detectors -= 1;
-        weight!(logical_edge.tx, physical_graph).transmitters -= 1;
+        // Reserve one memory qubit, unless memoryless, and one detector in
+        // the master/slave nodes and a transmitter in the tx node, unless
+        // time-shared.
+        let time_shared = time_shared && !fiber;
+        if !memoryless {
+            weight!(logical_edge.master, physical_graph).memory_qubits -= 1;
+            weight!(logical_edge.slave, physical_graph).memory_qubits -= 1;
+        }
+        if !time_shared {
+            weight!(logical_edge.master, physical_graph).detectors -= 1;
+            weight!(logical_edge.slave, physical_graph).detectors -= 1;
+        }
+        if !fiber && !time_shared {
+            weight!(logical_edge.tx, physical_graph).transmitters -= 1;
+        }
 
         // Add the edge to the logical topology.
         logical_graph.add_edge(
@@ -248,30 +359,34 @@ fn physical_to_logical_random_greedy(
             logical_edge.slave.into(),
             EdgeWeight {
                 tx: logical_edge.tx,
-                memory_qubits: 1,
-                capacity: 0.0,
+                memory_qubits: if memoryless { 0 } else { 1 },
+                capacity: logical_edge.fiber_capacity.unwrap_or(0.0),
                 cost: 1,
+                fiber,
+                time_shared,
+                memoryless,
             },
         );
 
-        // Break as soon as all the OGS nodes can reach one another.
-        if reachable(&logical_graph, &ogs_nodes) {
+        // Break as soon as all the end nodes can reach one another.
+        if reachable(&logical_graph, &end_nodes, &relays) {
             break;
         }
     }
 
     anyhow::ensure!(
-        reachable(&logical_graph, &ogs_nodes),
+        reachable(&logical_graph, &end_nodes, &relays),
         "could not find a logical topology for the given physical topology"
     );
 
     // Assign residual memory qubits as possible, one at a time.
     let mut candidate_edges = vec![];
     for edge in logical_graph.edge_references() {
-        candidate_edges.push((edge.source(), edge.target()));
+        if !edge.weight().memoryless {
+            candidate_edges.push((edge.source(), edge.target()));
+        }
     }
-    let mut irs = shuffle::irs::Irs::default();
-    let _ = irs.shuffle(&mut candidate_edges, rng);
+    candidate_edges.shuffle(rng);
 
     while !candidate_edges.is_empty() {
         let mut candidate_edges_new = vec![];
@@ -293,13 +408,14 @@ fn physical_to_logical_random_greedy(
 
     // Assign logical edge capacities, by dividing evenly for each node
     // between the number of logical edges crossing that node.
+    // Fiber links already have the capacity of their embedded source.
     for (u, w) in physical_graph.node_references() {
         let u_ndx = u.index() as u32;
 
         // Count how many logical edges are served by this node as the tx.
         let num_served = logical_graph
             .edge_references()
-            .filter(|e| e.weight().tx == u_ndx)
+            .filter(|e| e.weight().tx == u_ndx && !e.weight().fiber)
             .count();
 
         // Skip edges that do not serve as tx.
@@ -308,11 +424,11 @@ fn physical_to_logical_random_greedy(
         }
 
         // Divide equally the capacity between logical edges.
-        let even_capacity = w.capacity / num_served as f64;
+        let even_capacity = w.tx_capacity() / num_served as f64;
 
         // Assign it to all the logical edges.
         for w in logical_graph.edge_weights_mut() {
-            if w.tx == u_ndx {
+            if w.tx == u_ndx && !w.fiber {
                 w.capacity = even_capacity;
             }
         }
@@ -321,12 +437,78 @@ fn physical_to_logical_random_greedy(
     Ok(logical_graph)
 }
 
+/// Return the set of nodes in the physical topology that can act as
+/// intermediate repeaters.
+fn relay_set(
+    physical_topology: &crate::physical_topology::PhysicalTopology,
+) -> std::collections::HashSet<u32> {
+    physical_topology.relay_indices().into_iter().collect()
+}
+
+/// Return the path from `src` to `dst` using the predecessors in `paths`,
+/// or None if `dst` cannot be reached.
+fn walk_path(paths: &Paths, src: u32, dst: u32) -> Option<Vec<u32>> {
+    let paths = paths.get(&src)?;
+    let mut ret = vec![dst];
+
+    let mut cur = dst as usize;
+    while cur != src as usize {
+        assert!(cur < paths.predecessors.len());
+        cur = paths.predecessors[cur]?.index();
+        ret.push(cur as u32);
+    }
+
+    ret.reverse();
+
+    Some(ret)
+}
+
+/// Return the shortest paths on the logical topology graph from `source` to
+/// all other nodes, such that only nodes in `relays` can be intermediate
+/// and neither the edges in `excluded` nor the memoryless ones are used.
+///
+/// The memoryless logical links are never used, since their EPR pairs are
+/// measured on detection and cannot be swapped or delivered to a request.
+fn relay_paths(
+    logical_graph: &Graph,
+    source: petgraph::graph::NodeIndex,
+    relays: &std::collections::HashSet<u32>,
+    excluded: &std::collections::HashSet<(u32, u32)>,
+) -> Result<
+    petgraph::algo::bellman_ford::Paths<petgraph::graph::NodeIndex, EdgeWeight>,
+    petgraph::algo::NegativeCycle,
+> {
+    let filtered = petgraph::visit::EdgeFiltered::from_fn(
+        logical_graph,
+        |e: petgraph::graph::EdgeReference<EdgeWeight, u32>| {
+            (e.source() == source || relays.contains(&(e.source().index() as u32)))
+                && !excluded.contains(&(e.source().index() as u32, e.target().index() as u32))
+                && !e.weight().memoryless
+        },
+    );
+    petgraph::algo::bellman_ford(&filtered, source)
+}
+
+/// Return all possible paths on the logical topology graph from any source node
+/// to all others, where only nodes in `relays` can be intermediate.
+fn find_paths(
+    logical_graph: &Graph,
+    relays: &std::collections::HashSet<u32>,
+) -> anyhow::Result<Paths> {
+    find_paths_excluding(logical_graph, relays, &std::collections::HashSet::new())
+}
+
 /// Return all possible paths on the logical topology graph from any source node
-/// to all others.
-fn find_paths(logical_graph: &Graph) -> anyhow::Result<Paths> {
+/// to all others, where only nodes in `relays` can be intermediate and the
+/// edges in `excluded` are not used.
+fn find_paths_excluding(
+    logical_graph: &Graph,
+    relays: &std::collections::HashSet<u32>,
+    excluded: &std::collections::HashSet<(u32, u32)>,
+) -> anyhow::Result<Paths> {
     let mut all_paths = std::collections::HashMap::new();
     for source in logical_graph.node_indices() {
-        match petgraph::algo::bellman_ford(&logical_graph, source) {
+        match relay_paths(logical_graph, source, relays, excluded) {
             Ok(local_paths) => {
                 all_paths.insert(source.index() as u32, local_paths);
             }
@@ -343,7 +525,8 @@ fn find_paths(logical_graph: &Graph) -> anyhow::Result<Paths> {
 ///
 /// A logical topology is valid if:
 ///
-/// - any OGS node can reach any other
+/// - any end node (OGS or end-user node) can reach any other, only via
+///   nodes that can perform entanglement swapping
 /// - each edge appears at most once between any two nodes
 /// - each edge has non-vanishing memory qubits and capacity
 /// - the sum of the capacity of transmitters is not exceeded
@@ -360,8 +543,12 @@ pub fn is_valid(
     physical_topology: &crate::physical_topology::PhysicalTopology,
 ) -> anyhow::Result<()> {
     anyhow::ensure!(
-        reachable(logical_topology, &physical_topology.ogs_indices()),
-        "there is some OGS that cannot be reached by another OGS"
+        reachable(
+            logical_topology,
+            &physical_topology.end_node_indices(),
+            &relay_set(physical_topology)
+        ),
+        "there is some end node that cannot be reached by another end node"
     );
     for e in logical_topology.edge_references() {
         anyhow::ensure!(
@@ -379,7 +566,7 @@ pub fn is_valid(
             e
         );
         anyhow::ensure!(
-            e.weight().memory_qubits > 0,
+            e.weight().memory_qubits > 0 || e.weight().memoryless,
             "vanishing number of qubits for edge {:?}",
             e
         );
@@ -388,15 +575,15 @@ pub fn is_valid(
         let u_ndx = u.index() as u32;
         let sum_capacity: f64 = logical_topology
             .edge_weights()
-            .filter(|e| e.tx == u_ndx)
+            .filter(|e| e.tx == u_ndx && !e.fiber)
             .map(|e| e.capacity)
             .sum();
         anyhow::ensure!(
-            w.capacity >= sum_capacity || (sum_capacity - w.capacity) < NEGLIGIBLE_AMOUNT,
+            w.tx_capacity() >= sum_capacity || (sum_capacity - w.tx_capacity()) < NEGLIGIBLE_AMOUNT,
             "tx capacity of node {} exceeded: {} > {}",
             u_ndx,
             sum_capacity,
-            w.capacity
+            w.tx_capacity()
         );
 
         let sum_memory_qubits: u32 = logical_topology
@@ -412,10 +599,15 @@ pub fn is_valid(
             w.memory_qubits
         );
 
-        let sum_detectors: u32 = logical_topology
+        // Time-shared logical links only need a detector and a transmitter.
+        let time_shared = |e: &&EdgeWeight| e.time_shared;
+        let incident = logical_topology
             .edge_references()
             .filter(|e| e.source() == u || e.target() == u)
-            .count() as u32;
+            .map(|e| e.weight())
+            .collect::<Vec<_>>();
+        let sum_detectors = incident.iter().filter(|e| !e.time_shared).count() as u32
+            + incident.iter().any(time_shared) as u32;
         anyhow::ensure!(
             w.detectors >= sum_detectors,
             "detectors of node {} exceeded: {} > {}",
@@ -424,10 +616,12 @@ pub fn is_valid(
             w.detectors
         );
 
-        let sum_transmitters: u32 = logical_topology
-            .edge_references()
-            .filter(|e| e.weight().tx == u_ndx)
-            .count() as u32;
+        let served = logical_topology
+            .edge_weights()
+            .filter(|e| e.tx == u_ndx && !e.fiber)
+            .collect::<Vec<_>>();
+        let sum_transmitters = served.iter().filter(|e| !e.time_shared).count() as u32
+            + served.iter().any(time_shared) as u32;
         anyhow::ensure!(
             w.transmitters >= sum_transmitters,
             "transmitters of node {} exceeded: {} > {}",
@@ -439,16 +633,30 @@ pub fn is_valid(
     Ok(())
 }
 
-/// Return true if any node can reach any other via the given graph.
-fn reachable(graph: &Graph, nodes: &Vec<u32>) -> bool {
+/// Return true if any node can reach any other via the given graph, using
+/// only nodes in `relays` as intermediate nodes.
+///
+/// The paths are found as for the EPR requests, i.e., without memoryless
+/// logical links, but two nodes are also reachable if they share a
+/// memoryless logical link, whose EPR pairs they measure directly.
+fn reachable(graph: &Graph, nodes: &Vec<u32>, relays: &std::collections::HashSet<u32>) -> bool {
     for u in nodes {
-        match petgraph::algo::bellman_ford(&graph, (*u).into()) {
+        match relay_paths(
+            graph,
+            (*u).into(),
+            relays,
+            &std::collections::HashSet::new(),
+        ) {
             Ok(paths) => {
                 for v in nodes {
                     if *u == *v {
                         continue;
                     }
-                    if paths.predecessors[*v as usize].is_none() {
+                    if paths.predecessors[*v as usize].is_none()
+                        && !graph
+                            .find_edge_undirected((*u).into(), (*v).into())
+                            .is_some_and(|(e, _)| graph[e].memoryless)
+                    {
                         return false;
                     }
                 }
@@ -462,7 +670,12 @@ fn reachable(graph: &Graph, nodes: &Vec<u32>) -> bool {
 /// Find all possible logical edges in a given physical topology.
 ///
 /// Add two edges for each pair of nodes (u,v) that have at least one detector
-/// and can be reached by a transmitter tx with non-zero capacity.
+/// and can be reached by a transmitter tx with non-zero capacity, provided
+/// that the physical topology has a fidelity for the EPR pairs generated
+/// (e.g., OGS transmitters can only reach satellites).
+///
+/// Also add two edges for each fiber link whose end-points have at least one
+/// detector, with one of the end-points acting as tx.
 ///
 /// Return a vector of tuples (tx,u,v).
 fn find_possible_logical_edges(
@@ -475,10 +688,12 @@ fn find_possible_logical_edges(
         let u_w = graph.node_weight(u).unwrap();
         if u_w.transmitters > 0 {
             let mut rx_candidates = vec![];
-            // Find all neighbors that can be an rx
-            for v in graph.neighbors(u) {
-                if graph.node_weight(v).unwrap().detectors > 0 {
-                    rx_candidates.push(v.index());
+            // Find all neighbors over free-space links that can be an rx
+            for e in graph.edges(u) {
+                if *e.weight().link() == crate::physical_topology::LinkType::FreeSpace
+                    && graph.node_weight(e.target()).unwrap().detectors > 0
+                {
+                    rx_candidates.push(e.target().index());
                 }
             }
 
@@ -491,10 +706,21 @@ fn find_possible_logical_edges(
             for i in 0..rx_candidates.len() {
                 for j in 0..i {
                     assert_ne!(rx_candidates[i], rx_candidates[j]);
+                    if physical_topology
+                        .fidelity(
+                            u.index() as u32,
+                            rx_candidates[i] as u32,
+                            rx_candidates[j] as u32,
+                        )
+                        .is_err()
+                    {
+                        continue;
+                    }
                     let logical_edge = LogicalEdge {
                         tx: u.index() as u32,
                         master: rx_candidates[i] as u32,
                         slave: rx_candidates[j] as u32,
+                        fiber_capacity: None,
                     };
                     let logical_edge_swapped = logical_edge.swap_master_slave();
                     ret.push(logical_edge);
@@ -504,6 +730,24 @@ fn find_possible_logical_edges(
         }
     }
 
+    for e in graph.edge_references() {
+        if let crate::physical_topology::LinkType::Fiber(fiber_params) = e.weight().link() {
+            if graph.node_weight(e.source()).unwrap().detectors > 0
+                && graph.node_weight(e.target()).unwrap().detectors > 0
+            {
+                let logical_edge = LogicalEdge {
+                    tx: e.source().index() as u32,
+                    master: e.source().index() as u32,
+                    slave: e.target().index() as u32,
+                    fiber_capacity: Some(fiber_params.attempt_rate),
+                };
+                let logical_edge_swapped = logical_edge.swap_master_slave();
+                ret.push(logical_edge);
+                ret.push(logical_edge_swapped);
+            }
+        }
+    }
+
     ret
 }
 
@@ -512,9 +756,12 @@ mod tests {
     use petgraph::visit::EdgeRef;
     use rand::SeedableRng;
 
-    use crate::logical_topology::{is_valid, LogicalTopology, PhysicalToLogicalPolicy};
+    use crate::logical_topology::{is_valid, LogicalTopology};
 
-    use super::{find_paths, find_possible_logical_edges, physical_to_logical_random_greedy};
+    use super::{
+        find_paths, find_possible_logical_edges, physical_to_logical_random_greedy, relay_set,
+        EdgeWeight, Graph, NodeWeight,
+    };
     use crate::tests::physical_topology_2_2;
 
     #[test]
@@ -536,19 +783,44 @@ mod tests {
     }
 
     #[test]
-    fn test_logical_topology_path() {
-        let physical_topology = physical_topology_2_2();
-        let mut rng = rand::rngs::StdRng::seed_from_u64(42);
-        let logical_topology = LogicalTopology::from_physical_topology(
-            &PhysicalToLogicalPolicy::RandomGreedy,
-            &physical_topology,
-            &mut rng,
+    fn test_logical_topology_find_possible_logical_edges_uplink() {
+        let mut ogs_weight = crate::physical_topology::NodeWeight::default_ogs();
+        ogs_weight.transmitters = 1;
+        ogs_weight.capacity = 1.0;
+        let physical_topology = crate::physical_topology::PhysicalTopology::from_chain_static(
+            crate::physical_topology::ChainParams {
+                orbit_to_orbit_distance: 1.0,
+                ground_to_orbit_distance: 1.0,
+                num_repeaters: 2,
+                uplink_loss: 0.0,
+            },
+            crate::physical_topology::NodeWeight::default_sat(),
+            ogs_weight,
+            crate::physical_topology::StaticFidelities::default(),
         )
-        .expect("could not create the logical topology");
+        .unwrap();
+
+        let res = find_possible_logical_edges(&physical_topology);
+        let ogs_indices = physical_topology.ogs_indices();
+        let uplinks = res
+            .iter()
+            .filter(|e| ogs_indices.contains(&e.tx))
+            .collect::<Vec<_>>();
+
+        // Each OGS can only reach itself and the satellite at its end.
+        assert_eq!(4, uplinks.len());
+        for e in uplinks {
+            assert!(e.tx == e.master || e.tx == e.slave);
+        }
+    }
+
+    #[test]
+    fn test_logical_topology_path() {
+        let (physical_topology, logical_topology) = crate::tests::logical_topology_2_2();
 
         for src in physical_topology.ogs_indices() {
             for dst in physical_topology.ogs_indices() {
-                let path = logical_topology.path(src as u32, dst as u32);
+                let path = logical_topology.path(src, dst);
                 println!("src {} dst {} path {:?}", src, dst, path);
                 assert!(!path.is_empty());
 
@@ -558,10 +830,209 @@ mod tests {
                     assert_eq!(src, *path.first().unwrap());
                     assert_eq!(dst, *path.last().unwrap());
                 }
+
+                // OGS nodes cannot be intermediate nodes.
+                for ndx in path.iter().skip(1).take(path.len().saturating_sub(2)) {
+                    assert!(physical_topology.sat_indices().contains(ndx));
+                }
             }
         }
     }
 
+    #[test]
+    fn test_logical_topology_find_paths_relays() -> anyhow::Result<()> {
+        //
+        // 0 -> 1 -> 2
+        // |         ^
+        // v         |
+        // 3 ------> 4
+        //
+        let mut graph = Graph::new();
+        for _ in 0..5 {
+            graph.add_node(NodeWeight {});
+        }
+        let weight = EdgeWeight {
+            tx: 0,
+            memory_qubits: 1,
+            capacity: 1.0,
+            cost: 1,
+            fiber: false,
+            time_shared: false,
+            memoryless: false,
+        };
+        for (u, v) in [(0, 1), (1, 2), (0, 3), (3, 4), (4, 2)] {
+            graph.add_edge(u.into(), v.into(), weight);
+        }
+        let logical_topology = |relays: &[u32]| -> anyhow::Result<LogicalTopology> {
+            Ok(LogicalTopology {
+                graph: graph.clone(),
+                paths: find_paths(&graph, &relays.iter().cloned().collect())?,
+                relays: relays.iter().cloned().collect(),
+                ..Default::default()
+            })
+        };
+
+        assert_eq!(vec![0, 1, 2], logical_topology(&[1, 3, 4])?.path(0, 2));
+        assert_eq!(vec![0, 3, 4, 2], logical_topology(&[3, 4])?.path(0, 2));
+
+        // Reroute around a link that is down, unless there is no alternative.
+        let mut topo = logical_topology(&[1, 3, 4])?;
+        topo.reroute(&std::collections::HashSet::from([(1, 2)]))?;
+        assert_eq!(vec![0, 3, 4, 2], topo.path(0, 2));
+        topo.reroute(&std::collections::HashSet::from([(1, 2), (4, 2)]))?;
+        assert_eq!(vec![0, 1, 2], topo.path(0, 2));
+        topo.reroute(&std::collections::HashSet::new())?;
+        assert_eq!(vec![0, 1, 2], topo.path(0, 2));
+
+        // Source and destination do not need to be relays.
+        assert_eq!(vec![0, 1], logical_topology(&[])?.path(0, 1));
+        Ok(())
+    }
+
+    #[test]
+    fn test_logical_topology_memoryless_not_routed() -> anyhow::Result<()> {
+        //
+        // 0 -> 1 => 2
+        // |         ^
+        // v         |
+        // 3 ------> 4
+        //
+        // where => is a memoryless logical link.
+        let mut graph = Graph::new();
+        for _ in 0..5 {
+            graph.add_node(NodeWeight {});
+        }
+        let weight = EdgeWeight {
+            tx: 0,
+            memory_qubits: 1,
+            capacity: 1.0,
+            cost: 1,
+            fiber: false,
+            time_shared: false,
+            memoryless: false,
+        };
+        for (u, v) in [(0, 1), (0, 3), (3, 4), (4, 2)] {
+            graph.add_edge(u.into(), v.into(), weight);
+        }
+        graph.add_edge(
+            1.into(),
+            2.into(),
+            EdgeWeight {
+                memory_qubits: 0,
+                memoryless: true,
+                ..weight
+            },
+        );
+        let relays = std::collections::HashSet::from([1, 3, 4]);
+        let topo = LogicalTopology {
+            graph: graph.clone(),
+            paths: find_paths(&graph, &relays)?,
+            relays: relays.clone(),
+            ..Default::default()
+        };
+        assert!(topo.routable(0, 2));
+        assert_eq!(vec![0, 3, 4, 2], topo.path(0, 2));
+
+        // The memoryless link is not relayed, neither by the EPR requests
+        // nor to find a logical topology.
+        graph.remove_edge(graph.find_edge(4.into(), 2.into()).unwrap());
+        let no_excluded = std::collections::HashSet::new();
+        let paths = super::relay_paths(&graph, 0.into(), &relays, &no_excluded).unwrap();
+        assert!(paths.predecessors[2].is_none());
+        assert!(paths.predecessors[1].is_some());
+
+        // Nodes sharing a memoryless link reach one another directly.
+        assert!(super::reachable(&graph, &vec![1, 2], &relays));
+        let topo = LogicalTopology {
+            graph: graph.clone(),
+            paths: find_paths(&graph, &relays)?,
+            relays,
+            ..Default::default()
+        };
+        assert!(!topo.routable(0, 2));
+        assert!(topo.routable(0, 1));
+        Ok(())
+    }
+
+    #[test]
+    fn test_logical_topology_ogs_relays() {
+        let mut physical_topology = physical_topology_2_2();
+        assert!(relay_set(&physical_topology)
+            .iter()
+            .all(|ndx| physical_topology.sat_indices().contains(ndx)));
+
+        physical_topology
+            .set_ogs_relays(&[crate::physical_topology::OgsRelay {
+                ogs: 0,
+                swapping_success_prob: 0.9,
+                swapping_duration: 0.001,
+            }])
+            .unwrap();
+        let ogs_relay = physical_topology.ogs_indices()[0];
+        assert!(relay_set(&physical_topology).contains(&ogs_relay));
+    }
+
+    #[test]
+    fn test_logical_topology_fiber() {
+        let mut sat_weight = crate::physical_topology::NodeWeight::default_sat();
+        sat_weight.transmitters = 0;
+        sat_weight.capacity = 0.0;
+        let mut ogs_weight = crate::physical_topology::NodeWeight::default_ogs();
+        ogs_weight.memory_qubits = 4;
+        ogs_weight.detectors = 4;
+        let mut physical_topology = crate::physical_topology::PhysicalTopology::from_grid_fiber(
+            crate::physical_topology::GridParams {
+                orbit_to_orbit_distance: 1.0,
+                ground_to_orbit_distance: 1.0,
+                num_orbits: 2,
+                orbit_length: 2,
+                uplink_loss: 0.0,
+            },
+            crate::physical_topology::GridFiberParams {
+                layout: crate::physical_topology::FiberLayout::Ring,
+                ogs_to_ogs_distance: 1.0,
+                fiber: crate::physical_topology::FiberParams {
+                    attempt_rate: 42.0,
+                    ..Default::default()
+                },
+            },
+            sat_weight,
+            ogs_weight,
+            crate::physical_topology::StaticFidelities::default(),
+        )
+        .unwrap();
+
+        // Only the fiber links can generate EPR pairs, with the embedded
+        // source on one of the end-points.
+        let res = find_possible_logical_edges(&physical_topology);
+        assert_eq!(12, res.len());
+        for e in &res {
+            assert_eq!(Some(42.0), e.fiber_capacity);
+            assert!(e.tx == e.master || e.tx == e.slave);
+        }
+
+        // The OGS nodes must act as relays to reach one another.
+        let num_ogs = physical_topology.ogs_indices().len() as u32;
+        let relays = (0..num_ogs)
+            .map(|ogs| crate::physical_topology::OgsRelay {
+                ogs,
+                swapping_success_prob: 0.9,
+                swapping_duration: 0.001,
+            })
+            .collect::<Vec<_>>();
+        physical_topology.set_ogs_relays(&relays).unwrap();
+
+        let mut rng = rand::rngs::StdRng::seed_from_u64(42);
+        let logical_graph =
+            physical_to_logical_random_greedy(&physical_topology, false, &mut rng).unwrap();
+        is_valid(&logical_graph, &physical_topology).unwrap();
+        assert!(logical_graph.edge_count() > 0);
+        for w in logical_graph.edge_weights() {
+            assert!(w.fiber);
+            assert_eq!(42.0, w.capacity);
+        }
+    }
+
     #[test]
     fn test_logical_topology_physical_to_logical_random_greedy() -> anyhow::Result<()> {
         let mut rng = rand::rngs::StdRng::seed_from_u64(42);
@@ -569,7 +1040,7 @@ mod tests {
         for _try in 0..10 {
             let physical_topology = physical_topology_2_2();
             if let Ok(logical_graph) =
-                physical_to_logical_random_greedy(&physical_topology, &mut rng)
+                physical_to_logical_random_greedy(&physical_topology, false, &mut rng)
             {
                 for e in logical_graph.edge_references() {
                     println!(
@@ -584,7 +1055,7 @@ mod tests {
                     continue;
                 }
 
-                let all_paths = find_paths(&logical_graph)?;
+                let all_paths = find_paths(&logical_graph, &relay_set(&physical_topology))?;
 
                 let ogs_node_ids: std::collections::HashSet<u32> =
                     std::collections::HashSet::from_iter(
@@ -632,4 +1103,82 @@ mod tests {
 
         anyhow::bail!("test failed");
     }
+
+    #[test]
+    fn test_logical_topology_spdc_source() -> anyhow::Result<()> {
+        // The capacity of the links served by a satellite is determined by
+        // the rate of its source, which increases with the pump power.
+        let mut prev_capacity = 0.0;
+        for mean_photon_number in [0.01, 0.1] {
+            let source = crate::physical_topology::SpdcSource {
+                mean_photon_number,
+                repetition_rate: 1e6,
+                heralding_efficiency: 0.5,
+            };
+            let mut physical_topology = physical_topology_2_2();
+            let overrides = physical_topology
+                .sat_indices()
+                .into_iter()
+                .map(|ndx| crate::physical_topology::NodeOverride {
+                    selector: crate::physical_topology::NodeSelector::Index(ndx),
+                    weight: crate::physical_topology::NodeWeightOverride {
+                        source: Some(source.clone()),
+                        ..Default::default()
+                    },
+                })
+                .collect::<Vec<_>>();
+            physical_topology.set_node_overrides(&overrides)?;
+
+            // Not all the random choices lead to a feasible logical topology.
+            let logical_graph = (0..100)
+                .find_map(|seed| {
+                    let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
+                    physical_to_logical_random_greedy(&physical_topology, false, &mut rng).ok()
+                })
+                .expect("could not find a logical topology");
+            is_valid(&logical_graph, &physical_topology)?;
+
+            let mut capacity = 0.0;
+            for tx in physical_topology.sat_indices() {
+                let served = logical_graph
+                    .edge_weights()
+                    .filter(|w| w.tx == tx)
+                    .collect::<Vec<_>>();
+                let total = served.iter().map(|w| w.capacity).sum::<f64>();
+                if !served.is_empty() {
+                    assert_float_eq::assert_f64_near!(source.rate(), total);
+                }
+                capacity += total;
+            }
+            assert!(capacity > prev_capacity);
+            prev_capacity = capacity;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_logical_topology_reproducible() {
+        let edges = |seed| {
+            let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
+            physical_to_logical_random_greedy(&physical_topology_2_2(), false, &mut rng)
+                .ok()
+                .map(|logical_graph| {
+                    logical_graph
+                        .edge_references()
+                        .map(|e| (e.source().index(), e.target().index()))
+                        .collect::<Vec<(usize, usize)>>()
+                })
+        };
+
+        // The same seed yields the same logical topology.
+        let mut found = 0;
+        for seed in 0..10 {
+            let expected = edges(seed);
+            found += expected.iter().count();
+            for _ in 0..5 {
+                assert_eq!(expected, edges(seed));
+            }
+        }
+        assert!(found > 0);
+    }
 }
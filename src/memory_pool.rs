@@ -0,0 +1,196 @@
+// SPDX-FileCopyrightText: © 2025 Claudio Cicconetti <c.cicconetti@iit.cnr.it>
+// SPDX-License-Identifier: MIT
+
+/// Policy to allocate the memory qubits of a node to its logical links.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum MemoryPolicy {
+    /// Every logical link owns the memory qubits assigned by the logical
+    /// topology for the whole simulation.
+    #[default]
+    Partitioned,
+    /// All the memory qubits of the node form a pool, from which the
+    /// logical links borrow a qubit as long as one is free.
+    FirstCome,
+    /// Like `FirstCome`, but every logical link is guaranteed a minimum
+    /// number of qubits: the free qubits needed to honor the guarantees of
+    /// the other links cannot be borrowed and a link below its minimum can
+    /// evict a valid EPR pair from a link above its own.
+    MinGuarantee,
+    /// Like `FirstCome`, but every logical link is entitled to a share of
+    /// the pool proportional to the qubits assigned by the logical topology:
+    /// when the pool is full, a link below its share can evict a valid EPR
+    /// pair from the link that exceeds its own share the most.
+    WeightedShares,
+}
+
+/// Use of the pool by a logical link.
+#[derive(Debug, Clone, Copy)]
+pub struct LinkUsage {
+    /// Number of non-empty memory cells.
+    pub occupied: u32,
+    /// True if the link has a valid memory cell that can be evicted.
+    pub evictable: bool,
+    /// Number of qubits assigned by the logical topology.
+    pub assigned: u32,
+}
+
+/// Decision about where to store a new EPR pair in the pool.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Admission {
+    /// Store the EPR pair into a free qubit.
+    Free,
+    /// Evict a valid EPR pair of the link with given index, then store
+    /// the new one into the qubit released.
+    Evict(usize),
+    /// Overwrite the oldest valid EPR pair of the same link, if any.
+    Overwrite,
+}
+
+/// Memory qubits of a node shared by its logical links.
+#[derive(Debug)]
+pub struct MemoryPool {
+    /// Allocation policy, other than `Partitioned`.
+    policy: MemoryPolicy,
+    /// Total number of memory qubits.
+    size: u32,
+    /// Minimum number of qubits guaranteed to every link with `MinGuarantee`.
+    min_qubits: u32,
+}
+
+impl MemoryPool {
+    pub fn new(policy: MemoryPolicy, size: u32, min_qubits: u32) -> Self {
+        assert!(policy != MemoryPolicy::Partitioned);
+        Self {
+            policy,
+            size,
+            min_qubits,
+        }
+    }
+
+    /// Return the total number of memory qubits.
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+
+    /// Decide where a new EPR pair of the link with given index is stored.
+    ///
+    /// Parameters:
+    /// - `link`: the index of the link in `usage`
+    /// - `usage`: the current use of the pool by all the links of the node
+    pub fn admit(&self, link: usize, usage: &[LinkUsage]) -> Admission {
+        let occupied = usage.iter().map(|x| x.occupied).sum::<u32>();
+        let free = self.size.saturating_sub(occupied);
+        let own = usage[link].occupied;
+
+        match self.policy {
+            MemoryPolicy::Partitioned => panic!("partitioned memory has no pool"),
+            MemoryPolicy::FirstCome => {
+                if free > 0 {
+                    Admission::Free
+                } else {
+                    Admission::Overwrite
+                }
+            }
+            MemoryPolicy::MinGuarantee => {
+                let reserved = usage
+                    .iter()
+                    .enumerate()
+                    .filter(|(ndx, _)| *ndx != link)
+                    .map(|(_, x)| self.min_qubits.saturating_sub(x.occupied))
+                    .sum::<u32>();
+                if free > reserved {
+                    Admission::Free
+                } else if own < self.min_qubits {
+                    Self::victim(link, usage, |x| x.occupied as f64 - self.min_qubits as f64)
+                } else {
+                    Admission::Overwrite
+                }
+            }
+            MemoryPolicy::WeightedShares => {
+                let total_assigned = usage.iter().map(|x| x.assigned).sum::<u32>().max(1);
+                let share =
+                    |x: &LinkUsage| self.size as f64 * x.assigned as f64 / total_assigned as f64;
+                if free > 0 {
+                    Admission::Free
+                } else if ((own + 1) as f64) <= share(&usage[link]) {
+                    Self::victim(link, usage, |x| x.occupied as f64 - share(x))
+                } else {
+                    Admission::Overwrite
+                }
+            }
+        }
+    }
+
+    /// Return the link, other than the given one, with an evictable memory
+    /// cell and the largest positive excess, if any.
+    fn victim(link: usize, usage: &[LinkUsage], excess: impl Fn(&LinkUsage) -> f64) -> Admission {
+        let mut victim = None;
+        let mut max_excess = 0.0;
+        for (ndx, x) in usage.iter().enumerate() {
+            if ndx != link && x.evictable && excess(x) > max_excess {
+                victim = Some(ndx);
+                max_excess = excess(x);
+            }
+        }
+        match victim {
+            Some(ndx) => Admission::Evict(ndx),
+            None => Admission::Overwrite,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Admission, LinkUsage, MemoryPolicy, MemoryPool};
+
+    fn usage(occupied: &[u32], assigned: &[u32]) -> Vec<LinkUsage> {
+        occupied
+            .iter()
+            .zip(assigned.iter())
+            .map(|(occupied, assigned)| LinkUsage {
+                occupied: *occupied,
+                evictable: *occupied > 0,
+                assigned: *assigned,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_memory_pool_admit() {
+        let pool = MemoryPool::new(MemoryPolicy::FirstCome, 6, 0);
+        assert_eq!(Admission::Free, pool.admit(0, &usage(&[5, 0], &[3, 3])));
+        assert_eq!(
+            Admission::Overwrite,
+            pool.admit(1, &usage(&[6, 0], &[3, 3]))
+        );
+
+        // Two qubits are guaranteed to link 1, which is not using them.
+        let pool = MemoryPool::new(MemoryPolicy::MinGuarantee, 6, 2);
+        assert_eq!(Admission::Free, pool.admit(0, &usage(&[3, 0], &[3, 3])));
+        assert_eq!(
+            Admission::Overwrite,
+            pool.admit(0, &usage(&[4, 0], &[3, 3]))
+        );
+        assert_eq!(Admission::Free, pool.admit(1, &usage(&[4, 0], &[3, 3])));
+        assert_eq!(Admission::Evict(0), pool.admit(1, &usage(&[5, 1], &[3, 3])));
+        assert_eq!(
+            Admission::Overwrite,
+            pool.admit(1, &usage(&[4, 2], &[3, 3]))
+        );
+
+        // Link 0 is entitled to 4 qubits, link 1 to 2 qubits.
+        let pool = MemoryPool::new(MemoryPolicy::WeightedShares, 6, 0);
+        assert_eq!(Admission::Free, pool.admit(1, &usage(&[1, 4], &[2, 1])));
+        assert_eq!(Admission::Evict(1), pool.admit(0, &usage(&[2, 4], &[2, 1])));
+        assert_eq!(
+            Admission::Overwrite,
+            pool.admit(0, &usage(&[4, 2], &[2, 1]))
+        );
+        assert_eq!(
+            Admission::Overwrite,
+            pool.admit(1, &usage(&[4, 2], &[2, 1]))
+        );
+        assert_eq!(Admission::Evict(0), pool.admit(1, &usage(&[6, 0], &[2, 1])));
+        assert_eq!(Admission::Evict(0), pool.admit(1, &usage(&[5, 1], &[2, 1])));
+    }
+}
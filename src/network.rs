@@ -2,12 +2,70 @@
 // SPDX-License-Identifier: MIT
 
 use petgraph::visit::EdgeRef;
-use rand::SeedableRng;
+use rand::{Rng, SeedableRng};
 use rand_distr::Distribution;
 
 use crate::event::*;
 use crate::output::Sample;
 
+/// Emission of EPR pairs by a generator.
+#[derive(Debug)]
+enum Emission {
+    /// One EPR pair at a time, with exponentially distributed inter-arrival
+    /// times.
+    Single(rand_distr::Exp<f64>),
+    /// Batches of EPR pairs at periodic pulses, with the number of pairs
+    /// drawn from the modes attempted.
+    Multiplexed {
+        /// Interval between consecutive pulses, in s.
+        period: f64,
+        /// Binomial r.v. to generate the number of EPR pairs per pulse.
+        modes: rand_distr::Binomial,
+    },
+}
+
+impl Emission {
+    /// Create the emission of a transmitter generating EPR pairs for a
+    /// logical link at the given capacity. A multiplexed transmitter serves
+    /// the logical link with a share of its pulses proportional to the
+    /// capacity.
+    fn new(
+        weight: &crate::physical_topology::NodeWeight,
+        capacity: f64,
+        transmission_prob: f64,
+    ) -> anyhow::Result<Self> {
+        match &weight.multiplexing {
+            Some(multiplexing) => {
+                let share = if weight.tx_capacity() > 0.0 {
+                    capacity / weight.tx_capacity()
+                } else {
+                    0.0
+                };
+                anyhow::ensure!(
+                    multiplexing.pulse_rate > 0.0,
+                    "pulse rate ({}) <= 0",
+                    multiplexing.pulse_rate
+                );
+                anyhow::ensure!(
+                    share > 0.0 && share <= 1.0,
+                    "invalid share of the multiplexed pulses ({})",
+                    share
+                );
+                Ok(Emission::Multiplexed {
+                    period: 1.0 / (multiplexing.pulse_rate * share),
+                    modes: rand_distr::Binomial::new(
+                        multiplexing.num_modes as u64,
+                        multiplexing.mode_success_prob * transmission_prob,
+                    )?,
+                })
+            }
+            None => Ok(Emission::Single(rand_distr::Exp::new(
+                capacity * transmission_prob,
+            )?)),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct EprGenerator {
     /// Identifier of the node that creates the EPR pairs between
@@ -20,16 +78,37 @@ pub struct EprGenerator {
     /// Identifier of the node playing the slave role in the logical
     /// topology link.
     slave_node_id: u32,
-    /// Exponentially distributed r.v. to generate the inter-arrival times.
-    rv: rand_distr::Exp<f64>,
+    /// Emission of the EPR pairs.
+    emission: Emission,
     /// Pseudo-random number generator.
     rng: rand::rngs::StdRng,
+    /// Number of failed elements and OGSs blocked by the weather that
+    /// prevent the generation of EPR pairs.
+    down: u32,
+    /// Number of failed elements, out of `down`.
+    failed: u32,
+    /// True if the next EPR generation has been scheduled.
+    scheduled: bool,
+    /// OGSs whose weather affects the generation of EPR pairs.
+    stations: Vec<u32>,
+    /// False if the transmitter is time-shared and currently serving another
+    /// logical link.
+    active: bool,
+    /// True if the logical link is memoryless.
+    memoryless: bool,
+    /// Propagation latency of the photons from the transmitter to the
+    /// master and slave nodes, in s.
+    propagation: [f64; 2],
 }
 
 impl EprGenerator {
     /// Schedule the next EPR generation.
     fn handle(&mut self) -> Event {
-        let next_epr_generation = self.rv.sample(&mut self.rng);
+        self.scheduled = true;
+        let next_epr_generation = match &self.emission {
+            Emission::Single(rv) => rv.sample(&mut self.rng),
+            Emission::Multiplexed { period, .. } => *period,
+        };
         Event::new(
             next_epr_generation,
             EventType::NetworkEvent(NetworkEventData::EprGenerated(EprGeneratedData {
@@ -39,6 +118,42 @@ impl EprGenerator {
             })),
         )
     }
+
+    /// Return the times when the two halves of an EPR pair are detected by
+    /// the master and slave nodes, relative to its generation, in s.
+    fn detection_times(&mut self, weights: [&crate::physical_topology::NodeWeight; 2]) -> [f64; 2] {
+        let mut detected = self.propagation;
+        for (detected, weight) in detected.iter_mut().zip(weights) {
+            if weight.detection_jitter > 0.0 {
+                *detected += self.rng.gen::<f64>() * weight.detection_jitter;
+            }
+        }
+        detected
+    }
+
+    /// Return the number of EPR pairs generated in the current event.
+    fn batch_size(&mut self) -> u64 {
+        match &self.emission {
+            Emission::Single(_) => 1,
+            Emission::Multiplexed { modes, .. } => modes.sample(&mut self.rng),
+        }
+    }
+
+    /// Return true if the generation of EPR pairs is interrupted by the
+    /// failure of the given element.
+    fn affected_by(&self, target: &crate::faults::FaultTarget) -> bool {
+        match target {
+            crate::faults::FaultTarget::Node(node_id) => {
+                self.tx_node_id == *node_id
+                    || self.master_node_id == *node_id
+                    || self.slave_node_id == *node_id
+            }
+            crate::faults::FaultTarget::Transmitter(node_id) => self.tx_node_id == *node_id,
+            crate::faults::FaultTarget::LogicalLink(master_node_id, slave_node_id) => {
+                self.master_node_id == *master_node_id && self.slave_node_id == *slave_node_id
+            }
+        }
+    }
 }
 
 /// A quantum network is made of a collection of nodes.
@@ -53,6 +168,23 @@ pub struct Network {
     pub physical_topology: crate::physical_topology::PhysicalTopology,
     /// The logical topology.
     pub logical_topology: std::rc::Rc<crate::logical_topology::LogicalTopology>,
+    /// The failures of nodes and links.
+    faults: crate::faults::FaultInjector,
+    /// True if the paths are recomputed when logical links go down or up.
+    reroute: bool,
+    /// The weather and daylight conditions over the OGSs.
+    weather: crate::weather::WeatherModel,
+    /// The classical network used by the control plane, if simulated.
+    classical: Option<crate::classical::ClassicalNetwork>,
+    /// Schedulers of the time-shared transmitters, with the indices of the
+    /// EPR generators served, by ID of the tx node.
+    schedulers:
+        std::collections::HashMap<u32, (crate::time_sharing::TransmitterScheduler, Vec<usize>)>,
+    /// Maximum difference between the detection times of the two halves of
+    /// an EPR pair on a memoryless logical link, in s.
+    coincidence_window: f64,
+    /// Pseudo-random number generator to sample the measurement outcomes.
+    rng: rand::rngs::StdRng,
 }
 
 impl Network {
@@ -61,7 +193,7 @@ impl Network {
         physical_topology: crate::physical_topology::PhysicalTopology,
         logical_topology: std::rc::Rc<crate::logical_topology::LogicalTopology>,
         init_seed: u64,
-    ) -> Self {
+    ) -> anyhow::Result<Self> {
         // Create the nodes.
         let mut nodes = vec![];
 
@@ -76,6 +208,13 @@ impl Network {
                     swapping_success_prob: node_weight.swapping_success_prob,
                     swapping_duration: node_weight.swapping_duration,
                     correction_duration: node_weight.correction_duration,
+                    processing_units: node_weight.processing_units,
+                    read_efficiency: node_weight.read_efficiency,
+                    read_latency: node_weight.read_latency,
+                    transfer_duration: node_weight.transfer_duration,
+                    decay_rate: node_weight.decay_rate,
+                    storage_decay_rate: node_weight.storage_decay_rate,
+                    transfer_fidelity: node_weight.transfer_fidelity,
                 },
                 logical_topology.clone(),
                 init_seed,
@@ -85,6 +224,7 @@ impl Network {
         // Add the NICs and EPR generators.
         let mut epr_generators: std::collections::HashMap<u32, Vec<EprGenerator>> =
             std::collections::HashMap::new();
+        let mut link_fidelities = std::collections::HashMap::new();
         for (cnt, edge) in logical_topology.graph().edge_references().enumerate() {
             let master_node_id = edge.source().index();
             let slave_node_id = edge.target().index();
@@ -103,6 +243,36 @@ impl Network {
 
             let master_node_id = master_node_id as u32;
             let slave_node_id = slave_node_id as u32;
+
+            if let Ok(fidelity) =
+                physical_topology.fidelity(edge.weight().tx, master_node_id, slave_node_id)
+            {
+                link_fidelities.insert((master_node_id, slave_node_id), fidelity);
+            }
+
+            // Only the EPR pairs whose photons are both received count.
+            let transmission_prob = physical_topology
+                .transmission_prob(edge.weight().tx, master_node_id, slave_node_id)
+                .expect("invalid logical edge in the physical topology");
+            let stations = physical_topology
+                .ground_stations(edge.weight().tx, master_node_id, slave_node_id)
+                .expect("invalid logical edge in the physical topology");
+            let tx_weight = physical_topology
+                .graph()
+                .node_weight(edge.weight().tx.into())
+                .expect("cannot find weight of a node in the physical topology");
+            let propagation = [master_node_id, slave_node_id].map(|node_id| {
+                if node_id == edge.weight().tx {
+                    0.0
+                } else {
+                    physical_topology
+                        .graph()
+                        .find_edge(edge.weight().tx.into(), node_id.into())
+                        .map(|e| physical_topology.graph()[e].latency())
+                        .expect("no physical link between the transmitter and an end-point")
+                }
+            });
+
             epr_generators
                 .entry(edge.weight().tx)
                 .or_default()
@@ -110,37 +280,321 @@ impl Network {
                     tx_node_id: edge.weight().tx,
                     master_node_id,
                     slave_node_id,
-                    rv: rand_distr::Exp::new(edge.weight().capacity)
-                        .expect("could not create an expo rv"),
-                    rng: rand::rngs::StdRng::seed_from_u64(init_seed + cnt as u64),
+                    emission: Emission::new(tx_weight, edge.weight().capacity, transmission_prob)?,
+                    rng: rand::rngs::StdRng::seed_from_u64(crate::utils::component_seed(
+                        init_seed,
+                        "generator",
+                        cnt as u64,
+                    )),
+                    down: 0,
+                    failed: 0,
+                    scheduled: false,
+                    stations,
+                    active: true,
+                    memoryless: edge.weight().memoryless,
+                    propagation,
                 });
         }
 
+        // Add the fidelity of the EPR pairs on all the logical links.
+        let link_fidelities = std::rc::Rc::new(link_fidelities);
+        for node in &mut nodes {
+            node.set_link_fidelities(link_fidelities.clone());
+        }
+
+        // The advance reservations are booked on all the logical links.
+        let calendars = std::rc::Rc::new(std::cell::RefCell::new(std::collections::HashMap::new()));
+        for node in &mut nodes {
+            node.set_calendars(calendars.clone());
+        }
+
         let epr_register = crate::epr_register::EprRegister::default();
-        Self {
+        Ok(Self {
             nodes,
             epr_generators,
             epr_register,
             physical_topology,
             logical_topology,
+            faults: crate::faults::FaultInjector::default(),
+            reroute: false,
+            weather: crate::weather::WeatherModel::default(),
+            classical: None,
+            schedulers: std::collections::HashMap::new(),
+            coincidence_window: 0.0,
+            rng: rand::rngs::StdRng::seed_from_u64(crate::utils::component_seed(
+                init_seed, "network", 0,
+            )),
+        })
+    }
+
+    /// Set the random failures and scripted outages of nodes and links.
+    /// If `reroute` is true, then the paths are recomputed to avoid the
+    /// logical links that are down.
+    pub fn set_faults(
+        &mut self,
+        random: &[crate::faults::RandomFaults],
+        scripted: &[crate::faults::ScriptedFault],
+        reroute: bool,
+        seed: u64,
+    ) -> anyhow::Result<()> {
+        self.faults = crate::faults::FaultInjector::new(
+            random,
+            scripted,
+            &self.physical_topology,
+            &self.logical_topology,
+            crate::utils::component_seed(seed, "faults", 0),
+        )?;
+        self.reroute = reroute;
+        Ok(())
+    }
+
+    /// Set the weather and the day/night cycle over the OGSs.
+    pub fn set_weather(
+        &mut self,
+        weather: Option<crate::weather::WeatherParams>,
+        daylight: Option<crate::weather::DaylightParams>,
+        seed: u64,
+    ) -> anyhow::Result<()> {
+        self.weather = crate::weather::WeatherModel::new(
+            weather,
+            daylight,
+            &self.physical_topology.ogs_indices(),
+            crate::utils::component_seed(seed, "weather", 0),
+        )?;
+        Ok(())
+    }
+
+    /// Set the order in which local operations wait for a processing unit
+    /// in all the nodes.
+    pub fn set_queue_discipline(&mut self, discipline: crate::processor::QueueDiscipline) {
+        for node in &mut self.nodes {
+            node.set_queue_discipline(discipline);
+        }
+    }
+
+    /// Set the order in which the pending requests are served in every
+    /// node, with the weights of the traffic classes used by weighted fair
+    /// queuing. The policy of specific nodes can be overridden.
+    pub fn set_scheduling(
+        &mut self,
+        policy: crate::request_scheduler::SchedulingPolicy,
+        weights: &[f64],
+        node_policies: &[crate::request_scheduler::NodePolicy],
+    ) -> anyhow::Result<()> {
+        anyhow::ensure!(
+            weights.iter().all(|weight| *weight > 0.0),
+            "the weights of the traffic classes must be positive"
+        );
+        for node_policy in node_policies {
+            anyhow::ensure!(
+                (node_policy.node_id as usize) < self.nodes.len(),
+                "invalid node {} with a scheduling policy",
+                node_policy.node_id
+            );
+        }
+        for node in &mut self.nodes {
+            let policy = node_policies
+                .iter()
+                .rev()
+                .find(|node_policy| node_policy.node_id == node.node_id())
+                .map_or(policy, |node_policy| node_policy.policy);
+            node.set_scheduling(policy, weights.to_vec());
+        }
+        Ok(())
+    }
+
+    /// Set the policy to accept or reject the requests of the applications
+    /// in every node.
+    pub fn set_admission(
+        &mut self,
+        policy: crate::admission::AdmissionPolicy,
+    ) -> anyhow::Result<()> {
+        policy.validate()?;
+        for node in &mut self.nodes {
+            node.set_admission(policy);
         }
+        Ok(())
+    }
+
+    /// Set the policy to allocate the memory qubits of every node to its
+    /// logical links.
+    pub fn set_memory_policy(&mut self, memory: &crate::user_config::Memory) {
+        for node in &mut self.nodes {
+            let size = self
+                .physical_topology
+                .graph()
+                .node_weight(node.node_id().into())
+                .expect("cannot find weight of a node in the physical topology")
+                .memory_qubits;
+            node.set_memory_policy(memory.policy, size, memory.min_qubits);
+        }
+    }
+
+    /// Time-share the transmitters among the logical links that do not
+    /// reserve one. While serving a logical link, a transmitter generates
+    /// EPR pairs at its full capacity.
+    pub fn set_time_sharing(
+        &mut self,
+        params: &crate::time_sharing::TimeSharingParams,
+    ) -> anyhow::Result<()> {
+        for (tx_node_id, generators) in &mut self.epr_generators {
+            let mut links = vec![];
+            for (ndx, generator) in generators.iter().enumerate() {
+                let edge = self
+                    .logical_topology
+                    .graph()
+                    .find_edge(
+                        generator.master_node_id.into(),
+                        generator.slave_node_id.into(),
+                    )
+                    .expect("EPR generator without a logical link");
+                if self.logical_topology.graph()[edge].time_shared {
+                    links.push(ndx);
+                }
+            }
+            if links.len() < 2 {
+                continue;
+            }
+
+            let tx_weight = self
+                .physical_topology
+                .graph()
+                .node_weight((*tx_node_id).into())
+                .expect("cannot find weight of a node in the physical topology");
+            for (i, ndx) in links.iter().enumerate() {
+                let generator = &mut generators[*ndx];
+                let transmission_prob = self.physical_topology.transmission_prob(
+                    *tx_node_id,
+                    generator.master_node_id,
+                    generator.slave_node_id,
+                )?;
+                generator.emission =
+                    Emission::new(tx_weight, tx_weight.tx_capacity(), transmission_prob)?;
+                generator.active = i == 0;
+            }
+            self.schedulers.insert(
+                *tx_node_id,
+                (
+                    crate::time_sharing::TransmitterScheduler::new(params.clone(), links.len())?,
+                    links,
+                ),
+            );
+        }
+        Ok(())
+    }
+
+    /// Set the maximum difference between the detection times of the two
+    /// halves of an EPR pair on a memoryless logical link, in s.
+    pub fn set_coincidence_window(&mut self, coincidence_window: f64) {
+        self.coincidence_window = coincidence_window;
+    }
+
+    /// Add the storage qubits of the nodes, once all the NICs exist.
+    /// Fail if a NIC would have no storage qubits or if a logical link has
+    /// storage qubits at one end only, since the EPR pairs must be
+    /// transferred into storage at both ends before being used.
+    pub fn set_storage(&mut self) -> anyhow::Result<()> {
+        let storage_qubits = |node_id: u32| {
+            self.physical_topology
+                .graph()
+                .node_weight(node_id.into())
+                .expect("cannot find weight of a node in the physical topology")
+                .storage_qubits
+        };
+        for generator in self.epr_generators.values().flatten() {
+            anyhow::ensure!(
+                (storage_qubits(generator.master_node_id) > 0)
+                    == (storage_qubits(generator.slave_node_id) > 0),
+                "storage qubits at one end only of the link between {} and {}",
+                generator.master_node_id,
+                generator.slave_node_id
+            );
+        }
+        let storage_qubits = self
+            .nodes
+            .iter()
+            .map(|node| storage_qubits(node.node_id()))
+            .collect::<Vec<_>>();
+        for (node, storage_qubits) in self.nodes.iter_mut().zip(storage_qubits) {
+            node.add_storage(storage_qubits)?;
+        }
+        Ok(())
+    }
+
+    /// Simulate hop by hop the classical network used by the control plane.
+    /// Otherwise, the messages only incur the minimum propagation latency.
+    pub fn set_classical(
+        &mut self,
+        params: crate::classical::ClassicalParams,
+        seed: u64,
+    ) -> anyhow::Result<()> {
+        self.classical = Some(crate::classical::ClassicalNetwork::new(
+            params,
+            crate::utils::component_seed(seed, "classical", 0),
+        )?);
+        Ok(())
+    }
+
+    /// Start the measurements that are not collected via samples.
+    pub fn start_measurements(&mut self, now: u64) {
+        self.weather.start_measurements(now);
+    }
+
+    /// Return the samples of the measurements that are collected only at
+    /// the end of the simulation.
+    pub fn finish(&mut self, now: u64) -> Vec<Sample> {
+        self.weather
+            .reachability(now)
+            .into_iter()
+            .map(|(ogs_node_id, fraction)| {
+                Sample::Series(
+                    "ogs-reachable".to_string(),
+                    vec![ogs_node_id.to_string()],
+                    fraction,
+                )
+            })
+            .collect()
     }
 
     fn handle_node_event(&mut self, event: Event) -> (Vec<Event>, Vec<Sample>) {
         let mut event = event;
-        // let now = event.time();
+        let now = event.time();
+        let mut samples = vec![];
+        if let (Some(transfer), Some(classical)) = (&mut event.transfer, &mut self.classical) {
+            if !transfer.done {
+                // Forward the message to the next hop, until it reaches
+                // the destination node.
+                let sent = *transfer.sent.get_or_insert(now);
+                if transfer.hop != transfer.dst_node_id {
+                    let (delay, losses) = classical.forward(
+                        now,
+                        transfer,
+                        &event.event_type,
+                        &self.physical_topology,
+                    );
+                    for _ in 0..losses {
+                        samples.push(Sample::ScalarCount("classical_losses".to_string()));
+                    }
+                    event.reset(crate::utils::to_seconds(delay));
+                    return (vec![event], samples);
+                }
+                transfer.done = true;
+                samples.push(Sample::ScalarAvg(
+                    "classical_delay".to_string(),
+                    crate::utils::to_seconds(now - sent),
+                ));
+            }
+        }
         if let Some(transfer) = &mut event.transfer {
             if !transfer.done {
                 // Re-schedule the same event after adding a latency that takes
                 // into account the classical communication from the source
                 // to the destination node.
                 transfer.done = true;
-                let distance = self
+                let latency = self
                     .physical_topology
-                    .distance(transfer.src_node_id, transfer.dst_node_id)
-                    .expect("cannot compute distance between two nodes");
-
-                let latency = crate::utils::distance_to_latency(distance);
+                    .latency(transfer.src_node_id, transfer.dst_node_id)
+                    .expect("cannot compute latency between two nodes");
                 event.reset(latency);
                 return (vec![event], vec![]);
             }
@@ -148,7 +602,9 @@ impl Network {
         let node_id = event.target_node_id();
         assert!((node_id as usize) < self.nodes.len(), "invalid application event received by a Network object: node_id = {}, number of nodes = {}", node_id, self.nodes.len());
         let node = &mut self.nodes[node_id as usize];
-        node.handle(event)
+        let (events, mut node_samples) = node.handle(event);
+        samples.append(&mut node_samples);
+        (events, samples)
     }
 
     fn handle_network_event(&mut self, event: Event) -> (Vec<Event>, Vec<Sample>) {
@@ -157,7 +613,19 @@ impl Network {
             match data {
                 NetworkEventData::EprGenerated(data) => self.handle_epr_generated(now, data),
                 NetworkEventData::EprNotified(data) => self.handle_epr_notified(now, data),
+                NetworkEventData::EprBatchNotified(data) => {
+                    self.handle_epr_batch_notified(now, data)
+                }
+                NetworkEventData::EprStored(data) => self.handle_epr_stored(now, data),
                 NetworkEventData::EprConsume(data) => self.handle_epr_consume(now, data),
+                NetworkEventData::EprDiscard(data) => self.handle_epr_discard(data),
+                NetworkEventData::EprSwap(data) => self.handle_epr_swap(now, data),
+                NetworkEventData::EprDetected(data) => self.handle_epr_detected(data),
+                NetworkEventData::EprMeasure(data) => self.handle_epr_measure(now, data),
+                NetworkEventData::Failure(data) => self.handle_failure(now, data),
+                NetworkEventData::Repair(data) => self.handle_repair(now, data),
+                NetworkEventData::WeatherChange(data) => self.handle_weather_change(now, data),
+                NetworkEventData::TransmitterSlot(data) => self.handle_transmitter_slot(data),
             }
         } else {
             panic!(
@@ -180,47 +648,159 @@ impl Network {
             if generator.master_node_id == data.master_node_id
                 && generator.slave_node_id == data.slave_node_id
             {
+                // Stop the generation of EPR pairs until all the failed
+                // elements are repaired or the transmitter serves again
+                // this logical link.
+                if generator.down > 0 || !generator.active {
+                    generator.scheduled = false;
+                    return (vec![], vec![]);
+                }
+
                 let mut events = vec![];
                 let mut samples = vec![];
 
-                // Create a new EPR pair.
-                if let Ok(fidelity) = self.physical_topology.fidelity(
-                    data.tx_node_id,
-                    data.master_node_id,
-                    data.slave_node_id,
-                ) {
+                // Create the new EPR pairs, more than one only with
+                // multiplexed transmitters.
+                let batch_size = generator.batch_size();
+                if matches!(generator.emission, Emission::Multiplexed { .. }) {
+                    samples.push(Sample::ScalarAvg(
+                        "epr_batch_size".to_string(),
+                        batch_size as f64,
+                    ));
+                }
+                let weights = [data.master_node_id, data.slave_node_id].map(|node_id| {
+                    self.physical_topology
+                        .graph()
+                        .node_weight(node_id.into())
+                        .expect("cannot find weight of a node in the physical topology")
+                });
+                let mut epr_pair_ids = vec![];
+                for _ in 0..batch_size {
+                    // The EPR pair may be lost because of clouds or daylight,
+                    // which also add background noise.
+                    let (success_factor, visibility) =
+                        self.weather.conditions(now, &generator.stations);
+                    let lost = success_factor < 1.0 && generator.rng.gen::<f64>() >= success_factor;
+
+                    let mut fidelity = match (
+                        lost,
+                        self.physical_topology.fidelity(
+                            data.tx_node_id,
+                            data.master_node_id,
+                            data.slave_node_id,
+                        ),
+                    ) {
+                        (false, Ok(fidelity)) => fidelity,
+                        _ => continue,
+                    };
+                    if visibility < 1.0 {
+                        fidelity = 0.25 + visibility * (fidelity - 0.25);
+                    }
                     samples.push(Sample::Series(
                         "gen_fidelity".to_string(),
                         vec![data.tx_node_id.to_string()],
                         fidelity,
                     ));
 
-                    let epr_pair_id = self.epr_register.new_epr_pair(
+                    // On a memoryless logical link, the EPR pair is usable
+                    // only if both halves are detected in coincidence, which
+                    // requires the difference between the propagation
+                    // latencies to the two end-points to fit the window.
+                    if generator.memoryless {
+                        let detected = generator.detection_times(weights);
+                        if (detected[0] - detected[1]).abs() > self.coincidence_window {
+                            samples.push(Sample::ScalarCount("coincidence_misses".to_string()));
+                            continue;
+                        }
+                        samples.push(Sample::ScalarCount("coincidences".to_string()));
+
+                        let epr_pair_id = self.epr_register.new_epr_pair(
+                            data.master_node_id,
+                            data.slave_node_id,
+                            now,
+                            fidelity,
+                        );
+                        for (detected, this_node_id, peer_node_id, role) in [
+                            (
+                                detected[0],
+                                data.master_node_id,
+                                data.slave_node_id,
+                                crate::nic::Role::Master,
+                            ),
+                            (
+                                detected[1],
+                                data.slave_node_id,
+                                data.master_node_id,
+                                crate::nic::Role::Slave,
+                            ),
+                        ] {
+                            events.push(Event::new(
+                                detected,
+                                EventType::NetworkEvent(NetworkEventData::EprDetected(
+                                    EprNotifiedData {
+                                        this_node_id,
+                                        peer_node_id,
+                                        role,
+                                        epr_pair_id,
+                                    },
+                                )),
+                            ));
+                        }
+                        continue;
+                    }
+
+                    // Both qubits must be stored in the memories, otherwise
+                    // the EPR pair is lost.
+                    if weights.iter().any(|weight| {
+                        weight.write_efficiency < 1.0
+                            && generator.rng.gen::<f64>() >= weight.write_efficiency
+                    }) {
+                        samples.push(Sample::ScalarCount("memory_write_failures".to_string()));
+                        continue;
+                    }
+
+                    epr_pair_ids.push(self.epr_register.new_epr_pair(
                         data.master_node_id,
                         data.slave_node_id,
                         now,
                         fidelity,
-                    );
-
-                    // Add events notifying the creation of the EPR pair
-                    // on the master/slave nodes.
-                    events.push(Event::new(
-                        0.0_f64,
-                        EventType::NetworkEvent(NetworkEventData::EprNotified(EprNotifiedData {
-                            this_node_id: data.master_node_id,
-                            peer_node_id: data.slave_node_id,
-                            role: crate::nic::Role::Master,
-                            epr_pair_id,
-                        })),
                     ));
+                }
+
+                // Add events notifying the creation of the EPR pairs
+                // on the master/slave nodes, once stored.
+                for (weight, this_node_id, peer_node_id, role) in [
+                    (
+                        weights[0],
+                        data.master_node_id,
+                        data.slave_node_id,
+                        crate::nic::Role::Master,
+                    ),
+                    (
+                        weights[1],
+                        data.slave_node_id,
+                        data.master_node_id,
+                        crate::nic::Role::Slave,
+                    ),
+                ] {
+                    let notification = match epr_pair_ids.len() {
+                        0 => continue,
+                        1 => NetworkEventData::EprNotified(EprNotifiedData {
+                            this_node_id,
+                            peer_node_id,
+                            role,
+                            epr_pair_id: epr_pair_ids[0],
+                        }),
+                        _ => NetworkEventData::EprBatchNotified(EprBatchNotifiedData {
+                            this_node_id,
+                            peer_node_id,
+                            role,
+                            epr_pair_ids: epr_pair_ids.clone(),
+                        }),
+                    };
                     events.push(Event::new(
-                        0.0_f64,
-                        EventType::NetworkEvent(NetworkEventData::EprNotified(EprNotifiedData {
-                            this_node_id: data.slave_node_id,
-                            peer_node_id: data.master_node_id,
-                            role: crate::nic::Role::Slave,
-                            epr_pair_id,
-                        })),
+                        weight.write_latency,
+                        EventType::NetworkEvent(notification),
                     ));
                 }
 
@@ -263,15 +843,70 @@ impl Network {
         )
     }
 
+    fn handle_epr_batch_notified(
+        &mut self,
+        now: u64,
+        data: EprBatchNotifiedData,
+    ) -> (Vec<Event>, Vec<Sample>) {
+        assert!(
+            data.this_node_id < self.nodes.len() as u32,
+            "invalid node identifier {} with {} nodes",
+            data.this_node_id,
+            self.nodes.len()
+        );
+
+        self.nodes[data.this_node_id as usize].epr_batch_established(
+            now,
+            data.peer_node_id,
+            data.role,
+            &data.epr_pair_ids,
+        )
+    }
+
     /// Consume the half EPR and compute its fidelity.
+    fn handle_epr_stored(&mut self, now: u64, data: EprNotifiedData) -> (Vec<Event>, Vec<Sample>) {
+        let (stored, events, mut samples) = self.nodes[data.this_node_id as usize].epr_stored(
+            now,
+            data.peer_node_id,
+            data.role,
+            data.epr_pair_id,
+        );
+        if stored {
+            let transfer_fidelity = self
+                .physical_topology
+                .graph()
+                .node_weight(data.this_node_id.into())
+                .expect("cannot find weight of a node in the physical topology")
+                .transfer_fidelity;
+            if transfer_fidelity < 1.0 {
+                self.epr_register
+                    .depolarize(data.epr_pair_id, transfer_fidelity);
+            }
+        } else {
+            // The EPR pair is dropped at both ends, so that it is never
+            // stored at one end only, unless already done when the
+            // transfer failed at the other end.
+            let holders = self.epr_register.remove(data.epr_pair_id);
+            if !holders.is_empty() {
+                for (node_id, local_pair_id) in holders {
+                    self.nodes[node_id as usize].release(local_pair_id);
+                }
+                samples.push(Sample::ScalarCount("storage_drops".to_string()));
+            }
+        }
+        (events, samples)
+    }
+
     fn handle_epr_consume(&mut self, now: u64, data: EprConsumeData) -> (Vec<Event>, Vec<Sample>) {
         assert!(data.consume_node_id <= self.nodes.len() as u32);
 
-        let fidelity = if let Some(cell) = self.nodes[data.consume_node_id as usize].consume(
+        let node = &mut self.nodes[data.consume_node_id as usize];
+        let fidelity = if let Some(cell) = node.consume(
             data.memory_cell_id.neighbor_node_id,
             &data.memory_cell_id.role,
             data.memory_cell_id.local_pair_id,
         ) {
+            let retrieved = node.read();
             if let Some(weight) = self
                 .physical_topology
                 .graph()
@@ -282,11 +917,15 @@ impl Network {
                     .consume(cell.local_pair_id, data.consume_node_id)
                 {
                     assert!(now >= updated);
-                    crate::utils::fidelity(
-                        fidelity,
-                        weight.decay_rate,
-                        crate::utils::to_seconds(now - updated),
-                    )
+                    if !retrieved {
+                        // The qubit could not be retrieved from the memory.
+                        log::debug!("failed to read EPR at {data:?}");
+                        return (
+                            self.epr_lost(cell.local_pair_id, data),
+                            vec![Sample::ScalarCount("memory_read_failures".to_string())],
+                        );
+                    }
+                    Self::decayed(weight, &cell, updated, fidelity, now, weight.read_latency)
                 } else {
                     panic!("EPR pair not found {data:?}");
                 }
@@ -294,21 +933,514 @@ impl Network {
                 panic!("no such node {data:?}");
             }
         } else {
-            panic!("no EPR found at {data:?}");
+            // The memory cell has been flushed by a fault.
+            log::debug!("no EPR found at {data:?}");
+            return (vec![], vec![]);
         };
 
         (
             vec![],
-            vec![Sample::Series(
-                "fidelity".to_string(),
-                vec![
-                    data.req_app_node_id.to_string(),
-                    data.req_app_port.to_string(),
-                ],
+            Self::fidelity_samples(
+                data.req_app_node_id,
+                data.req_app_port,
                 fidelity,
-            )],
+                data.epr.min_fidelity,
+                data.epr.class,
+            ),
         )
     }
+
+    /// Return the fidelity of an EPR pair whose qubit is held in a memory
+    /// cell of a node, which decoheres from `updated` time until it is read
+    /// from the memory, i.e., `read_latency` after `now`, at a different rate
+    /// once transferred into storage.
+    fn decayed(
+        weight: &crate::physical_topology::NodeWeight,
+        cell: &crate::nic::MemoryCellData,
+        updated: u64,
+        fidelity: f64,
+        now: u64,
+        read_latency: f64,
+    ) -> f64 {
+        match cell.stored {
+            None => crate::utils::fidelity(
+                fidelity,
+                weight.decay_rate,
+                crate::utils::to_seconds(now - updated) + read_latency,
+            ),
+            Some(stored) => crate::utils::fidelity(
+                crate::utils::fidelity(
+                    fidelity,
+                    weight.decay_rate,
+                    crate::utils::to_seconds(stored - updated),
+                ),
+                weight.storage_decay_rate,
+                crate::utils::to_seconds(now - stored) + read_latency,
+            ),
+        }
+    }
+
+    /// Swap the half EPR pairs held by an intermediate node towards the
+    /// previous and next hops of a path, whose qubits have been already
+    /// retrieved from the memory, into one EPR pair between the other
+    /// end-points. Entanglement swapping of Werner states multiplies their
+    /// parameters.
+    ///
+    /// If either memory cell has been flushed by a fault meanwhile, then the
+    /// other half EPR pair is discarded.
+    fn handle_epr_swap(&mut self, now: u64, data: EprSwapData) -> (Vec<Event>, Vec<Sample>) {
+        let node = &mut self.nodes[data.node_id as usize];
+        let cells = [&data.prev_memory_cell_id, &data.next_memory_cell_id].map(|memory_cell| {
+            node.consume(
+                memory_cell.neighbor_node_id,
+                &memory_cell.role,
+                memory_cell.local_pair_id,
+            )
+        });
+        let [Some(prev), Some(next)] = &cells else {
+            log::debug!("no EPR found at {data:?}");
+            for cell in cells.iter().flatten() {
+                self.epr_register.consume(cell.local_pair_id, data.node_id);
+            }
+            return (vec![], vec![]);
+        };
+
+        let weight = self
+            .physical_topology
+            .graph()
+            .node_weight(data.node_id.into())
+            .expect("cannot find weight of a node in the physical topology");
+        let werner = |cell: &crate::nic::MemoryCellData| {
+            let (updated, fidelity) = self
+                .epr_register
+                .fidelity(cell.local_pair_id)
+                .unwrap_or((now, 0.25));
+            (4.0 * Self::decayed(weight, cell, updated, fidelity, now, 0.0) - 1.0) / 3.0
+        };
+        let fidelity = (3.0 * werner(prev) * werner(next) + 1.0) / 4.0;
+        self.epr_register.swap(
+            data.node_id,
+            prev.local_pair_id,
+            next.local_pair_id,
+            now,
+            fidelity,
+        );
+        (vec![], vec![])
+    }
+
+    /// Release the other half of an EPR pair whose qubit could not be read
+    /// from the memory, if not consumed yet, and notify the loss to both
+    /// the applications.
+    fn epr_lost(&mut self, epr_pair_id: u64, data: EprConsumeData) -> Vec<Event> {
+        for (node_id, local_pair_id) in self.epr_register.remove(epr_pair_id) {
+            self.nodes[node_id as usize].release(local_pair_id);
+        }
+
+        let is_source = data.consume_node_id == data.epr.source_node_id;
+        let peer_node_id = if is_source {
+            data.epr.target_node_id
+        } else {
+            data.epr.source_node_id
+        };
+        vec![
+            Event::new(
+                0.0,
+                EventType::AppEvent(AppEventData::EprLost(data.epr.clone(), is_source)),
+            ),
+            Event::new_transfer(
+                EventType::AppEvent(AppEventData::EprLost(data.epr, !is_source)),
+                data.consume_node_id,
+                peer_node_id,
+            ),
+        ]
+    }
+
+    /// Return the samples of the fidelity of an EPR pair consumed by an
+    /// application, also reporting if it met the minimum required, if any,
+    /// and the fidelity of its traffic class.
+    fn fidelity_samples(
+        req_app_node_id: u32,
+        req_app_port: u16,
+        fidelity: f64,
+        min_fidelity: Option<MinFidelity>,
+        class: u32,
+    ) -> Vec<Sample> {
+        let mut samples = vec![
+            Sample::Series(
+                "fidelity".to_string(),
+                vec![req_app_node_id.to_string(), req_app_port.to_string()],
+                fidelity,
+            ),
+            Sample::Series(
+                "class-fidelity".to_string(),
+                vec![class.to_string()],
+                fidelity,
+            ),
+        ];
+        if let Some(min_fidelity) = min_fidelity {
+            let met = if fidelity >= min_fidelity.0 { 1.0 } else { 0.0 };
+            samples.push(Sample::ScalarAvg("fidelity_met".to_string(), met));
+        }
+        samples
+    }
+
+    /// Free the memory cell of half of an EPR pair discarded by a node, which
+    /// then no longer holds it in the EPR register.
+    fn handle_epr_discard(&mut self, data: EprDiscardData) -> (Vec<Event>, Vec<Sample>) {
+        self.nodes[data.node_id as usize].release(data.epr_pair_id);
+        self.epr_register.consume(data.epr_pair_id, data.node_id);
+        (vec![], vec![])
+    }
+
+    /// Deliver half of an EPR pair detected on a memoryless logical link to
+    /// the application that measures it, if any, otherwise the half is lost.
+    fn handle_epr_detected(&mut self, data: EprNotifiedData) -> (Vec<Event>, Vec<Sample>) {
+        match self.nodes[data.this_node_id as usize]
+            .epr_detected(data.peer_node_id, data.epr_pair_id)
+        {
+            Some(event) => (vec![event], vec![]),
+            None => {
+                self.epr_register
+                    .consume(data.epr_pair_id, data.this_node_id);
+                (vec![], vec![])
+            }
+        }
+    }
+
+    /// Measure half of an EPR pair, detected on a memoryless logical link or
+    /// established by a measure-directly request. In the latter case, sample
+    /// the outcome and notify it to the application: if the other half has
+    /// been measured in the same basis, then the outcomes are the same with
+    /// probability (2F + 1) / 3, where F is the fidelity.
+    fn handle_epr_measure(&mut self, now: u64, data: EprMeasureData) -> (Vec<Event>, Vec<Sample>) {
+        let other = self.epr_register.outcome(data.epr_pair_id);
+        let fidelity = match self
+            .epr_register
+            .consume(data.epr_pair_id, data.measure_node_id)
+        {
+            Some((updated, fidelity)) => {
+                let decay_rate = self
+                    .physical_topology
+                    .graph()
+                    .node_weight(data.measure_node_id.into())
+                    .expect("cannot find weight of a node in the physical topology")
+                    .decay_rate;
+                crate::utils::fidelity(
+                    fidelity,
+                    decay_rate,
+                    crate::utils::to_seconds(now - updated),
+                )
+            }
+            None => {
+                // The memory cell has been flushed by a fault.
+                log::debug!("no EPR found at {data:?}");
+                return match data.response {
+                    Some(response) => (
+                        vec![Event::new(
+                            0.0,
+                            EventType::AppEvent(AppEventData::EprResponse(response)),
+                        )],
+                        vec![],
+                    ),
+                    None => (vec![], vec![]),
+                };
+            }
+        };
+
+        let mut events = vec![];
+        let mut samples = Self::fidelity_samples(
+            data.req_app_node_id,
+            data.req_app_port,
+            fidelity,
+            data.response
+                .as_ref()
+                .and_then(|response| response.epr.min_fidelity),
+            data.response
+                .as_ref()
+                .map_or(0, |response| response.epr.class),
+        );
+
+        if let Some(mut response) = data.response {
+            let basis = match response.epr.request_type {
+                RequestType::MeasureDirectly(Basis::Random) => {
+                    if self.rng.gen_bool(0.5) {
+                        Basis::Z
+                    } else {
+                        Basis::X
+                    }
+                }
+                RequestType::MeasureDirectly(basis) => basis,
+                RequestType::CreateAndKeep => panic!("measuring a create-and-keep EPR pair"),
+            };
+            let outcome = match other {
+                Some(other) if other.basis == basis => {
+                    let error = !self
+                        .rng
+                        .gen_bool(((2.0 * fidelity + 1.0) / 3.0).clamp(0.0, 1.0));
+                    samples.push(Sample::ScalarAvg(
+                        "qber".to_string(),
+                        if error { 1.0 } else { 0.0 },
+                    ));
+                    MeasureOutcome {
+                        basis,
+                        bit: other.bit ^ error,
+                    }
+                }
+                _ => MeasureOutcome {
+                    basis,
+                    bit: self.rng.gen(),
+                },
+            };
+            self.epr_register.set_outcome(data.epr_pair_id, outcome);
+            response.outcome = Some(outcome);
+            events.push(Event::new(
+                0.0,
+                EventType::AppEvent(AppEventData::EprResponse(response)),
+            ));
+        }
+
+        (events, samples)
+    }
+}
+
+impl Network {
+    /// Handle the failure of an element of the network.
+    ///
+    /// If the element was up:
+    /// - stop the EPR generators affected
+    /// - flush the memory cells of the NICs affected
+    /// - fail the requests waiting for an ES through the element
+    fn handle_failure(&mut self, now: u64, data: FaultData) -> (Vec<Event>, Vec<Sample>) {
+        let (went_down, mut events) = self.faults.fail(now, &data);
+        let mut samples = vec![];
+        if !went_down {
+            return (events, samples);
+        }
+        samples.push(Sample::ScalarCount("failures".to_string()));
+
+        events.append(&mut self.set_generators_down(|x| x.affected_by(&data.target), true, true));
+
+        let mut flushed = vec![];
+        match data.target {
+            crate::faults::FaultTarget::Node(node_id) => {
+                for node in &mut self.nodes {
+                    let peer_node_id = if node.node_id() == node_id {
+                        None
+                    } else {
+                        Some(node_id)
+                    };
+                    for cell in node.flush_nics(peer_node_id) {
+                        flushed.push((node.node_id(), cell));
+                    }
+                }
+            }
+            crate::faults::FaultTarget::Transmitter(_) => {}
+            crate::faults::FaultTarget::LogicalLink(master_node_id, slave_node_id) => {
+                for cell in self.nodes[master_node_id as usize]
+                    .flush_nic(slave_node_id, &crate::nic::Role::Master)
+                {
+                    flushed.push((master_node_id, cell));
+                }
+                for cell in self.nodes[slave_node_id as usize]
+                    .flush_nic(master_node_id, &crate::nic::Role::Slave)
+                {
+                    flushed.push((slave_node_id, cell));
+                }
+            }
+        }
+        for (node_id, cell) in flushed {
+            self.epr_register.consume(cell.local_pair_id, node_id);
+        }
+
+        let affected = |path: &[u32]| match data.target {
+            crate::faults::FaultTarget::Node(node_id) => path.contains(&node_id),
+            crate::faults::FaultTarget::Transmitter(_) => false,
+            crate::faults::FaultTarget::LogicalLink(master_node_id, slave_node_id) => path
+                .windows(2)
+                .any(|x| x[0] == master_node_id && x[1] == slave_node_id),
+        };
+        for node in &self.nodes {
+            for event in node.fail_requests(affected) {
+                samples.push(Sample::ScalarCount("es_remote_failed".to_string()));
+                events.push(event);
+            }
+        }
+
+        self.update_availability(&mut samples);
+        (events, samples)
+    }
+
+    /// Handle the repair of an element of the network.
+    ///
+    /// If the element is up again, restart the EPR generators that are
+    /// not affected by other failures.
+    fn handle_repair(&mut self, now: u64, data: FaultData) -> (Vec<Event>, Vec<Sample>) {
+        let (downtime, mut events) = self.faults.repair(now, &data);
+        let mut samples = vec![];
+        if let Some(downtime) = downtime {
+            samples.push(Sample::ScalarAvg(
+                "recovery_time".to_string(),
+                crate::utils::to_seconds(downtime),
+            ));
+
+            events.append(&mut self.set_generators_down(
+                |x| x.affected_by(&data.target),
+                false,
+                true,
+            ));
+
+            self.update_availability(&mut samples);
+        }
+        (events, samples)
+    }
+
+    /// Handle the change of weather over an OGS.
+    ///
+    /// The EPR generators through the OGS are stopped while its optical
+    /// links are blocked, but the memory cells are not affected.
+    fn handle_weather_change(&mut self, now: u64, data: WeatherData) -> (Vec<Event>, Vec<Sample>) {
+        let (prev, new, next) = self.weather.change(now, &data);
+        let mut events = next.into_iter().collect::<Vec<Event>>();
+        let mut samples = vec![];
+        let blocked = crate::weather::WeatherState::Blocked;
+        if (prev == blocked) != (new == blocked) {
+            events.append(&mut self.set_generators_down(
+                |x| x.stations.contains(&data.ogs_node_id),
+                new == blocked,
+                false,
+            ));
+            samples.push(Sample::ScalarTimeAvg(
+                "ogs_reachable".to_string(),
+                self.weather.reachable(),
+            ));
+            self.update_availability(&mut samples);
+        }
+        (events, samples)
+    }
+
+    /// Add (if `down` is true) or remove an outage to the EPR generators
+    /// affected, which is due to a fault if `fault` is true, or to the
+    /// weather otherwise, and return the events to restart those that are
+    /// up again.
+    fn set_generators_down(
+        &mut self,
+        affected: impl Fn(&EprGenerator) -> bool,
+        down: bool,
+        fault: bool,
+    ) -> Vec<Event> {
+        let mut events = vec![];
+        let mut generators = self.epr_generators.iter_mut().collect::<Vec<_>>();
+        generators.sort_by_key(|(tx_node_id, _)| **tx_node_id);
+        for generator in generators.into_iter().flat_map(|(_, x)| x) {
+            if !affected(generator) {
+                continue;
+            }
+            if down {
+                generator.down += 1;
+                generator.failed += fault as u32;
+            } else {
+                generator.down -= 1;
+                generator.failed -= fault as u32;
+                if generator.down == 0 && generator.active && !generator.scheduled {
+                    events.push(generator.handle());
+                }
+            }
+        }
+        events
+    }
+
+    /// Handle the start or end of a slot of a time-shared transmitter.
+    fn handle_transmitter_slot(&mut self, data: TransmitterData) -> (Vec<Event>, Vec<Sample>) {
+        let (scheduler, links) = self
+            .schedulers
+            .get_mut(&data.tx_node_id)
+            .expect("unknown time-shared transmitter");
+        let generators = self
+            .epr_generators
+            .get_mut(&data.tx_node_id)
+            .expect("unknown tx node id");
+        let slot = |start: bool, delay: f64| {
+            Event::new(
+                delay,
+                EventType::NetworkEvent(NetworkEventData::TransmitterSlot(TransmitterData {
+                    tx_node_id: data.tx_node_id,
+                    start,
+                })),
+            )
+        };
+
+        let mut events = vec![];
+        let mut samples = vec![];
+        if data.start {
+            let generator = &mut generators[links[scheduler.current()]];
+            generator.active = true;
+            if generator.down == 0 && !generator.scheduled {
+                events.push(generator.handle());
+            }
+            events.push(slot(false, scheduler.dwell()));
+        } else {
+            // The demand is the number of requests queued at the master.
+            let demand = links
+                .iter()
+                .map(|ndx| {
+                    let generator = &generators[*ndx];
+                    self.nodes[generator.master_node_id as usize]
+                        .num_queued(generator.slave_node_id)
+                })
+                .collect::<Vec<usize>>();
+            let prev = scheduler.current();
+            let switching_time = scheduler.next(&demand);
+            if scheduler.current() == prev {
+                events.push(slot(false, scheduler.dwell()));
+            } else {
+                generators[links[prev]].active = false;
+                events.push(slot(true, switching_time));
+                samples.push(Sample::ScalarCount("tx_switches".to_string()));
+            }
+        }
+        (events, samples)
+    }
+
+    /// Add the fractions of EPR generators that are not affected by faults
+    /// and by the weather to the samples, and recompute the paths, if
+    /// needed.
+    fn update_availability(&mut self, samples: &mut Vec<Sample>) {
+        let mut num_generators = 0;
+        let mut excluded = std::collections::HashSet::new();
+        let mut failed = std::collections::HashSet::new();
+        let mut blocked = std::collections::HashSet::new();
+        for generator in self.epr_generators.values().flatten() {
+            num_generators += 1;
+            let link = (generator.master_node_id, generator.slave_node_id);
+            if generator.down > 0 {
+                excluded.insert(link);
+            }
+            if generator.failed > 0 {
+                failed.insert(link);
+            }
+            if generator.down > generator.failed {
+                blocked.insert(link);
+            }
+        }
+        if num_generators > 0 {
+            samples.push(Sample::ScalarTimeAvg(
+                "availability".to_string(),
+                1.0 - failed.len() as f64 / num_generators as f64,
+            ));
+            samples.push(Sample::ScalarTimeAvg(
+                "weather_availability".to_string(),
+                1.0 - blocked.len() as f64 / num_generators as f64,
+            ));
+        }
+        if self.reroute {
+            std::rc::Rc::make_mut(&mut self.logical_topology)
+                .reroute(&excluded)
+                .expect("cannot reroute in the logical topology");
+            for node in &mut self.nodes {
+                node.set_logical_topology(self.logical_topology.clone());
+            }
+        }
+    }
 }
 
 impl EventHandler for Network {
@@ -327,16 +1459,34 @@ impl EventHandler for Network {
     fn initial(&mut self) -> Vec<Event> {
         let mut events = vec![];
 
-        for generators in self.epr_generators.values_mut() {
-            for generator in generators {
+        // The maps are visited in order of tx node, so that the events with
+        // the same time are always handled in the same order.
+        let mut generators = self.epr_generators.iter_mut().collect::<Vec<_>>();
+        generators.sort_by_key(|(tx_node_id, _)| **tx_node_id);
+        for generator in generators.into_iter().flat_map(|(_, x)| x) {
+            if generator.active {
                 events.push(generator.handle());
             }
         }
+        let mut schedulers = self.schedulers.iter().collect::<Vec<_>>();
+        schedulers.sort_by_key(|(tx_node_id, _)| **tx_node_id);
+        for (tx_node_id, (scheduler, _links)) in schedulers {
+            events.push(Event::new(
+                scheduler.dwell(),
+                EventType::NetworkEvent(NetworkEventData::TransmitterSlot(TransmitterData {
+                    tx_node_id: *tx_node_id,
+                    start: false,
+                })),
+            ));
+        }
 
         for node in &mut self.nodes {
             events.append(&mut node.initial());
         }
 
+        events.append(&mut self.faults.initial());
+        events.append(&mut self.weather.initial());
+
         events
     }
 }
@@ -346,20 +1496,85 @@ mod tests {
     use rand::SeedableRng;
     use rand_distr::Distribution;
 
-    use super::Network;
+    use super::{Emission, Network};
 
     #[test]
     fn test_network_from_logical_topology() {
         let (physical_topology, logical_topology) = crate::tests::logical_topology_2_2();
-        let network = Network::new(physical_topology, std::rc::Rc::new(logical_topology), 42);
+        let network =
+            Network::new(physical_topology, std::rc::Rc::new(logical_topology), 42).unwrap();
         assert_eq!(10, network.nodes.len());
     }
 
+    #[test]
+    fn test_network_multiplexed_emission() {
+        let mut weight = crate::physical_topology::NodeWeight {
+            multiplexing: Some(crate::physical_topology::MultiplexedSource {
+                pulse_rate: 1e3,
+                num_modes: 50,
+                mode_success_prob: 0.1,
+            }),
+            ..Default::default()
+        };
+        let capacity = weight.tx_capacity();
+        match Emission::new(&weight, capacity / 2.0, 1.0).unwrap() {
+            Emission::Multiplexed { period, .. } => {
+                assert_float_eq::assert_f64_near!(2e-3, period)
+            }
+            Emission::Single(_) => panic!("multiplexed transmitter with single emission"),
+        }
+
+        // The share of the pulses must be in (0, 1].
+        assert!(Emission::new(&weight, 0.0, 1.0).is_err());
+        assert!(Emission::new(&weight, 2.0 * capacity, 1.0).is_err());
+
+        weight.multiplexing.as_mut().unwrap().pulse_rate = 0.0;
+        assert!(Emission::new(&weight, capacity, 1.0).is_err());
+    }
+
+    #[test]
+    fn test_network_detection_times() {
+        let (physical_topology, logical_topology) = crate::tests::logical_topology_2_2();
+        let mut network =
+            Network::new(physical_topology, std::rc::Rc::new(logical_topology), 42).unwrap();
+
+        // The photons propagate from the transmitter to the end-points.
+        let latency = crate::utils::distance_to_latency(1.0);
+        for generator in network.epr_generators.values().flatten() {
+            for (node_id, propagation) in [generator.master_node_id, generator.slave_node_id]
+                .into_iter()
+                .zip(generator.propagation)
+            {
+                if node_id == generator.tx_node_id {
+                    assert_eq!(0.0, propagation);
+                } else {
+                    assert_eq!(latency, propagation);
+                }
+            }
+        }
+        let generator = network
+            .epr_generators
+            .values_mut()
+            .flatten()
+            .next()
+            .unwrap();
+
+        // The difference between the detection times depends on the
+        // difference between the propagation latencies.
+        let mut weight = crate::physical_topology::NodeWeight::default_ogs();
+        weight.detection_jitter = 1e-9;
+        generator.propagation = [1e-3, 2e-3];
+        for _ in 0..100 {
+            let detected = generator.detection_times([&weight, &weight]);
+            assert!((detected[1] - detected[0] - 1e-3).abs() <= 1e-9);
+        }
+    }
+
     #[test]
     fn test_expo_rv() {
         let mut rng = rand::rngs::StdRng::seed_from_u64(42);
         let rv = rand_distr::Exp::new(10.0).unwrap();
-        let mut sum = 0.0;
+        let mut sum = 0.0_f64;
         for i in 0..100 {
             let x = rv.sample(&mut rng);
             sum += x;
@@ -369,6 +1584,6 @@ mod tests {
                 print!("\t{}", x);
             }
         }
-        assert_float_eq::assert_f64_near!(0.1, (((sum / 100.0) * 10.0) as f64).round() / 10.0);
+        assert_float_eq::assert_f64_near!(0.1, ((sum / 100.0) * 10.0).round() / 10.0);
     }
 }
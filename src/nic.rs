@@ -5,6 +5,8 @@
 pub struct MemoryCellData {
     pub created: u64,
     pub local_pair_id: u64,
+    /// Time when the qubit was transferred into storage, if it was.
+    pub stored: Option<u64>,
 }
 
 impl PartialOrd for MemoryCellData {
@@ -30,6 +32,7 @@ impl MemoryCell {
         MemoryCell::Valid(MemoryCellData {
             created,
             local_pair_id: identifier,
+            stored: None,
         })
     }
 
@@ -153,8 +156,13 @@ impl std::fmt::Display for Role {
 pub struct Nic {
     /// Role of this NIC.
     role: Role,
-    /// Quantum memory cells assigned to this NIC.
+    /// Quantum memory cells assigned to this NIC, used for communication.
     memory_cells: Vec<MemoryCell>,
+    /// Storage qubits assigned to this NIC. If not empty, only the EPR pairs
+    /// transferred into storage can be used.
+    storage_cells: Vec<MemoryCell>,
+    /// Number of memory cells assigned when created.
+    assigned: u32,
 }
 
 impl std::fmt::Display for Nic {
@@ -179,7 +187,80 @@ impl Nic {
         for _ in 0..num_qubits {
             memory_cells.push(MemoryCell::Empty);
         }
-        Self { role, memory_cells }
+        Self {
+            role,
+            memory_cells,
+            storage_cells: vec![],
+            assigned: num_qubits,
+        }
+    }
+
+    /// Add empty storage cells.
+    pub fn add_storage_cells(&mut self, num_qubits: u32) {
+        for _ in 0..num_qubits {
+            self.storage_cells.push(MemoryCell::Empty);
+        }
+    }
+
+    /// Return true if the NIC has storage cells.
+    pub fn has_storage(&self) -> bool {
+        !self.storage_cells.is_empty()
+    }
+
+    /// Transfer a valid EPR pair from a communication cell into an empty
+    /// storage cell or, if not available, overwrite the oldest valid one.
+    /// Return false if the EPR pair is not found or there is no storage
+    /// cell that can be overwritten.
+    pub fn store(&mut self, now: u64, local_pair_id: u64) -> bool {
+        let Some(from) = self
+            .memory_cells
+            .iter()
+            .position(|cell| cell.is_valid() && cell.local_pair_id() == Some(local_pair_id))
+        else {
+            return false;
+        };
+        let to = match self.storage_cells.iter().position(|cell| cell.is_empty()) {
+            Some(to) => to,
+            None => match Self::oldest_valid_in(&self.storage_cells) {
+                Some(to) => to,
+                None => return false,
+            },
+        };
+        let mut data = self.memory_cells[from]
+            .take_data()
+            .expect("empty memory cell");
+        data.stored = Some(now);
+        self.storage_cells[to] = MemoryCell::Valid(data);
+        true
+    }
+
+    /// Return the cells holding the EPR pairs that can be used.
+    fn usable_cells(&self) -> &Vec<MemoryCell> {
+        if self.has_storage() {
+            &self.storage_cells
+        } else {
+            &self.memory_cells
+        }
+    }
+
+    /// Return the number of memory cells assigned when created.
+    pub fn assigned(&self) -> u32 {
+        self.assigned
+    }
+
+    /// Change the number of memory cells, which must be all empty.
+    pub fn resize(&mut self, num_qubits: u32) {
+        assert!(self.memory_cells.iter().all(|cell| cell.is_empty()));
+        self.memory_cells
+            .resize_with(num_qubits as usize, MemoryCell::default);
+    }
+
+    /// Return the number of non-empty memory cells.
+    pub fn num_occupied(&self) -> u32 {
+        self.memory_cells
+            .iter()
+            .filter(|cell| !cell.is_empty())
+            .count() as u32
     }
 
     /// Add a fresh EPR pair to an empty memory cell or, if not available,
@@ -199,6 +280,42 @@ impl Nic {
             return true;
         }
 
+        self.overwrite_oldest(now, epr_pair_id)
+    }
+
+    /// Add a batch of fresh EPR pairs generated at the same time, as with
+    /// `add_epr_pair`, but never overwriting an EPR pair of the same batch.
+    /// Return the identifiers of the EPR pairs that could not be stored.
+    pub fn add_epr_pairs(&mut self, now: u64, epr_pair_ids: &[u64]) -> Vec<u64> {
+        let mut written = vec![];
+        let mut dropped = vec![];
+        for epr_pair_id in epr_pair_ids {
+            let index = self
+                .memory_cells
+                .iter()
+                .position(|cell| cell.is_empty())
+                .or_else(|| {
+                    self.memory_cells
+                        .iter()
+                        .enumerate()
+                        .filter(|(index, cell)| cell.is_valid() && !written.contains(index))
+                        .min_by(|(_, a), (_, b)| a.cmp(b))
+                        .map(|(index, _)| index)
+                });
+            match index {
+                Some(index) => {
+                    self.memory_cells[index] = MemoryCell::new(now, *epr_pair_id);
+                    written.push(index);
+                }
+                None => dropped.push(*epr_pair_id),
+            }
+        }
+        dropped
+    }
+
+    /// Overwrite the oldest valid memory cell with a fresh EPR pair.
+    /// Do nothing if there are no valid memory cells and return false.
+    pub fn overwrite_oldest(&mut self, now: u64, epr_pair_id: u64) -> bool {
         if let Some(index) = self.oldest_valid() {
             self.memory_cells[index] = MemoryCell::new(now, epr_pair_id);
             return true;
@@ -207,10 +324,20 @@ impl Nic {
         false
     }
 
+    /// Empty the oldest valid memory cell, if any, and return its data.
+    pub fn evict_oldest(&mut self) -> Option<MemoryCellData> {
+        self.oldest_valid()
+            .and_then(|index| self.memory_cells[index].take_data())
+    }
+
     /// Consume an EPR pair. Return None if there is no memory cell
     /// associated with the local pair requested.
     pub fn consume(&mut self, local_pair_id: u64) -> Option<MemoryCellData> {
-        for memory_cell in &mut self.memory_cells {
+        for memory_cell in self
+            .memory_cells
+            .iter_mut()
+            .chain(self.storage_cells.iter_mut())
+        {
             if let Some(data) = memory_cell.data() {
                 if data.local_pair_id == local_pair_id {
                     return memory_cell.take_data();
@@ -220,6 +347,33 @@ impl Nic {
         None
     }
 
+    /// Empty all the memory cells, including those in use.
+    /// Return the data of the non-empty memory cells.
+    pub fn flush(&mut self) -> Vec<MemoryCellData> {
+        self.memory_cells
+            .iter_mut()
+            .chain(self.storage_cells.iter_mut())
+            .filter_map(|cell| cell.take_data())
+            .collect()
+    }
+
+    /// Return true if a memory cell holds the given local pair.
+    pub fn contains(&self, local_pair_id: u64) -> bool {
+        self.memory_cells
+            .iter()
+            .chain(self.storage_cells.iter())
+            .any(|cell| cell.local_pair_id() == Some(local_pair_id))
+    }
+
+    /// Return the data of the cell holding the given local pair, if any.
+    pub fn data(&self, local_pair_id: u64) -> Option<MemoryCellData> {
+        self.memory_cells
+            .iter()
+            .chain(self.storage_cells.iter())
+            .find(|cell| cell.local_pair_id() == Some(local_pair_id))
+            .and_then(|cell| cell.data())
+    }
+
     /// Return the occupancy of the NIC, i.e., the number of non-empty memory
     /// cells divided by the total number of cells.
     pub fn occupancy(&mut self) -> f64 {
@@ -235,9 +389,15 @@ impl Nic {
     }
 
     /// Flag a memory cell as used, identified by its local pair identifier.
-    /// Return true if found and it was valid.
+    /// Return true if found and it was valid, in storage if the NIC has
+    /// storage cells.
     pub fn used(&mut self, local_pair_id: u64) -> bool {
-        for memory_cell in &mut self.memory_cells {
+        let cells = if self.has_storage() {
+            &mut self.storage_cells
+        } else {
+            &mut self.memory_cells
+        };
+        for memory_cell in cells {
             if let Some(data) = memory_cell.data() {
                 if data.local_pair_id == local_pair_id {
                     if memory_cell.is_valid() {
@@ -254,7 +414,12 @@ impl Nic {
 
     /// Return the index of the oldest valid memory cell, if any.
     pub fn oldest_valid(&self) -> Option<usize> {
-        self.memory_cells
+        Self::oldest_valid_in(&self.memory_cells)
+    }
+
+    /// Return the index of the oldest valid cell in a vector, if any.
+    fn oldest_valid_in(cells: &[MemoryCell]) -> Option<usize> {
+        cells
             .iter()
             .enumerate()
             .filter(|(_, cell)| cell.is_valid())
@@ -262,10 +427,11 @@ impl Nic {
             .map(|(index, _)| index)
     }
 
-    /// Return the local pair ID of the newest valid memory cell, if any.
+    /// Return the local pair ID of the newest valid memory cell, if any,
+    /// in storage if the NIC has storage cells.
     pub fn newest_valid(&self) -> Option<u64> {
         if let Some(memory_cell) = self
-            .memory_cells
+            .usable_cells()
             .iter()
             .filter(|cell| cell.is_valid())
             .max()
@@ -410,7 +576,9 @@ mod tests {
         }
 
         // Consume one pair.
+        assert!(nic.contains(7));
         assert!(nic.consume(7).is_some());
+        assert!(!nic.contains(7));
 
         // New pairs can be added, they will overwrite the only valid one.
         for i in 0..100 {
@@ -433,9 +601,92 @@ mod tests {
         for cell in &nic.memory_cells {
             assert!(cell.is_empty());
         }
+
+        // Flush both valid and used cells.
+        for i in 0..5 {
+            nic.add_epr_pair(i, i);
+        }
+        assert!(nic.used(0));
+        assert_eq!(5, nic.flush().len());
+        assert_float_eq::assert_f64_near!(0.0, nic.occupancy());
+        assert!(nic.flush().is_empty());
+    }
+
+    #[test]
+    fn test_nic_pooled_cells() {
+        let mut nic = Nic::new(Role::Slave, 2);
+        nic.resize(4);
+        assert_eq!(2, nic.assigned());
+        assert_eq!(0, nic.num_occupied());
+        assert!(!nic.overwrite_oldest(100, 0));
+        assert!(nic.evict_oldest().is_none());
+
+        for i in 0..3 {
+            assert!(nic.add_epr_pair(100 + i, i));
+        }
+        assert_eq!(3, nic.num_occupied());
+        assert!(nic.used(0));
+        assert!(nic.overwrite_oldest(200, 3));
+        assert!(!nic.contains(1));
+        assert_eq!(2, nic.evict_oldest().unwrap().local_pair_id);
+        assert_eq!(2, nic.num_occupied());
+    }
+
+    #[test]
+    fn test_nic_add_epr_pairs() {
+        let mut nic = Nic::new(Role::Master, 3);
+        nic.add_epr_pair(100, 0);
+        nic.add_epr_pair(110, 1);
+        nic.used(1);
+
+        // The batch fills the empty cell, overwrites the oldest valid one,
+        // then the remaining EPR pairs are dropped.
+        assert_eq!(vec![4, 5], nic.add_epr_pairs(200, &[2, 3, 4, 5]));
+        assert!(!nic.contains(0));
+        assert!(nic.contains(1));
+        assert!(nic.contains(2));
+        assert!(nic.contains(3));
+
+        // A new batch overwrites the previous one.
+        assert!(nic.add_epr_pairs(300, &[6, 7]).is_empty());
+        assert!(nic.contains(1));
+        assert!(nic.contains(6));
+        assert!(nic.contains(7));
+    }
+
+    #[test]
+    fn test_nic_storage_cells() {
+        let mut nic = Nic::new(Role::Master, 2);
+        nic.add_storage_cells(1);
+        assert!(nic.has_storage());
+
+        // Fresh EPR pairs cannot be used until stored.
+        nic.add_epr_pair(100, 0);
+        nic.add_epr_pair(110, 1);
+        assert!(nic.newest_valid().is_none());
+        assert!(!nic.used(0));
+
+        assert!(nic.store(200, 0));
+        assert!(!nic.store(200, 0));
+        assert_eq!(0, nic.newest_valid().unwrap());
+        assert_eq!(1, nic.num_occupied());
+
+        // The oldest valid storage cell is overwritten, unless in use.
+        assert!(nic.store(210, 1));
+        assert_eq!(1, nic.newest_valid().unwrap());
+        assert!(!nic.contains(0));
+        assert!(nic.used(1));
+        nic.add_epr_pair(220, 2);
+        assert!(!nic.store(230, 2));
+
+        let data = nic.consume(1).unwrap();
+        assert_eq!(110, data.created);
+        assert_eq!(Some(210), data.stored);
+        assert_eq!(1, nic.flush().len());
     }
 
     #[test]
+    #[allow(clippy::nonminimal_bool)]
     fn test_nic_memory_cell_order() {
         assert!(MemoryCell::Empty == MemoryCell::Empty);
         assert!(MemoryCell::Empty <= MemoryCell::Empty);
@@ -21,6 +21,30 @@ struct Request {
     status: Status,
     /// Path
     path: Vec<u32>,
+    /// Number of times the EPR pair has been requested again after a failure.
+    retries: u32,
+    /// True if the request has been skipped at least once because of its
+    /// minimum fidelity.
+    skipped: bool,
+    /// Virtual finish time, with weighted fair queuing.
+    tag: f64,
+    /// True if the EPR pair has been reserved in advance.
+    reserved: bool,
+    /// Memory cell holding the half EPR pair shared with the previous hop,
+    /// if the request has been forwarded to this node by another one along
+    /// the path, otherwise None.
+    slave: Option<MemoryCellId>,
+}
+
+/// EPR pairs established for a request of multiple pairs.
+#[derive(Default)]
+struct Batch {
+    /// Number of EPR pairs established so far.
+    established: u32,
+    /// Number of EPR pairs failed so far.
+    failed: u32,
+    /// Responses held until enough EPR pairs are established.
+    held: Vec<Event>,
 }
 
 #[derive(Debug, Clone)]
@@ -31,6 +55,20 @@ pub struct NodeProperties {
     pub swapping_duration: f64,
     /// Duration of the local operations to correct end-to-end pairs, in s.
     pub correction_duration: f64,
+    /// Number of processing units, 0 for unlimited.
+    pub processing_units: u32,
+    /// Probability that a qubit is retrieved successfully from memory.
+    pub read_efficiency: f64,
+    /// Time to retrieve a qubit from memory, in s.
+    pub read_latency: f64,
+    /// Time to transfer a qubit from communication to storage, in s.
+    pub transfer_duration: f64,
+    /// Decay rate of the fidelity of the qubits in memory, in s^-1.
+    pub decay_rate: f64,
+    /// Decay rate of the fidelity of the qubits in storage, in s^-1.
+    pub storage_decay_rate: f64,
+    /// Fidelity of the transfer of a qubit from communication to storage.
+    pub transfer_fidelity: f64,
 }
 
 /// A quantum node.
@@ -45,10 +83,41 @@ pub struct Node {
     nics_slave: std::collections::HashMap<u32, super::nic::Nic>,
     /// The applications, identified by their port.
     applications: std::collections::HashMap<u16, Box<dyn crate::event::EventHandler>>,
+    /// Ports of the applications measuring the EPR pairs of memoryless
+    /// logical links, by peer.
+    measurers: std::collections::HashMap<u32, u16>,
     /// The logical topology.
     logical_topology: std::rc::Rc<crate::logical_topology::LogicalTopology>,
     /// Pending requests grouped by peer.
     pending_requests: std::collections::HashMap<u32, Vec<Request>>,
+    /// Requests of multiple pairs, by source node ID, source port, and
+    /// request ID.
+    batches: std::collections::HashMap<(u32, u16, u64), Batch>,
+    /// EPR pairs locked for an ES whose local operations are waiting or in
+    /// progress, by previous hop and local pair identifier, with the EPR
+    /// to be established.
+    operations: std::collections::HashMap<(u32, u64), EprFiveTuple>,
+    /// Processing units for the local operations.
+    processor: crate::processor::Processor,
+    /// Scheduler of the pending requests.
+    scheduler: crate::request_scheduler::RequestScheduler,
+    /// Admission control of the requests of the applications.
+    admission: crate::admission::AdmissionControl,
+    /// Calendars of the advance reservations, by logical link in the
+    /// direction of the paths, shared by all the nodes.
+    calendars: std::rc::Rc<
+        std::cell::RefCell<
+            std::collections::HashMap<(u32, u32), crate::reservation::ReservationCalendar>,
+        >,
+    >,
+    /// Number of EPR pairs delivered for the advance reservations whose
+    /// time window is in progress, by source port and request ID.
+    reservations: std::collections::HashMap<(u16, u64), u32>,
+    /// Memory qubits shared by all the NICs, if not partitioned.
+    pool: Option<crate::memory_pool::MemoryPool>,
+    /// Fidelity of the EPR pairs generated on every logical link, by master
+    /// and slave node IDs.
+    link_fidelities: std::rc::Rc<std::collections::HashMap<(u32, u32), f64>>,
     /// Pseudo-random number generator.
     rng: rand::rngs::StdRng,
 }
@@ -82,14 +151,179 @@ impl Node {
     ) -> Self {
         Self {
             node_id,
+            processor: crate::processor::Processor::new(node_id, properties.processing_units),
+            scheduler: crate::request_scheduler::RequestScheduler::default(),
+            admission: crate::admission::AdmissionControl::default(),
+            calendars: std::rc::Rc::new(std::cell::RefCell::new(std::collections::HashMap::new())),
+            reservations: std::collections::HashMap::new(),
             properties,
             nics_master: std::collections::HashMap::new(),
             nics_slave: std::collections::HashMap::new(),
             applications: std::collections::HashMap::new(),
+            measurers: std::collections::HashMap::new(),
             logical_topology,
             pending_requests: std::collections::HashMap::new(),
-            rng: rand::rngs::StdRng::seed_from_u64(init_seed + node_id as u64),
+            batches: std::collections::HashMap::new(),
+            operations: std::collections::HashMap::new(),
+            pool: None,
+            link_fidelities: std::rc::Rc::new(std::collections::HashMap::new()),
+            rng: rand::rngs::StdRng::seed_from_u64(crate::utils::component_seed(
+                init_seed,
+                "node",
+                node_id as u64,
+            )),
+        }
+    }
+
+    /// Return the node's identifier.
+    pub fn node_id(&self) -> u32 {
+        self.node_id
+    }
+
+    /// Set the order in which local operations wait for a processing unit.
+    pub fn set_queue_discipline(&mut self, discipline: crate::processor::QueueDiscipline) {
+        self.processor.set_discipline(discipline);
+    }
+
+    /// Set the order in which the pending requests are served, with the
+    /// weights of the traffic classes used by weighted fair queuing.
+    pub fn set_scheduling(
+        &mut self,
+        policy: crate::request_scheduler::SchedulingPolicy,
+        weights: Vec<f64>,
+    ) {
+        self.scheduler = crate::request_scheduler::RequestScheduler::new(policy, weights);
+    }
+
+    /// Set the policy to accept or reject the requests of the applications.
+    pub fn set_admission(&mut self, policy: crate::admission::AdmissionPolicy) {
+        self.admission = crate::admission::AdmissionControl::new(policy);
+    }
+
+    /// Share the memory qubits of the node among all its NICs, according to
+    /// the given policy, unless partitioned. Must be called before any EPR
+    /// pair is established.
+    ///
+    /// Parameters:
+    /// - `policy`: the memory allocation policy
+    /// - `size`: the total number of memory qubits of the node
+    /// - `min_qubits`: the qubits guaranteed to every NIC with `MinGuarantee`
+    pub fn set_memory_policy(
+        &mut self,
+        policy: crate::memory_pool::MemoryPolicy,
+        size: u32,
+        min_qubits: u32,
+    ) {
+        if policy == crate::memory_pool::MemoryPolicy::Partitioned {
+            return;
+        }
+        for nic in self
+            .nics_master
+            .values_mut()
+            .chain(self.nics_slave.values_mut())
+        {
+            nic.resize(size);
         }
+        self.pool = Some(crate::memory_pool::MemoryPool::new(
+            policy, size, min_qubits,
+        ));
+    }
+
+    /// Set the fidelity of the EPR pairs generated on every logical link,
+    /// used to estimate the end-to-end fidelity of the EPR requests.
+    pub fn set_link_fidelities(
+        &mut self,
+        link_fidelities: std::rc::Rc<std::collections::HashMap<(u32, u32), f64>>,
+    ) {
+        self.link_fidelities = link_fidelities;
+    }
+
+    /// Set the logical topology, shared by all the nodes, e.g., after its
+    /// paths have been recomputed to avoid the links down.
+    pub fn set_logical_topology(
+        &mut self,
+        logical_topology: std::rc::Rc<crate::logical_topology::LogicalTopology>,
+    ) {
+        self.logical_topology = logical_topology;
+    }
+
+    /// Set the calendars of the advance reservations, shared by all the
+    /// nodes, so that a reservation is booked on every logical link of its
+    /// path and enforced by all the nodes along it.
+    pub fn set_calendars(
+        &mut self,
+        calendars: std::rc::Rc<
+            std::cell::RefCell<
+                std::collections::HashMap<(u32, u32), crate::reservation::ReservationCalendar>,
+            >,
+        >,
+    ) {
+        self.calendars = calendars;
+    }
+
+    /// Estimate the end-to-end fidelity of an EPR pair that uses the given
+    /// local pair towards the next hop, depending on its age, and freshly
+    /// generated pairs on the other logical links of the path, all swapped.
+    fn estimate_fidelity(
+        properties: &NodeProperties,
+        link_fidelities: &std::collections::HashMap<(u32, u32), f64>,
+        now: u64,
+        path: &[u32],
+        cell: &crate::nic::MemoryCellData,
+    ) -> f64 {
+        let link_fidelity = |u: u32, v: u32| {
+            link_fidelities
+                .get(&(u, v))
+                .or(link_fidelities.get(&(v, u)))
+                .copied()
+                .unwrap_or(1.0)
+        };
+
+        // The local qubit decoheres until it is read from the memory, at a
+        // different rate once transferred into storage.
+        let fidelity = link_fidelity(path[0], path[1]);
+        let first = match cell.stored {
+            None => crate::utils::fidelity(
+                fidelity,
+                properties.decay_rate,
+                crate::utils::to_seconds(now - cell.created) + properties.read_latency,
+            ),
+            Some(stored) => crate::utils::fidelity(
+                0.25 + properties.transfer_fidelity
+                    * (crate::utils::fidelity(
+                        fidelity,
+                        properties.decay_rate,
+                        crate::utils::to_seconds(stored - cell.created),
+                    ) - 0.25),
+                properties.storage_decay_rate,
+                crate::utils::to_seconds(now - stored) + properties.read_latency,
+            ),
+        };
+
+        // Entanglement swapping of Werner states multiplies their parameters.
+        let werner = |fidelity: f64| (4.0 * fidelity - 1.0) / 3.0;
+        let parameter = path
+            .windows(2)
+            .skip(1)
+            .fold(werner(first), |parameter, hop| {
+                parameter * werner(link_fidelity(hop[0], hop[1]))
+            });
+        (3.0 * parameter + 1.0) / 4.0
+    }
+
+    /// Estimate the end-to-end fidelity of an EPR pair along the given path,
+    /// from this node, with freshly generated pairs on all the logical links.
+    pub fn fresh_fidelity(&self, path: &[u32]) -> f64 {
+        let stored = self
+            .nics_master
+            .get(&path[1])
+            .is_some_and(|nic| nic.has_storage());
+        let cell = crate::nic::MemoryCellData {
+            created: 0,
+            local_pair_id: 0,
+            stored: stored.then_some(0),
+        };
+        Self::estimate_fidelity(&self.properties, &self.link_fidelities, 0, path, &cell)
     }
 
     /// Retrieve an application running on this node.
@@ -114,6 +348,35 @@ impl Node {
         }
     }
 
+    /// Add an application to this node that measures the EPR pairs of the
+    /// memoryless logical link towards a given peer.
+    pub fn add_measurer(
+        &mut self,
+        application: Box<dyn crate::event::EventHandler>,
+        port: u16,
+        peer_node_id: u32,
+    ) {
+        self.add_applicaton(application, port);
+        self.measurers.insert(peer_node_id, port);
+    }
+
+    /// Notify that half of an EPR pair has been detected on the memoryless
+    /// logical link towards a given peer. Return the event for the
+    /// application that measures it, if any.
+    pub fn epr_detected(&self, peer_node_id: u32, epr_pair_id: u64) -> Option<Event> {
+        self.measurers.get(&peer_node_id).map(|port| {
+            Event::new(
+                0.0,
+                EventType::AppEvent(AppEventData::EprMeasured(EprMeasuredData {
+                    this_node_id: self.node_id,
+                    this_port: *port,
+                    peer_node_id,
+                    epr_pair_id,
+                })),
+            )
+        })
+    }
+
     /// Return the next port number available.
     pub fn next_port(&self) -> u16 {
         let mut port = 0;
@@ -148,14 +411,36 @@ impl Node {
         role: super::nic::Role,
         epr_pair_id: u64,
     ) -> (Vec<Event>, Vec<Sample>) {
-        let occupancy = {
+        let mut pool_samples = vec![];
+        let occupancy = if self.pool.is_some() {
+            pool_samples = self.add_epr_pair_pooled(now, peer_node_id, &role, epr_pair_id);
+            self.get_nic(peer_node_id, &role).occupancy()
+        } else {
             let nic = self.get_nic(peer_node_id, &role);
             nic.add_epr_pair(now, epr_pair_id);
             nic.occupancy()
         };
 
-        // Schedule pending requests for this peer, if any.
-        let (events, mut samples) = self.schedule_pending_requests(peer_node_id);
+        // If there are storage qubits, then the EPR pair cannot be used
+        // until transferred, otherwise schedule pending requests for this
+        // peer, if any.
+        let (events, mut samples) = if self.get_nic(peer_node_id, &role).has_storage() {
+            (
+                vec![Event::new(
+                    self.properties.transfer_duration,
+                    EventType::NetworkEvent(NetworkEventData::EprStored(EprNotifiedData {
+                        this_node_id: self.node_id,
+                        peer_node_id,
+                        role,
+                        epr_pair_id,
+                    })),
+                )],
+                vec![],
+            )
+        } else {
+            self.schedule_pending_requests(now, peer_node_id)
+        };
+        samples.append(&mut pool_samples);
 
         samples.push(Sample::Series(
             "occupancy".to_string(),
@@ -166,6 +451,203 @@ impl Node {
         (events, samples)
     }
 
+    /// Notify that a batch of EPR pairs generated at the same time has been
+    /// established with a peer. With a memory pool, the EPR pairs are
+    /// admitted one by one.
+    pub fn epr_batch_established(
+        &mut self,
+        now: u64,
+        peer_node_id: u32,
+        role: super::nic::Role,
+        epr_pair_ids: &[u64],
+    ) -> (Vec<Event>, Vec<Sample>) {
+        let mut pool_samples = vec![];
+        let stored = if self.pool.is_some() {
+            for epr_pair_id in epr_pair_ids {
+                pool_samples.append(&mut self.add_epr_pair_pooled(
+                    now,
+                    peer_node_id,
+                    &role,
+                    *epr_pair_id,
+                ));
+            }
+            epr_pair_ids.to_vec()
+        } else {
+            let dropped = self
+                .get_nic(peer_node_id, &role)
+                .add_epr_pairs(now, epr_pair_ids);
+            for _ in &dropped {
+                pool_samples.push(Sample::ScalarCount("batch_drops".to_string()));
+            }
+            epr_pair_ids
+                .iter()
+                .filter(|epr_pair_id| !dropped.contains(epr_pair_id))
+                .copied()
+                .collect()
+        };
+        let occupancy = self.get_nic(peer_node_id, &role).occupancy();
+
+        // Same as with a single EPR pair, but pending requests are scheduled
+        // only once for the whole batch.
+        let (events, mut samples) = if self.get_nic(peer_node_id, &role).has_storage() {
+            (
+                stored
+                    .into_iter()
+                    .map(|epr_pair_id| {
+                        Event::new(
+                            self.properties.transfer_duration,
+                            EventType::NetworkEvent(NetworkEventData::EprStored(EprNotifiedData {
+                                this_node_id: self.node_id,
+                                peer_node_id,
+                                role: role.clone(),
+                                epr_pair_id,
+                            })),
+                        )
+                    })
+                    .collect(),
+                vec![],
+            )
+        } else {
+            self.schedule_pending_requests(now, peer_node_id)
+        };
+        samples.append(&mut pool_samples);
+
+        samples.push(Sample::Series(
+            "occupancy".to_string(),
+            vec![self.node_id.to_string(), peer_node_id.to_string()],
+            occupancy,
+        ));
+
+        (events, samples)
+    }
+
+    /// Distribute storage qubits among the NICs, one at a time.
+    /// Fail if there are not enough storage qubits for all the NICs.
+    pub fn add_storage(&mut self, num_qubits: u32) -> anyhow::Result<()> {
+        let links = self.links();
+        if links.is_empty() || num_qubits == 0 {
+            return Ok(());
+        }
+        anyhow::ensure!(
+            num_qubits as usize >= links.len(),
+            "node {} has {} storage qubits for {} links",
+            self.node_id,
+            num_qubits,
+            links.len()
+        );
+        let mut num_cells = vec![0; links.len()];
+        for i in 0..num_qubits as usize {
+            num_cells[i % links.len()] += 1;
+        }
+        for ((role, peer), num_cells) in links.iter().zip(num_cells) {
+            self.get_nic(*peer, role).add_storage_cells(num_cells);
+        }
+        Ok(())
+    }
+
+    /// Notify that the transfer of an EPR pair into storage is complete.
+    /// Return true if the EPR pair was stored.
+    pub fn epr_stored(
+        &mut self,
+        now: u64,
+        peer_node_id: u32,
+        role: super::nic::Role,
+        epr_pair_id: u64,
+    ) -> (bool, Vec<Event>, Vec<Sample>) {
+        if !self.get_nic(peer_node_id, &role).store(now, epr_pair_id) {
+            // The EPR pair was overwritten, flushed, or all the storage
+            // qubits are in use.
+            return (false, vec![], vec![]);
+        }
+
+        // Schedule pending requests for this peer, if any.
+        let (events, mut samples) = self.schedule_pending_requests(now, peer_node_id);
+        samples.push(Sample::ScalarCount("storage_transfers".to_string()));
+        (true, events, samples)
+    }
+
+    /// Return the role and peer of all the NICs, sorted.
+    fn links(&self) -> Vec<(super::nic::Role, u32)> {
+        let mut links = vec![];
+        for (nics, nic_role) in [
+            (&self.nics_master, super::nic::Role::Master),
+            (&self.nics_slave, super::nic::Role::Slave),
+        ] {
+            let mut peers = nics.keys().copied().collect::<Vec<u32>>();
+            peers.sort();
+            for peer in peers {
+                links.push((nic_role.clone(), peer));
+            }
+        }
+        links
+    }
+
+    /// Store a new EPR pair in the memory pool, as decided by its policy.
+    fn add_epr_pair_pooled(
+        &mut self,
+        now: u64,
+        peer_node_id: u32,
+        role: &super::nic::Role,
+        epr_pair_id: u64,
+    ) -> Vec<Sample> {
+        let pool = self.pool.as_ref().expect("no memory pool");
+
+        // Sort the NICs to evict deterministically.
+        let links = self.links();
+        let usage = links
+            .iter()
+            .map(|(nic_role, peer)| {
+                let nic = match nic_role {
+                    super::nic::Role::Master => &self.nics_master[peer],
+                    super::nic::Role::Slave => &self.nics_slave[peer],
+                };
+                crate::memory_pool::LinkUsage {
+                    occupied: nic.num_occupied(),
+                    evictable: nic.oldest_valid().is_some(),
+                    assigned: nic.assigned(),
+                }
+            })
+            .collect::<Vec<_>>();
+        let link = links
+            .iter()
+            .position(|(nic_role, peer)| nic_role == role && *peer == peer_node_id)
+            .expect("EPR pair established on an unknown NIC");
+
+        let mut samples = vec![];
+        let admission = pool.admit(link, &usage);
+        let size = pool.size();
+        match admission {
+            crate::memory_pool::Admission::Free => {
+                self.get_nic(peer_node_id, role)
+                    .add_epr_pair(now, epr_pair_id);
+            }
+            crate::memory_pool::Admission::Evict(victim) => {
+                let (victim_role, victim_peer) = &links[victim];
+                self.get_nic(*victim_peer, victim_role).evict_oldest();
+                self.get_nic(peer_node_id, role)
+                    .add_epr_pair(now, epr_pair_id);
+                samples.push(Sample::ScalarCount("memory_evictions".to_string()));
+            }
+            crate::memory_pool::Admission::Overwrite => {
+                self.get_nic(peer_node_id, role)
+                    .overwrite_oldest(now, epr_pair_id);
+            }
+        }
+
+        let occupied = self
+            .nics_master
+            .values()
+            .chain(self.nics_slave.values())
+            .map(|nic| nic.num_occupied())
+            .sum::<u32>();
+        samples.push(Sample::Series(
+            "pool-occupancy".to_string(),
+            vec![self.node_id.to_string()],
+            occupied as f64 / size as f64,
+        ));
+        samples
+    }
+
     /// Consume the qubit of an EPR stored in a memory cell in one of the NICs.
     /// Return the creation time and identifier.
     pub fn consume(
@@ -177,6 +659,71 @@ impl Node {
         self.get_nic(peer_node_id, role).consume(local_pair_id)
     }
 
+    /// Empty the memory cell holding a given local pair, towards any peer
+    /// and with any role. Return true if found.
+    pub fn release(&mut self, local_pair_id: u64) -> bool {
+        self.nics_master
+            .values_mut()
+            .chain(self.nics_slave.values_mut())
+            .any(|nic| nic.consume(local_pair_id).is_some())
+    }
+
+    /// Retrieve a qubit from the memory, which may fail depending on the
+    /// read efficiency.
+    pub fn read(&mut self) -> bool {
+        self.properties.read_efficiency >= 1.0
+            || self.rng.gen::<f64>() < self.properties.read_efficiency
+    }
+
+    /// Empty the memory cells of the NICs towards a given peer, with any
+    /// role, or of all the NICs if `peer_node_id` is None.
+    /// Return the data of the memory cells that were not empty.
+    pub fn flush_nics(&mut self, peer_node_id: Option<u32>) -> Vec<crate::nic::MemoryCellData> {
+        let mut ret = vec![];
+        for nics in [&mut self.nics_master, &mut self.nics_slave] {
+            for (peer, nic) in nics.iter_mut() {
+                if peer_node_id.is_none_or(|x| x == *peer) {
+                    ret.append(&mut nic.flush());
+                }
+            }
+        }
+        ret
+    }
+
+    /// Empty the memory cells of the NIC towards a given peer with a given role.
+    /// Return the data of the memory cells that were not empty.
+    pub fn flush_nic(
+        &mut self,
+        peer_node_id: u32,
+        role: &super::nic::Role,
+    ) -> Vec<crate::nic::MemoryCellData> {
+        self.get_nic(peer_node_id, role).flush()
+    }
+
+    /// Fail the requests originated by this node that are waiting for
+    /// the response to an ES whose path is affected by a fault.
+    /// Return the `EsRemoteFailed` events for such requests.
+    pub fn fail_requests(&self, affected: impl Fn(&[u32]) -> bool) -> Vec<Event> {
+        let mut events = vec![];
+        let mut pending_requests = self.pending_requests.iter().collect::<Vec<_>>();
+        pending_requests.sort_by_key(|(peer, _)| **peer);
+        for (_peer, requests) in pending_requests {
+            for request in requests.iter().filter(|request| request.slave.is_none()) {
+                if let Status::WaitingForResponse(_) = request.status {
+                    if affected(&request.path) {
+                        events.push(Event::new(
+                            0.0_f64,
+                            EventType::NodeEvent(NodeEventData::EsRemoteFailed(
+                                request.epr.clone(),
+                            )),
+                        ));
+                    }
+                }
+            }
+        }
+        events
+    }
+
     /// Return the right set of NICs depending on the role.
     fn nics(
         &mut self,
@@ -201,13 +748,23 @@ impl Node {
         let now = event.time();
         if let EventType::NodeEvent(data) = event.event_type {
             match data {
-                NodeEventData::EprRequestApp(epr) => self.handle_epr_request_app(now, now, epr),
+                NodeEventData::EprRequestApp(epr) => self.handle_epr_request_app(now, epr),
                 NodeEventData::EsRequest(data) => self.handle_es_request(now, data),
+                NodeEventData::EsMemoryRead(data) => self.handle_es_memory_read(now, data),
                 NodeEventData::EsLocalComplete(data) => self.handle_es_local_complete(now, data),
                 NodeEventData::EsSuccess(data) => self.handle_es_response(now, data, true),
                 NodeEventData::EsFailure(data) => self.handle_es_response(now, data, false),
                 NodeEventData::EsRemoteComplete(data) => self.handle_es_remote_complete(now, data),
                 NodeEventData::EsRemoteFailed(data) => self.handle_es_remote_failed(now, data),
+                NodeEventData::EsAbort(data) => self.handle_es_abort(now, data),
+                NodeEventData::EprRequestTimeout(data) => {
+                    self.handle_epr_request_timeout(now, data)
+                }
+                NodeEventData::EprReservationApp(data) => {
+                    self.handle_epr_reservation_app(now, data)
+                }
+                NodeEventData::ReservationStart(data) => self.handle_reservation_start(now, data),
+                NodeEventData::ReservationEnd(data) => self.handle_reservation_end(now, data),
             }
         } else {
             panic!(
@@ -218,16 +775,149 @@ impl Node {
     }
 
     /// Handle EPR request from an application on this node.
+    fn handle_epr_request_app(&mut self, now: u64, epr: EprFiveTuple) -> (Vec<Event>, Vec<Sample>) {
+        assert!(epr.number_of_pairs > 0, "request for no EPR pairs: {epr}");
+        if !self
+            .logical_topology
+            .routable(epr.source_node_id, epr.target_node_id)
+        {
+            // The destination can only be reached via memoryless logical
+            // links, whose EPR pairs are measured on detection.
+            log::debug!("node {}: no route for EPR {}", self.node_id, epr);
+            return self.reject(epr);
+        }
+        if !self.deliverable(&epr) {
+            log::debug!("node {}: too many EPR pairs held for {}", self.node_id, epr);
+            return self.reject(epr);
+        }
+        if !self.attainable(&epr) {
+            log::debug!(
+                "node {}: minimum fidelity unattainable for {}",
+                self.node_id,
+                epr
+            );
+            return self.reject(epr);
+        }
+        if !self.admit(now, &epr) {
+            return self.reject(epr);
+        }
+        let mut samples = self.acceptance_samples(&epr, true);
+        let (events, mut request_samples) = self.request(now, epr);
+        samples.append(&mut request_samples);
+        (events, samples)
+    }
+
+    /// Request the EPR pairs of an accepted request.
+    ///
+    /// A request for multiple pairs is queued as one request per EPR pair,
+    /// each with its own index. If the request has a deadline, then a timer
+    /// is started for all its EPR pairs.
+    fn request(&mut self, now: u64, epr: EprFiveTuple) -> (Vec<Event>, Vec<Sample>) {
+        let mut peer = None;
+        for index in 0..epr.number_of_pairs {
+            peer = Some(self.enqueue(
+                now,
+                EprFiveTuple {
+                    index,
+                    ..epr.clone()
+                },
+                0,
+            ));
+        }
+        let (mut events, samples) = self.schedule_pending_requests(now, peer.unwrap());
+        if let Some(timeout) = epr.timeout {
+            events.push(Event::new(
+                crate::utils::to_seconds(timeout),
+                EventType::NodeEvent(NodeEventData::EprRequestTimeout(epr)),
+            ));
+        }
+        (events, samples)
+    }
+
+    /// Decide whether to accept a request from an application on this node,
+    /// depending on the EPR pairs already pending towards the next hop and
+    /// the capacity of the path.
+    fn admit(&mut self, now: u64, epr: &EprFiveTuple) -> bool {
+        let path = self
+            .logical_topology
+            .path(epr.source_node_id, epr.target_node_id);
+        let pending = path
+            .get(1)
+            .and_then(|peer| self.pending_requests.get(peer))
+            .map_or(0, |requests| requests.len());
+        self.admission.admit(
+            now,
+            epr.source_port,
+            epr.number_of_pairs,
+            pending,
+            self.logical_topology.capacity(&path),
+        )
+    }
+
+    /// Return true if the EPR pairs of a request that must be established
+    /// before delivering the first one fit the memory qubits at the
+    /// end-points, otherwise the request would never be served.
+    fn deliverable(&self, epr: &EprFiveTuple) -> bool {
+        let path = self
+            .logical_topology
+            .path(epr.source_node_id, epr.target_node_id);
+        epr.delivery.threshold(epr.number_of_pairs)
+            <= self.logical_topology.end_memory_qubits(&path)
+    }
+
+    /// Return true if the minimum fidelity of a request, if any, can be met
+    /// at least with freshly generated pairs.
+    fn attainable(&self, epr: &EprFiveTuple) -> bool {
+        epr.min_fidelity.is_none_or(|min_fidelity| {
+            let path = self
+                .logical_topology
+                .path(epr.source_node_id, epr.target_node_id);
+            self.fresh_fidelity(&path) >= min_fidelity.0
+        })
+    }
+
+    /// Reject a request from an application on this node, which receives
+    /// a failed response for every EPR pair requested.
+    fn reject(&self, epr: EprFiveTuple) -> (Vec<Event>, Vec<Sample>) {
+        log::debug!("node {}: request rejected for EPR {}", self.node_id, epr);
+        let events = (0..epr.number_of_pairs)
+            .map(|index| {
+                Event::new(
+                    0.0_f64,
+                    EventType::AppEvent(AppEventData::EprResponse(EprResponseData {
+                        epr: EprFiveTuple {
+                            index,
+                            ..epr.clone()
+                        },
+                        is_source: true,
+                        memory_cell: None,
+                        outcome: None,
+                    })),
+                )
+            })
+            .collect();
+        (events, self.acceptance_samples(&epr, false))
+    }
+
+    /// Return the samples of the acceptance of a request.
+    fn acceptance_samples(&self, epr: &EprFiveTuple, accepted: bool) -> Vec<Sample> {
+        let value = if accepted { 1.0 } else { 0.0 };
+        vec![
+            Sample::ScalarAvg("acceptance_ratio".to_string(), value),
+            Sample::Series(
+                "acceptance".to_string(),
+                vec![self.node_id.to_string(), epr.source_port.to_string()],
+                value,
+            ),
+        ]
+    }
+
+    /// Queue the request for an EPR pair. Return the next hop.
     ///
-    /// - `now`: the current simulated time
     /// - `received`: the time when the request was originally received
     /// - `epr`: the EPR to be established
-    fn handle_epr_request_app(
-        &mut self,
-        _now: u64,
-        received: u64,
-        epr: EprFiveTuple,
-    ) -> (Vec<Event>, Vec<Sample>) {
+    /// - `retries`: the number of times the EPR pair has been already requested
+    fn enqueue(&mut self, received: u64, epr: EprFiveTuple, retries: u32) -> u32 {
         assert_ne!(
             epr.source_node_id, epr.target_node_id,
             "src and dst nodes must be different"
@@ -242,7 +932,13 @@ impl Node {
         assert_eq!(epr.source_node_id, *path.first().unwrap());
         assert_eq!(epr.target_node_id, *path.last().unwrap());
 
-        let peer = *path.last().unwrap();
+        // Requests are queued towards the next hop, i.e., the peer of the
+        // master NIC that will be used to serve them.
+        let peer = path[1];
+        let tag = self.scheduler.tag(epr.class);
+        let reserved = self
+            .reservations
+            .contains_key(&(epr.source_port, epr.request_id));
         self.pending_requests
             .entry(peer)
             .or_default()
@@ -251,16 +947,263 @@ impl Node {
                 epr,
                 status: Status::Queued,
                 path,
+                retries,
+                skipped: false,
+                tag,
+                reserved,
+                slave: None,
+            });
+        peer
+    }
+
+    /// Handle an advance reservation from an application on this node.
+    ///
+    /// The reservation is booked in the calendars of all the logical links
+    /// of the path if their capacity and memory qubits are enough during its
+    /// time window, at the start of which its EPR pairs are requested.
+    /// A reservation of no EPR pairs, or whose time window is empty or
+    /// already started, is blocked.
+    fn handle_epr_reservation_app(
+        &mut self,
+        now: u64,
+        data: ReservationData,
+    ) -> (Vec<Event>, Vec<Sample>) {
+        assert_eq!(self.node_id, data.epr.source_node_id);
+
+        // The reservation is blocked if the destination can only be reached
+        // via memoryless logical links.
+        let accepted = data.epr.number_of_pairs > 0
+            && now <= data.start
+            && data.start < data.end
+            && self
+                .logical_topology
+                .routable(data.epr.source_node_id, data.epr.target_node_id)
+            && self.book(&data);
+        log::debug!(
+            "node {}: reservation {} for EPR {}",
+            self.node_id,
+            if accepted { "accepted" } else { "blocked" },
+            data.epr
+        );
+
+        let mut events = vec![];
+        if accepted {
+            events.push(Event::new(
+                crate::utils::to_seconds(data.start - now),
+                EventType::NodeEvent(NodeEventData::ReservationStart(data.clone())),
+            ));
+            events.push(Event::new(
+                crate::utils::to_seconds(data.end - now),
+                EventType::NodeEvent(NodeEventData::ReservationEnd(data.clone())),
+            ));
+        }
+        events.push(Event::new(
+            0.0_f64,
+            EventType::AppEvent(AppEventData::ReservationResponse(data, accepted)),
+        ));
+        (
+            events,
+            vec![Sample::ScalarAvg(
+                "reservation_blocking".to_string(),
+                if accepted { 0.0 } else { 1.0 },
+            )],
+        )
+    }
+
+    /// Book a reservation on all the logical links of its path, or on none
+    /// of them if any has not enough resources.
+    fn book(&mut self, data: &ReservationData) -> bool {
+        let path = self
+            .logical_topology
+            .path(data.epr.source_node_id, data.epr.target_node_id);
+        let mut calendars = self.calendars.borrow_mut();
+        let fits = path.windows(2).all(|hop| {
+            calendars.entry((hop[0], hop[1])).or_default().fits(
+                data.start,
+                data.end,
+                data.epr.number_of_pairs,
+                self.logical_topology.capacity(hop),
+                self.logical_topology.memory_qubits(hop),
+            )
+        });
+        if fits {
+            for hop in path.windows(2) {
+                calendars.entry((hop[0], hop[1])).or_default().book(
+                    data.start,
+                    data.end,
+                    data.epr.number_of_pairs,
+                    self.logical_topology.capacity(hop),
+                    self.logical_topology.memory_qubits(hop),
+                );
+            }
+        }
+        fits
+    }
+
+    /// Handle the start of the time window of an advance reservation.
+    ///
+    /// Its EPR pairs are requested with a deadline at the end of the window
+    /// and are served before those of the requests not reserved.
+    fn handle_reservation_start(
+        &mut self,
+        now: u64,
+        data: ReservationData,
+    ) -> (Vec<Event>, Vec<Sample>) {
+        self.reservations
+            .insert((data.epr.source_port, data.epr.request_id), 0);
+        self.request(
+            now,
+            EprFiveTuple {
+                timeout: Some(data.end - data.start),
+                ..data.epr
+            },
+        )
+    }
+
+    /// Handle the end of the time window of an advance reservation, whose
+    /// resources are released, and measure the fraction of the EPR pairs
+    /// reserved that have been delivered.
+    fn handle_reservation_end(
+        &mut self,
+        now: u64,
+        data: ReservationData,
+    ) -> (Vec<Event>, Vec<Sample>) {
+        let delivered = self
+            .reservations
+            .remove(&(data.epr.source_port, data.epr.request_id))
+            .unwrap_or_default();
+        let path = self
+            .logical_topology
+            .path(data.epr.source_node_id, data.epr.target_node_id);
+        let mut calendars = self.calendars.borrow_mut();
+        for hop in path.windows(2) {
+            if let Some(calendar) = calendars.get_mut(&(hop[0], hop[1])) {
+                calendar.release(now);
+            }
+        }
+        let utilization = delivered as f64 / data.epr.number_of_pairs as f64;
+        (
+            vec![],
+            vec![
+                Sample::ScalarAvg("reservation_utilization".to_string(), utilization),
+                Sample::Series(
+                    "reservation-utilization".to_string(),
+                    vec![self.node_id.to_string(), data.epr.source_port.to_string()],
+                    utilization,
+                ),
+            ],
+        )
+    }
+
+    /// Handle the expiration of the deadline of a request from an
+    /// application on this node.
+    ///
+    /// The EPR pairs of the request are removed and the failure is notified
+    /// to the application. The local EPR pairs of those waiting for the
+    /// response to an ES request are discarded.
+    fn handle_epr_request_timeout(
+        &mut self,
+        now: u64,
+        epr: EprFiveTuple,
+    ) -> (Vec<Event>, Vec<Sample>) {
+        assert_eq!(self.node_id, epr.source_node_id);
+
+        let mut expired = vec![];
+        for requests in self.pending_requests.values_mut() {
+            requests.retain(|request| {
+                if request.slave.is_some()
+                    || request.epr.source_port != epr.source_port
+                    || request.epr.request_id != epr.request_id
+                {
+                    return true;
+                }
+                expired.push(request.clone());
+                false
             });
+        }
+
+        let mut events = vec![];
+        let mut samples = vec![];
+        for request in expired {
+            let memory_cell = match request.status {
+                Status::Queued => None,
+                Status::WaitingForResponse(memory_cell) => Some(memory_cell),
+            };
+            let (mut new_events, mut new_samples) =
+                self.fail(now, request.epr, &request.path, memory_cell, true);
+            events.append(&mut new_events);
+            samples.append(&mut new_samples);
+        }
+        (events, samples)
+    }
 
-        self.schedule_pending_requests(peer)
+    /// Notify the application that the request for an EPR pair has failed,
+    /// because of its deadline or maximum number of retries.
+    ///
+    /// The local EPR pair, if any, is discarded and the ES is aborted at the
+    /// other nodes along the path, starting from the next hop.
+    fn fail(
+        &mut self,
+        now: u64,
+        epr: EprFiveTuple,
+        path: &[u32],
+        memory_cell: Option<MemoryCellId>,
+        timeout: bool,
+    ) -> (Vec<Event>, Vec<Sample>) {
+        log::debug!(
+            "node {}: request failed ({}) for EPR {}",
+            self.node_id,
+            if timeout { "timeout" } else { "abandoned" },
+            epr
+        );
+        let mut events = vec![];
+        let pending = match memory_cell {
+            Some(memory_cell) => {
+                events.push(self.discard(&memory_cell));
+                false
+            }
+            None => true,
+        };
+        events.push(Event::new_transfer(
+            EventType::NodeEvent(NodeEventData::EsAbort(EsAbortData {
+                epr: epr.clone(),
+                node_id: path[1],
+                prev_hop: self.node_id,
+                path: path.to_vec(),
+                pending,
+            })),
+            self.node_id,
+            path[1],
+        ));
+        let response = Event::new(
+            0.0_f64,
+            EventType::AppEvent(AppEventData::EprResponse(EprResponseData {
+                epr: epr.clone(),
+                is_source: true,
+                memory_cell: None,
+                outcome: None,
+            })),
+        );
+        let (mut new_events, mut samples) = self.deliver(now, &epr, Some(response), false, None);
+        events.append(&mut new_events);
+        samples.push(Sample::ScalarAvg(
+            "timeout_rate".to_string(),
+            if timeout { 1.0 } else { 0.0 },
+        ));
+        samples.push(Sample::ScalarAvg(
+            "abandon_rate".to_string(),
+            if timeout { 0.0 } else { 1.0 },
+        ));
+        (events, samples)
     }
 
     /// Handle ES request from another node.
     ///
-    /// If the memory cell does not contain what the master expects, then
-    /// send an EsFailure to the previous hop to free resources.
-    fn handle_es_request(&mut self, _now: u64, data: EsRequestData) -> (Vec<Event>, Vec<Sample>) {
+    /// If the memory cell does not contain what the master expects, or its
+    /// qubit cannot be read, then send an EsFailure to the previous hop to
+    /// free resources. Otherwise, an intermediate node queues the request
+    /// towards the next hop, while the target node corrects its qubit.
+    fn handle_es_request(&mut self, now: u64, data: EsRequestData) -> (Vec<Event>, Vec<Sample>) {
         assert_eq!(self.node_id, data.next_hop);
 
         #[cfg(debug_assertions)]
@@ -291,44 +1234,7 @@ impl Node {
             .get_mut(&data.prev_hop)
             .expect("received an EsRequest from an unknown peer");
 
-        if nic.used(data.local_pair_id) {
-            // We just locked the memory cell so that it cannot be modified.
-            // We now schedule an event for when the local operations (Bell-state
-            // measurement or X/Z corrections) need to be done.
-
-            let event_delay = if data.epr.target_node_id == self.node_id {
-                // This is the final target node.
-                //
-                // If this is a single hop EPR request, then the EPR pair can
-                // be used immediately. Otherwise, X/Z corrections might be
-                // necessary dependin on the outcome of the BSM operations
-                // along the path.
-                if data.path.len() > 2 {
-                    let rand = self.rng.gen_range(0..4);
-                    if rand == 0 {
-                        // no corrections
-                        0.0
-                    } else if rand == 1 {
-                        // both X and Z corrections
-                        self.properties.correction_duration * 2.0
-                    } else {
-                        // only X or Z correction
-                        self.properties.correction_duration
-                    }
-                } else {
-                    0.0
-                }
-            } else {
-                // This is an intermediate node, which has to perform entanglement
-                // swapping.
-                self.properties.swapping_duration
-            };
-
-            events.push(Event::new(
-                event_delay,
-                EventType::NodeEvent(NodeEventData::EsLocalComplete(data)),
-            ));
-        } else {
+        if !nic.used(data.local_pair_id) {
             if log::log_enabled!(log::Level::Debug) {
                 nic.print_all_cells();
             }
@@ -342,59 +1248,268 @@ impl Node {
             ));
 
             samples.push(Sample::ScalarCount("slave_fails".to_string()))
+        } else if self.properties.read_efficiency < 1.0
+            && self.rng.gen::<f64>() >= self.properties.read_efficiency
+        {
+            // The qubit could not be retrieved from the memory: the EPR
+            // pair is lost, and the previous hop releases its half and
+            // notifies the source node, which retries the request.
+            events.push(self.discard(&MemoryCellId {
+                neighbor_node_id: data.prev_hop,
+                role: super::nic::Role::Slave,
+                local_pair_id: data.local_pair_id,
+            }));
+            let dst_node_id = data.prev_hop;
+            events.push(Event::new_transfer(
+                EventType::NodeEvent(NodeEventData::EsFailure(data)),
+                self.node_id,
+                dst_node_id,
+            ));
+
+            samples.push(Sample::ScalarCount("memory_read_failures".to_string()))
+        } else {
+            // We just locked the memory cell so that it cannot be modified.
+            self.operations
+                .insert((data.prev_hop, data.local_pair_id), data.epr.clone());
+            let (mut new_events, mut new_samples) = if self.node_id == data.epr.target_node_id {
+                self.read_memory(now, data)
+            } else {
+                self.forward(now, data)
+            };
+            events.append(&mut new_events);
+            samples.append(&mut new_samples);
         }
 
         (events, samples)
     }
 
+    /// Queue an ES request received by an intermediate node towards the next
+    /// hop, which is served as those originated by this node: its EPR pair
+    /// shared with the previous hop is swapped with the one assigned to the
+    /// request towards the next hop.
+    fn forward(&mut self, now: u64, data: EsRequestData) -> (Vec<Event>, Vec<Sample>) {
+        let pos = data
+            .path
+            .iter()
+            .position(|x| *x == self.node_id)
+            .expect("this node is not present in the path of an EsRequest");
+        let peer = data.path[pos + 1];
+        let tag = self.scheduler.tag(data.epr.class);
+        self.pending_requests
+            .entry(peer)
+            .or_default()
+            .push(Request {
+                received: data.received,
+                epr: data.epr,
+                status: Status::Queued,
+                path: data.path,
+                retries: 0,
+                skipped: false,
+                tag,
+                reserved: data.reserved,
+                slave: Some(MemoryCellId {
+                    neighbor_node_id: data.prev_hop,
+                    role: super::nic::Role::Slave,
+                    local_pair_id: data.local_pair_id,
+                }),
+            });
+        self.schedule_pending_requests(now, peer)
+    }
+
+    /// Remove the request forwarded to this node for an EPR, identified by
+    /// the memory cell holding the half EPR pair shared with the previous hop.
+    fn take_forwarded(&mut self, epr: &EprFiveTuple, slave: &MemoryCellId) -> Option<Request> {
+        for requests in self.pending_requests.values_mut() {
+            if let Some(ndx) = requests
+                .iter()
+                .position(|request| request.epr == *epr && request.slave.as_ref() == Some(slave))
+            {
+                return Some(requests.remove(ndx));
+            }
+        }
+        None
+    }
+
+    /// Return true if a memory cell still holds its half EPR pair.
+    fn holds(&self, memory_cell: &MemoryCellId) -> bool {
+        match memory_cell.role {
+            super::nic::Role::Master => &self.nics_master,
+            super::nic::Role::Slave => &self.nics_slave,
+        }
+        .get(&memory_cell.neighbor_node_id)
+        .is_some_and(|nic| nic.contains(memory_cell.local_pair_id))
+    }
+
+    /// Retrieve from the memory the qubit locked for an ES. The local
+    /// operations (Bell-state measurement or X/Z corrections) are done as
+    /// soon as a processing unit is available after the read latency.
+    fn read_memory(&mut self, now: u64, data: EsRequestData) -> (Vec<Event>, Vec<Sample>) {
+        if self.properties.read_latency > 0.0 {
+            (
+                vec![Event::new(
+                    self.properties.read_latency,
+                    EventType::NodeEvent(NodeEventData::EsMemoryRead(data)),
+                )],
+                vec![],
+            )
+        } else {
+            self.handle_es_memory_read(now, data)
+        }
+    }
+
+    /// Handle the retrieval from the memory of the qubit of an EPR pair for
+    /// an ES, by submitting the local operation to the processing units.
+    fn handle_es_memory_read(
+        &mut self,
+        now: u64,
+        data: EsRequestData,
+    ) -> (Vec<Event>, Vec<Sample>) {
+        assert_eq!(self.node_id, data.next_hop);
+
+        // The memory cell has been flushed by a fault, or discarded by an
+        // abort, while the qubit was being retrieved. The EPR pair towards the
+        // next hop, if still assigned, is discarded.
+        let slave = MemoryCellId {
+            neighbor_node_id: data.prev_hop,
+            role: super::nic::Role::Slave,
+            local_pair_id: data.local_pair_id,
+        };
+        if !self.holds(&slave) {
+            self.operations.remove(&(data.prev_hop, data.local_pair_id));
+            let events = match self.take_forwarded(&data.epr, &slave) {
+                Some(Request {
+                    status: Status::WaitingForResponse(memory_cell),
+                    ..
+                }) => vec![self.discard(&memory_cell)],
+                _ => vec![],
+            };
+            return (events, vec![]);
+        }
+
+        let (operation, duration) = if data.epr.target_node_id == self.node_id {
+            // This is the final target node.
+            //
+            // If this is a single hop EPR request, then the EPR pair can
+            // be used immediately. Otherwise, X/Z corrections might be
+            // necessary dependin on the outcome of the BSM operations
+            // along the path.
+            let duration = if data.path.len() > 2 {
+                let rand = self.rng.gen_range(0..4);
+                if rand == 0 {
+                    // no corrections
+                    0.0
+                } else if rand == 1 {
+                    // both X and Z corrections
+                    self.properties.correction_duration * 2.0
+                } else {
+                    // only X or Z correction
+                    self.properties.correction_duration
+                }
+            } else {
+                0.0
+            };
+            (crate::processor::Operation::Correction, duration)
+        } else {
+            // This is an intermediate node, which has to perform entanglement
+            // swapping.
+            (
+                crate::processor::Operation::Swap,
+                self.properties.swapping_duration,
+            )
+        };
+        self.processor.submit(now, operation, duration, data)
+    }
+
     /// Handle completion of local operations for an ES.
     ///
     /// If the operation was a BSM, decide (randomly) if successful:
-    /// - Success: send `EsSuccess` to the previous hop and send a new
-    ///   `EsRequest` to the next hop.
-    /// - Failure: send `EsFailure` to the previous hop and free the local EPR
-    ///   pair (slave).
+    /// - Success: swap the local EPR pairs (slave and master), send
+    ///   `EsSuccess` to the previous hop and send a new `EsRequest` to the
+    ///   next hop.
+    /// - Failure: send `EsFailure` to the previous hop and free both the
+    ///   local EPR pairs (slave and master).
     ///
     /// If the operation was a correction:
     /// - Send `EsRemoteComplete` to source node.
     /// - Notify `EprResponse` (is_source = false) to the local app.
     fn handle_es_local_complete(
         &mut self,
-        _now: u64,
+        now: u64,
         data: EsRequestData,
     ) -> (Vec<Event>, Vec<Sample>) {
         assert_eq!(self.node_id, data.next_hop);
         assert!(data.path.len() >= 2);
 
-        let mut events = vec![];
-        let mut samples = vec![];
-        if self.node_id == *data.path.last().unwrap() {
-            // This node is the last element in the path, which means that the
-            // local operation was an X/Z correction, which never fails.
-            let src_node_id = *data.path.first().unwrap();
-            let epr = data.epr.clone();
-            events.push(Event::new_transfer(
-                EventType::NodeEvent(NodeEventData::EsRemoteComplete(data.epr)),
-                self.node_id,
-                src_node_id,
-            ));
-            let memory_cell = Some(MemoryCellId {
-                neighbor_node_id: data.prev_hop,
-                role: super::nic::Role::Slave,
-                local_pair_id: data.local_pair_id,
-            });
-            events.push(Event::new(
-                0.0_f64,
-                EventType::AppEvent(AppEventData::EprResponse(EprResponseData {
-                    epr,
-                    is_source: false,
-                    memory_cell,
-                })),
-            ));
+        // Release the processing unit, which can serve the next operation.
+        let (mut events, mut samples) = self.processor.complete(now, &data);
+        self.operations.remove(&(data.prev_hop, data.local_pair_id));
+
+        let slave = MemoryCellId {
+            neighbor_node_id: data.prev_hop,
+            role: super::nic::Role::Slave,
+            local_pair_id: data.local_pair_id,
+        };
+        let is_target = self.node_id == *data.path.last().unwrap();
+        let master = if is_target {
+            None
         } else {
+            match self.take_forwarded(&data.epr, &slave) {
+                Some(Request {
+                    status: Status::WaitingForResponse(memory_cell),
+                    ..
+                }) => Some(memory_cell),
+                _ => None,
+            }
+        };
+
+        // The memory cells have been flushed by a fault, or discarded by an
+        // abort, while the local operations were in progress, and the source
+        // node has been already notified. The EPR pair left, if any, is
+        // discarded at an intermediate node.
+        if !self.holds(&slave)
+            || (!is_target && master.as_ref().is_none_or(|master| !self.holds(master)))
+        {
+            log::debug!(
+                "node {}: memory cell flushed for EPR {}",
+                self.node_id,
+                data.epr
+            );
+            for memory_cell in std::iter::once(slave).chain(master) {
+                if !is_target && self.holds(&memory_cell) {
+                    events.push(self.discard(&memory_cell));
+                }
+            }
+            return (events, samples);
+        }
+
+        if let Some(master) = master {
             // This is an intermediate node.
             if self.rng.gen_bool(self.properties.swapping_success_prob) {
-                // Successful Bell-state measurement.
+                // Successful Bell-state measurement: the EPR pairs shared
+                // with the previous and next hops are joined into one, and
+                // the ES continues at the next hop.
+                let next_hop = master.neighbor_node_id;
+                events.push(Event::new_transfer(
+                    EventType::NodeEvent(NodeEventData::EsRequest(EsRequestData {
+                        epr: data.epr.clone(),
+                        prev_hop: self.node_id,
+                        next_hop,
+                        path: data.path.clone(),
+                        local_pair_id: master.local_pair_id,
+                        received: data.received,
+                        reserved: data.reserved,
+                    })),
+                    self.node_id,
+                    next_hop,
+                ));
+                events.push(Event::new(
+                    0.0_f64,
+                    EventType::NetworkEvent(NetworkEventData::EprSwap(EprSwapData {
+                        node_id: self.node_id,
+                        prev_memory_cell_id: slave,
+                        next_memory_cell_id: master,
+                    })),
+                ));
                 let dst_node_id = data.prev_hop;
                 events.push(Event::new_transfer(
                     EventType::NodeEvent(NodeEventData::EsSuccess(data)),
@@ -403,7 +1518,9 @@ impl Node {
                 ));
                 samples.push(Sample::ScalarAvg("bsm_prob".to_string(), 1.0));
             } else {
-                // Failed Bell-state measurement.
+                // Failed Bell-state measurement: both the EPR pairs are lost.
+                events.push(self.discard(&slave));
+                events.push(self.discard(&master));
                 let dst_node_id = data.prev_hop;
                 events.push(Event::new_transfer(
                     EventType::NodeEvent(NodeEventData::EsFailure(data)),
@@ -412,6 +1529,21 @@ impl Node {
                 ));
                 samples.push(Sample::ScalarAvg("bsm_prob".to_string(), 0.0));
             }
+        } else {
+            // This node is the last element in the path, which means that the
+            // local operation was an X/Z correction, which never fails.
+            let src_node_id = *data.path.first().unwrap();
+            let epr = data.epr.clone();
+            events.push(Event::new_transfer(
+                EventType::NodeEvent(NodeEventData::EsRemoteComplete(data.epr)),
+                self.node_id,
+                src_node_id,
+            ));
+            let response = self.respond(epr.clone(), false, slave);
+            let (mut new_events, mut new_samples) =
+                self.deliver(now, &epr, Some(response), true, None);
+            events.append(&mut new_events);
+            samples.append(&mut new_samples);
         }
 
         (events, samples)
@@ -420,21 +1552,43 @@ impl Node {
     /// Handle response received for an ES request.
     ///
     /// If failed:
-    /// - Communicate failure to the source of the path.
-    /// - Free local EPR pair (master).
+    /// - At the source node, free the local EPR pair (master) and retry.
+    /// - At an intermediate node, communicate the failure to the source of
+    ///   the path, since the local EPR pairs have been already swapped.
     ///
-    /// If success:
-    /// - Free previous EPR pair (if any).
+    /// If success, there is nothing to free: the EPR pair shared with the
+    /// next hop has been consumed by its Bell-state measurement, while the
+    /// local EPR pair at the source node is held until the ES completes.
     ///
-    /// In both cases remove the request from the pending queue
+    /// A failure is also received when the next hop does not hold the EPR
+    /// pair anymore, e.g., because it has been overwritten by a batch of EPR
+    /// pairs before the request arrived, in which case the request is
+    /// retried as with any other remote failure.
     fn handle_es_response(
         &mut self,
-        _now: u64,
+        now: u64,
         data: EsRequestData,
         success: bool,
     ) -> (Vec<Event>, Vec<Sample>) {
-        // XXX
-        (vec![], vec![])
+        assert_eq!(self.node_id, data.prev_hop);
+
+        if success {
+            return (vec![], vec![]);
+        }
+
+        if self.node_id == data.epr.source_node_id {
+            return self.handle_es_remote_failed(now, data.epr);
+        }
+
+        let src_node_id = data.epr.source_node_id;
+        (
+            vec![Event::new_transfer(
+                EventType::NodeEvent(NodeEventData::EsRemoteFailed(data.epr)),
+                self.node_id,
+                src_node_id,
+            )],
+            vec![],
+        )
     }
 
     /// Handle indication at the source node that a remote entanglement
@@ -442,6 +1596,9 @@ impl Node {
     ///
     /// Search for a pending request with matching `EprFiveTuple` and, if found,
     /// notify `EprResponse` (is_source = true) to the application.
+    ///
+    /// The indication is ignored if the request has been failed meanwhile
+    /// because of a fault or its deadline.
     fn handle_es_remote_complete(
         &mut self,
         now: u64,
@@ -450,28 +1607,37 @@ impl Node {
         assert_eq!(self.node_id, epr.source_node_id);
 
         for requests in &mut self.pending_requests.values_mut() {
-            if let Some(epr_ndx) = requests.iter().position(|x| x.epr == epr) {
-                let request = requests.swap_remove(epr_ndx);
+            if let Some(epr_ndx) = requests
+                .iter()
+                .position(|x| x.epr == epr && matches!(x.status, Status::WaitingForResponse(_)))
+            {
+                let request = requests.remove(epr_ndx);
                 if let Status::WaitingForResponse(memory_cell) = request.status {
-                    let events = vec![Event::new(
-                        0.0_f64,
-                        EventType::AppEvent(AppEventData::EprResponse(EprResponseData {
-                            epr,
-                            is_source: true,
-                            memory_cell: Some(memory_cell),
-                        })),
-                    )];
-                    return (
-                        events,
-                        vec![Sample::Series(
-                            "epr-request-latency".to_string(),
-                            vec![
-                                self.node_id.to_string(),
-                                (request.path.len() - 1).to_string(),
-                            ],
-                            crate::utils::to_seconds(now - request.received),
-                        )],
-                    );
+                    if let Some(delivered) = self
+                        .reservations
+                        .get_mut(&(epr.source_port, epr.request_id))
+                    {
+                        *delivered += 1;
+                    }
+                    let response = self.respond(epr.clone(), true, memory_cell);
+                    let (events, mut samples) =
+                        self.deliver(now, &epr, Some(response), true, Some(request.received));
+                    samples.push(Sample::ScalarAvg("timeout_rate".to_string(), 0.0));
+                    samples.push(Sample::ScalarAvg("abandon_rate".to_string(), 0.0));
+                    samples.push(Sample::Series(
+                        "class-latency".to_string(),
+                        vec![epr.class.to_string()],
+                        crate::utils::to_seconds(now - request.received),
+                    ));
+                    samples.push(Sample::Series(
+                        "epr-request-latency".to_string(),
+                        vec![
+                            self.node_id.to_string(),
+                            (request.path.len() - 1).to_string(),
+                        ],
+                        crate::utils::to_seconds(now - request.received),
+                    ));
+                    return (events, samples);
                 } else {
                     panic!(
                         "wrong queued request at node {} for EPR {}: {:?}",
@@ -481,9 +1647,138 @@ impl Node {
             }
         }
 
-        panic!(
+        log::debug!(
             "could not find a queued request at node {} for EPR {}",
-            self.node_id, epr
+            self.node_id,
+            epr
+        );
+        (vec![], vec![])
+    }
+
+    /// Deliver the response for an EPR pair to the application, unless it
+    /// must be held until enough EPR pairs of the same request are
+    /// established, depending on the delivery.
+    ///
+    /// If the EPR pair has failed, i.e., `established` is false, then the
+    /// response to the application, if any, is held as well, and the number
+    /// of EPR pairs to be established is reduced accordingly.
+    ///
+    /// At the source node, `received` is the time when the request was
+    /// received, which is used to measure the latency of the first and last
+    /// EPR pairs established.
+    fn deliver(
+        &mut self,
+        now: u64,
+        epr: &EprFiveTuple,
+        response: Option<Event>,
+        established: bool,
+        received: Option<u64>,
+    ) -> (Vec<Event>, Vec<Sample>) {
+        if epr.number_of_pairs <= 1 {
+            return (response.into_iter().collect(), vec![]);
+        }
+
+        let key = (epr.source_node_id, epr.source_port, epr.request_id);
+        let batch = self.batches.entry(key).or_default();
+        if established {
+            batch.established += 1;
+        } else {
+            batch.failed += 1;
+        }
+        batch.held.extend(response);
+        let threshold = epr
+            .delivery
+            .threshold(epr.number_of_pairs)
+            .min(epr.number_of_pairs - batch.failed);
+        if batch.established < threshold {
+            return (vec![], vec![]);
+        }
+
+        let events = std::mem::take(&mut batch.held);
+        let settled = batch.established + batch.failed == epr.number_of_pairs;
+        let first = batch.established == threshold;
+        if settled {
+            self.batches.remove(&key);
+        }
+
+        let mut samples = vec![];
+        if let Some(received) = received.filter(|_| established) {
+            let labels = vec![self.node_id.to_string(), epr.number_of_pairs.to_string()];
+            let latency = crate::utils::to_seconds(now - received);
+            if first {
+                samples.push(Sample::Series(
+                    "epr-first-pair-latency".to_string(),
+                    labels.clone(),
+                    latency,
+                ));
+            }
+            if settled {
+                samples.push(Sample::Series(
+                    "epr-last-pair-latency".to_string(),
+                    labels,
+                    latency,
+                ));
+            }
+        }
+        (events, samples)
+    }
+
+    /// Free the memory cell of a local EPR pair that is no longer needed and
+    /// notify the network, which removes the EPR pair from the register once
+    /// both its halves are consumed or discarded.
+    fn discard(&mut self, memory_cell: &MemoryCellId) -> Event {
+        if let Some(nic) = self
+            .nics(&memory_cell.role)
+            .get_mut(&memory_cell.neighbor_node_id)
+        {
+            nic.consume(memory_cell.local_pair_id);
+        }
+        Event::new(
+            0.0_f64,
+            EventType::NetworkEvent(NetworkEventData::EprDiscard(EprDiscardData {
+                node_id: self.node_id,
+                epr_pair_id: memory_cell.local_pair_id,
+            })),
+        )
+    }
+
+    /// Return the event notifying the response to an EPR request to the
+    /// application. With measure-directly requests, the memory cell is
+    /// released and the qubit is measured before notifying the outcome.
+    fn respond(&mut self, epr: EprFiveTuple, is_source: bool, memory_cell: MemoryCellId) -> Event {
+        if let RequestType::CreateAndKeep = epr.request_type {
+            return Event::new(
+                0.0_f64,
+                EventType::AppEvent(AppEventData::EprResponse(EprResponseData {
+                    epr,
+                    is_source,
+                    memory_cell: Some(memory_cell),
+                    outcome: None,
+                })),
+            );
+        }
+
+        self.get_nic(memory_cell.neighbor_node_id, &memory_cell.role)
+            .consume(memory_cell.local_pair_id);
+        let (req_app_node_id, req_app_port) = if is_source {
+            (epr.source_node_id, epr.source_port)
+        } else {
+            (epr.target_node_id, epr.target_port)
+        };
+        Event::new(
+            0.0_f64,
+            EventType::NetworkEvent(NetworkEventData::EprMeasure(EprMeasureData {
+                req_app_node_id,
+                req_app_port,
+                measure_node_id: self.node_id,
+                epr_pair_id: memory_cell.local_pair_id,
+                response: Some(EprResponseData {
+                    epr,
+                    is_source,
+                    memory_cell: None,
+                    outcome: None,
+                }),
+            })),
         )
     }
 
@@ -491,7 +1786,9 @@ impl Node {
     /// swapping procedure has failed.
     ///
     /// Search for a pending request with matching `EprFiveTuple` and, if found,
-    /// free the local EPR pair and reschedule the end-to-end request.
+    /// discard the local EPR pair and reschedule the end-to-end request,
+    /// unless the maximum number of retries is reached, in which case the
+    /// failure is notified to the application.
     fn handle_es_remote_failed(
         &mut self,
         now: u64,
@@ -501,38 +1798,233 @@ impl Node {
 
         for requests in &mut self.pending_requests.values_mut() {
             if let Some(epr_ndx) = requests.iter().position(|x| x.epr == epr) {
-                let request = requests.swap_remove(epr_ndx);
-                return self.handle_epr_request_app(now, request.received, request.epr);
+                let request = requests.remove(epr_ndx);
+                let mut events = vec![];
+                if let Status::WaitingForResponse(memory_cell) = &request.status {
+                    events.push(self.discard(memory_cell));
+                }
+                let (mut new_events, samples) = if request
+                    .epr
+                    .max_retries
+                    .is_some_and(|max_retries| request.retries >= max_retries)
+                {
+                    self.fail(now, request.epr, &request.path, None, false)
+                } else {
+                    let peer = self.enqueue(request.received, request.epr, request.retries + 1);
+                    self.schedule_pending_requests(now, peer)
+                };
+                events.append(&mut new_events);
+                return (events, samples);
             }
         }
 
         (vec![], vec![])
     }
 
-    /// Schedule requests pending for a given peer, if possible.
-    fn schedule_pending_requests(&mut self, peer: u32) -> (Vec<Event>, Vec<Sample>) {
+    /// Handle the abort of an EPR pair whose request failed at the source
+    /// node, which is forwarded along the path one hop at a time.
+    ///
+    /// The node where the ES is in progress discards the EPR pairs held for
+    /// it, i.e., shared with the previous hop and, if any, with the next hop,
+    /// in which case its local operations are withdrawn, if still waiting for
+    /// a processing unit, or do not complete otherwise. The target node
+    /// counts the EPR pair as failed, so that the responses held for the
+    /// other EPR pairs of the same request are not delayed, unless it has
+    /// been already delivered to the application.
+    fn handle_es_abort(&mut self, now: u64, data: EsAbortData) -> (Vec<Event>, Vec<Sample>) {
+        assert_eq!(self.node_id, data.node_id);
+
+        let mut events = vec![];
+        let mut samples = vec![];
+        let mut pending = data.pending;
+        if let Some((prev_hop, local_pair_id)) = self
+            .operations
+            .iter()
+            .find(|((prev_hop, _), epr)| *prev_hop == data.prev_hop && **epr == data.epr)
+            .map(|(key, _)| *key)
+        {
+            self.operations.remove(&(prev_hop, local_pair_id));
+            samples.append(&mut self.processor.cancel(&data.epr, local_pair_id));
+            let slave = MemoryCellId {
+                neighbor_node_id: prev_hop,
+                role: super::nic::Role::Slave,
+                local_pair_id,
+            };
+            if let Some(Request {
+                status: Status::WaitingForResponse(master),
+                ..
+            }) = self.take_forwarded(&data.epr, &slave)
+            {
+                events.push(self.discard(&master));
+            }
+            events.push(self.discard(&slave));
+            pending = true;
+        }
+        log::debug!(
+            "node {}: ES aborted for EPR {} ({})",
+            self.node_id,
+            data.epr,
+            if pending { "pending" } else { "gone past" }
+        );
+
+        if self.node_id == data.epr.target_node_id {
+            if pending {
+                let (mut new_events, mut new_samples) =
+                    self.deliver(now, &data.epr, None, false, None);
+                events.append(&mut new_events);
+                samples.append(&mut new_samples);
+            }
+            return (events, samples);
+        }
+
+        let pos = data
+            .path
+            .iter()
+            .position(|x| *x == self.node_id)
+            .expect("this node is not present in the path of an EsAbort");
+        let next_hop = data.path[pos + 1];
+        events.push(Event::new_transfer(
+            EventType::NodeEvent(NodeEventData::EsAbort(EsAbortData {
+                node_id: next_hop,
+                prev_hop: self.node_id,
+                pending,
+                ..data
+            })),
+            self.node_id,
+            next_hop,
+        ));
+        (events, samples)
+    }
+
+    /// Return the number of requests queued for a given peer.
+    pub fn num_queued(&self, peer: u32) -> usize {
+        self.pending_requests.get(&peer).map_or(0, |requests| {
+            requests
+                .iter()
+                .filter(|request| matches!(request.status, Status::Queued))
+                .count()
+        })
+    }
+
+    /// Schedule requests pending for a given peer, if possible, in the order
+    /// decided by the scheduling policy.
+    ///
+    /// A request with a minimum fidelity is skipped, and remains queued, if
+    /// the estimated fidelity with the newest EPR pair does not meet it.
+    /// Every request is counted at most once in the fidelity skips.
+    ///
+    /// The memory qubits held by the advance reservations in progress on the
+    /// logical link towards the peer are used only by the requests reserved.
+    ///
+    /// The requests forwarded by other nodes are scheduled in the same way,
+    /// but their minimum fidelity is not checked again.
+    fn schedule_pending_requests(&mut self, now: u64, peer: u32) -> (Vec<Event>, Vec<Sample>) {
         let log_status = format!("{self}");
         let mut events = vec![];
+        let mut samples = vec![];
+        let mut swaps = vec![];
+        let held = self
+            .calendars
+            .borrow()
+            .get(&(self.node_id, peer))
+            .map_or(0, |calendar| calendar.held(now));
+        let unheld = match held {
+            0 => u32::MAX,
+            _ => self
+                .logical_topology
+                .memory_qubits(&[self.node_id, peer])
+                .saturating_sub(held),
+        };
         if let Some(nic) = self.nics_master.get_mut(&peer) {
             if let Some(requests) = &mut self.pending_requests.get_mut(&peer) {
                 if !requests.is_empty() {
                     log::debug!("{log_status}");
                 }
-                for request in requests.iter_mut() {
+                let mut order = self.scheduler.order(
+                    &requests
+                        .iter()
+                        .map(|request| crate::request_scheduler::QueuedRequest {
+                            class: request.epr.class,
+                            deadline: request
+                                .epr
+                                .timeout
+                                .map(|timeout| request.received + timeout),
+                            tag: request.tag,
+                        })
+                        .collect::<Vec<_>>(),
+                );
+                // The EPR pairs reserved in advance are served first, in the
+                // order of the scheduling policy, since the sort is stable.
+                order.sort_by_key(|ndx| !requests[*ndx].reserved);
+                let mut unreserved = requests
+                    .iter()
+                    .filter(|request| {
+                        !request.reserved && matches!(request.status, Status::WaitingForResponse(_))
+                    })
+                    .count() as u32;
+                for ndx in order {
+                    let request = &mut requests[ndx];
                     if let Status::Queued = request.status {
+                        if !request.reserved && unreserved >= unheld {
+                            break;
+                        }
                         if let Some(local_pair_id) = nic.newest_valid() {
+                            // The minimum fidelity is checked at the source
+                            // node only.
+                            if let Some(min_fidelity) =
+                                request.epr.min_fidelity.filter(|_| request.slave.is_none())
+                            {
+                                let cell =
+                                    nic.data(local_pair_id).expect("valid cell without data");
+                                let fidelity = Self::estimate_fidelity(
+                                    &self.properties,
+                                    &self.link_fidelities,
+                                    now,
+                                    &request.path,
+                                    &cell,
+                                );
+                                if fidelity < min_fidelity.0 {
+                                    if !request.skipped {
+                                        request.skipped = true;
+                                        samples.push(Sample::ScalarCount(
+                                            "fidelity_skips".to_string(),
+                                        ));
+                                    }
+                                    continue;
+                                }
+                            }
                             nic.used(local_pair_id);
-                            events.push(Event::new_transfer(
-                                EventType::NodeEvent(NodeEventData::EsRequest(EsRequestData {
+                            if !request.reserved {
+                                unreserved += 1;
+                            }
+                            self.scheduler.served(request.tag);
+                            match &request.slave {
+                                None => events.push(Event::new_transfer(
+                                    EventType::NodeEvent(NodeEventData::EsRequest(EsRequestData {
+                                        epr: request.epr.clone(),
+                                        prev_hop: self.node_id,
+                                        next_hop: peer,
+                                        path: request.path.clone(),
+                                        local_pair_id,
+                                        received: request.received,
+                                        reserved: request.reserved,
+                                    })),
+                                    self.node_id,
+                                    peer,
+                                )),
+                                // A request forwarded by the previous hop is
+                                // served by swapping the EPR pairs, once
+                                // retrieved from the memory.
+                                Some(slave) => swaps.push(EsRequestData {
                                     epr: request.epr.clone(),
-                                    prev_hop: self.node_id,
-                                    next_hop: peer,
+                                    prev_hop: slave.neighbor_node_id,
+                                    next_hop: self.node_id,
                                     path: request.path.clone(),
-                                    local_pair_id,
-                                })),
-                                self.node_id,
-                                peer,
-                            ));
+                                    local_pair_id: slave.local_pair_id,
+                                    received: request.received,
+                                    reserved: request.reserved,
+                                }),
+                            }
                             request.status = Status::WaitingForResponse(MemoryCellId {
                                 neighbor_node_id: peer,
                                 role: crate::nic::Role::Master,
@@ -546,7 +2038,12 @@ impl Node {
             }
         }
 
-        (events, vec![])
+        for data in swaps {
+            let (mut new_events, mut new_samples) = self.read_memory(now, data);
+            events.append(&mut new_events);
+            samples.append(&mut new_samples);
+        }
+        (events, samples)
     }
 }
 
@@ -579,7 +2076,9 @@ impl EventHandler for Node {
     fn initial(&mut self) -> Vec<Event> {
         let mut events = vec![];
 
-        for application in self.applications.values_mut() {
+        let mut applications = self.applications.iter_mut().collect::<Vec<_>>();
+        applications.sort_by_key(|(port, _)| **port);
+        for (_port, application) in applications {
             events.append(&mut application.initial());
         }
 
@@ -7,6 +7,23 @@ pub enum NodeType {
     SAT,
     /// On ground station.
     OGS,
+    /// Terrestrial repeater, connected to other nodes via fiber links only.
+    REP,
+    /// High-altitude platform, e.g., a drone or balloon, relaying between
+    /// ground and space via free-space links.
+    HAP,
+    /// End-user node, connected to an OGS via a fiber last mile.
+    USER,
+}
+
+impl NodeType {
+    /// Return true if the node can have free-space optical links.
+    pub fn free_space(&self) -> bool {
+        match self {
+            NodeType::SAT | NodeType::OGS | NodeType::HAP => true,
+            NodeType::REP | NodeType::USER => false,
+        }
+    }
 }
 
 impl std::fmt::Display for NodeType {
@@ -17,6 +34,9 @@ impl std::fmt::Display for NodeType {
             match self {
                 NodeType::SAT => "SAT",
                 NodeType::OGS => "OGS",
+                NodeType::REP => "REP",
+                NodeType::HAP => "HAP",
+                NodeType::USER => "USER",
             }
         )
     }
@@ -30,12 +50,42 @@ pub struct NodeWeight {
     pub memory_qubits: u32,
     /// Fidelity decay rate of a qubit in memory.
     pub decay_rate: f64,
+    /// Probability that a qubit is stored successfully in memory.
+    #[serde(default = "default_one")]
+    pub write_efficiency: f64,
+    /// Probability that a qubit is retrieved successfully from memory.
+    #[serde(default = "default_one")]
+    pub read_efficiency: f64,
+    /// Time to store a qubit in memory, in s.
+    #[serde(default)]
+    pub write_latency: f64,
+    /// Time to retrieve a qubit from memory, in s.
+    #[serde(default)]
+    pub read_latency: f64,
+    /// Number of storage qubits, in addition to the memory qubits used for
+    /// communication. If non-zero, fresh EPR pairs are transferred into
+    /// storage before they can be used.
+    #[serde(default)]
+    pub storage_qubits: u32,
+    /// Fidelity decay rate of a qubit in storage.
+    #[serde(default)]
+    pub storage_decay_rate: f64,
+    /// Time to transfer a qubit from communication to storage, in s.
+    #[serde(default)]
+    pub transfer_duration: f64,
+    /// Fidelity of the transfer operation, which depolarizes the EPR pair.
+    #[serde(default = "default_one")]
+    pub transfer_fidelity: f64,
     /// Entanglement swapping success probability.
     pub swapping_success_prob: f64,
     /// Entanglement swapping duration, in s.
     pub swapping_duration: f64,
     /// Duration of the local operations to correct end-to-end pairs, in s.
     pub correction_duration: f64,
+    /// Number of processing units that perform local operations in
+    /// parallel, 0 for unlimited.
+    #[serde(default)]
+    pub processing_units: u32,
     /// Number of detectors.
     pub detectors: u32,
     /// Number of transmitters, i.e., entangled photon source generators.
@@ -43,6 +93,27 @@ pub struct NodeWeight {
     /// Capacity of transmitters, i.e., rate at which they generate
     /// EPR pairs.
     pub capacity: f64,
+    /// Entangled photon source of the transmitters. If present, it
+    /// determines the capacity, instead of `capacity`, and depolarizes
+    /// the EPR pairs generated.
+    #[serde(default)]
+    pub source: Option<SpdcSource>,
+    /// Multiplexing of the transmitters, which attempt many modes per pulse
+    /// and generate EPR pairs in batches. If present, it determines the
+    /// capacity, instead of `capacity` and `source`.
+    #[serde(default)]
+    pub multiplexing: Option<MultiplexedSource>,
+    /// True if the node has no memory, hence its detectors measure the
+    /// photons as soon as they arrive.
+    #[serde(default)]
+    pub memoryless: bool,
+    /// Maximum timing jitter of the detectors, in s.
+    #[serde(default)]
+    pub detection_jitter: f64,
+}
+
+fn default_one() -> f64 {
+    1.0
 }
 
 impl std::fmt::Display for NodeWeight {
@@ -63,12 +134,25 @@ impl NodeWeight {
             node_type: NodeType::SAT,
             memory_qubits: 1,
             decay_rate: 0.0,
+            write_efficiency: 1.0,
+            read_efficiency: 1.0,
+            write_latency: 0.0,
+            read_latency: 0.0,
+            storage_qubits: 0,
+            storage_decay_rate: 0.0,
+            transfer_duration: 0.0,
+            transfer_fidelity: 1.0,
             swapping_success_prob: 1.0,
             swapping_duration: 0.001,
             correction_duration: 0.0,
+            processing_units: 0,
             detectors: 1,
             transmitters: 1,
             capacity: 1.0,
+            source: None,
+            multiplexing: None,
+            memoryless: false,
+            detection_jitter: 0.0,
         }
     }
 
@@ -77,18 +161,134 @@ impl NodeWeight {
             node_type: NodeType::OGS,
             memory_qubits: 1,
             decay_rate: 0.0,
+            write_efficiency: 1.0,
+            read_efficiency: 1.0,
+            write_latency: 0.0,
+            read_latency: 0.0,
+            storage_qubits: 0,
+            storage_decay_rate: 0.0,
+            transfer_duration: 0.0,
+            transfer_fidelity: 1.0,
+            swapping_success_prob: 1.0,
+            swapping_duration: 0.0,
+            correction_duration: 0.001,
+            processing_units: 0,
+            detectors: 1,
+            transmitters: 0,
+            capacity: 0.0,
+            source: None,
+            multiplexing: None,
+            memoryless: false,
+            detection_jitter: 0.0,
+        }
+    }
+
+    pub fn default_rep() -> Self {
+        Self {
+            node_type: NodeType::REP,
+            memory_qubits: 1,
+            decay_rate: 0.0,
+            write_efficiency: 1.0,
+            read_efficiency: 1.0,
+            write_latency: 0.0,
+            read_latency: 0.0,
+            storage_qubits: 0,
+            storage_decay_rate: 0.0,
+            transfer_duration: 0.0,
+            transfer_fidelity: 1.0,
+            swapping_success_prob: 1.0,
+            swapping_duration: 0.001,
+            correction_duration: 0.0,
+            processing_units: 0,
+            detectors: 1,
+            transmitters: 0,
+            capacity: 0.0,
+            source: None,
+            multiplexing: None,
+            memoryless: false,
+            detection_jitter: 0.0,
+        }
+    }
+
+    pub fn default_hap() -> Self {
+        Self {
+            node_type: NodeType::HAP,
+            memory_qubits: 1,
+            decay_rate: 0.0,
+            write_efficiency: 1.0,
+            read_efficiency: 1.0,
+            write_latency: 0.0,
+            read_latency: 0.0,
+            storage_qubits: 0,
+            storage_decay_rate: 0.0,
+            transfer_duration: 0.0,
+            transfer_fidelity: 1.0,
             swapping_success_prob: 1.0,
+            swapping_duration: 0.001,
+            correction_duration: 0.0,
+            processing_units: 0,
+            detectors: 1,
+            transmitters: 1,
+            capacity: 1.0,
+            source: None,
+            multiplexing: None,
+            memoryless: false,
+            detection_jitter: 0.0,
+        }
+    }
+
+    pub fn default_user() -> Self {
+        Self {
+            node_type: NodeType::USER,
+            memory_qubits: 1,
+            decay_rate: 0.0,
+            write_efficiency: 1.0,
+            read_efficiency: 1.0,
+            write_latency: 0.0,
+            read_latency: 0.0,
+            storage_qubits: 0,
+            storage_decay_rate: 0.0,
+            transfer_duration: 0.0,
+            transfer_fidelity: 1.0,
+            swapping_success_prob: 0.0,
             swapping_duration: 0.0,
             correction_duration: 0.001,
+            processing_units: 0,
             detectors: 1,
             transmitters: 0,
             capacity: 0.0,
+            source: None,
+            multiplexing: None,
+            memoryless: false,
+            detection_jitter: 0.0,
+        }
+    }
+
+    /// Return true if the node can act as an intermediate repeater, i.e.,
+    /// it can perform entanglement swapping.
+    pub fn can_swap(&self) -> bool {
+        self.swapping_success_prob > 0.0 && !self.memoryless
+    }
+
+    /// Return the rate at which the transmitters generate EPR pairs, which
+    /// is determined by the multiplexing or the source, if any.
+    pub fn tx_capacity(&self) -> f64 {
+        match (&self.multiplexing, &self.source) {
+            (Some(multiplexing), _) => multiplexing.rate(),
+            (None, Some(source)) => source.rate(),
+            (None, None) => self.capacity,
         }
     }
 
     fn valid(&self) -> anyhow::Result<()> {
         let mut errors = vec![];
-        if self.memory_qubits == 0 && self.detectors > 0 {
+        if self.memoryless && (self.memory_qubits > 0 || self.storage_qubits > 0) {
+            errors.push(format!(
+                "memoryless node with {} memory qubits and {} storage qubits",
+                self.memory_qubits, self.storage_qubits
+            ))
+        }
+        if self.memory_qubits == 0 && self.detectors > 0 && !self.memoryless {
             errors.push(format!(
                 "vanishing memory qubits with {} detectors",
                 self.detectors
@@ -103,6 +303,40 @@ impl NodeWeight {
         if self.decay_rate < 0.0 {
             errors.push(format!("decay rate ({}) < 0", self.decay_rate))
         }
+        for (name, efficiency) in [
+            ("write", self.write_efficiency),
+            ("read", self.read_efficiency),
+        ] {
+            if !(0.0..=1.0).contains(&efficiency) {
+                errors.push(format!("invalid {} efficiency ({})", name, efficiency))
+            }
+        }
+        for (name, latency) in [("write", self.write_latency), ("read", self.read_latency)] {
+            if latency < 0.0 {
+                errors.push(format!("{} latency ({}) < 0", name, latency))
+            }
+        }
+        if self.detection_jitter < 0.0 {
+            errors.push(format!("detection jitter ({}) < 0", self.detection_jitter))
+        }
+        if self.storage_decay_rate < 0.0 {
+            errors.push(format!(
+                "storage decay rate ({}) < 0",
+                self.storage_decay_rate
+            ))
+        }
+        if self.transfer_duration < 0.0 {
+            errors.push(format!(
+                "transfer duration ({}) < 0",
+                self.transfer_duration
+            ))
+        }
+        if !(0.0..=1.0).contains(&self.transfer_fidelity) {
+            errors.push(format!(
+                "invalid transfer fidelity ({})",
+                self.transfer_fidelity
+            ))
+        }
         if self.swapping_success_prob < 0.0 || self.swapping_success_prob > 1.0 {
             errors.push(format!(
                 "invalid swapping success probability ({})",
@@ -112,6 +346,12 @@ impl NodeWeight {
         if self.capacity < 0.0 {
             errors.push(format!("capacity ({}) < 0", self.capacity))
         }
+        if let Some(source) = &self.source {
+            source.valid(&mut errors);
+        }
+        if let Some(multiplexing) = &self.multiplexing {
+            multiplexing.valid(&mut errors);
+        }
 
         if !errors.is_empty() {
             anyhow::bail!(
@@ -123,39 +363,214 @@ impl NodeWeight {
     }
 }
 
+/// Entangled photon source based on spontaneous parametric down-conversion
+/// (SPDC), which emits multiple pairs with a probability that increases with
+/// the mean photon number, i.e., the pump power.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct SpdcSource {
+    /// Mean number of photon pairs emitted per pulse.
+    pub mean_photon_number: f64,
+    /// Repetition rate of the pump laser, in Hz.
+    pub repetition_rate: f64,
+    /// Probability that a photon pair is heralded once emitted.
+    pub heralding_efficiency: f64,
+}
+
+impl SpdcSource {
+    /// Return the rate at which EPR pairs are heralded, in Hz.
+    ///
+    /// The number of pairs per pulse follows a thermal distribution, hence
+    /// at least one is emitted with probability μ / (1 + μ).
+    pub fn rate(&self) -> f64 {
+        self.repetition_rate * self.heralding_efficiency * self.mean_photon_number
+            / (1.0 + self.mean_photon_number)
+    }
+
+    /// Return the visibility of the EPR pairs, which is reduced by the
+    /// multi-pair emissions as 1 / (1 + 2μ).
+    pub fn visibility(&self) -> f64 {
+        1.0 / (1.0 + 2.0 * self.mean_photon_number)
+    }
+
+    fn valid(&self, errors: &mut Vec<String>) {
+        if self.mean_photon_number <= 0.0 {
+            errors.push(format!(
+                "mean photon number ({}) <= 0",
+                self.mean_photon_number
+            ))
+        }
+        if self.repetition_rate < 0.0 {
+            errors.push(format!("repetition rate ({}) < 0", self.repetition_rate))
+        }
+        if !(0.0..=1.0).contains(&self.heralding_efficiency) {
+            errors.push(format!(
+                "invalid heralding efficiency ({})",
+                self.heralding_efficiency
+            ))
+        }
+    }
+}
+
+/// Multiplexed entanglement source, which attempts to generate an EPR pair
+/// over many (frequency, temporal, or spatial) modes at every pulse.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct MultiplexedSource {
+    /// Rate of the pulses, in Hz.
+    pub pulse_rate: f64,
+    /// Number of modes attempted per pulse.
+    pub num_modes: u32,
+    /// Probability that an EPR pair is generated in a mode.
+    pub mode_success_prob: f64,
+}
+
+impl MultiplexedSource {
+    /// Return the average rate at which EPR pairs are generated, in Hz.
+    pub fn rate(&self) -> f64 {
+        self.pulse_rate * self.num_modes as f64 * self.mode_success_prob
+    }
+
+    fn valid(&self, errors: &mut Vec<String>) {
+        if self.pulse_rate <= 0.0 {
+            errors.push(format!("pulse rate ({}) <= 0", self.pulse_rate))
+        }
+        if self.num_modes == 0 {
+            errors.push("no multiplexed modes".to_string())
+        }
+        if !(0.0..=1.0).contains(&self.mode_success_prob) {
+            errors.push(format!(
+                "invalid mode success probability ({})",
+                self.mode_success_prob
+            ))
+        }
+    }
+}
+
+/// Characteristics of a terrestrial fiber link.
+///
+/// The EPR pairs are generated by a heralded source embedded in the link,
+/// hence fiber links do not consume transmitters of the end nodes.
+#[derive(Debug, Clone, Copy, PartialOrd, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct FiberParams {
+    /// Attenuation of the fiber, in dB/km.
+    pub attenuation: f64,
+    /// Refractive index of the fiber.
+    pub refractive_index: f64,
+    /// Rate at which the heralded source attempts to generate EPR pairs.
+    pub attempt_rate: f64,
+    /// Fidelity of the EPR pairs generated.
+    pub fidelity: f64,
+}
+
+impl Default for FiberParams {
+    fn default() -> Self {
+        Self {
+            attenuation: 0.2,
+            refractive_index: 1.468,
+            attempt_rate: 1000.0,
+            fidelity: 0.95,
+        }
+    }
+}
+
+impl FiberParams {
+    fn valid(&self) -> anyhow::Result<()> {
+        let mut errors = vec![];
+        if self.attenuation < 0.0 {
+            errors.push(format!("attenuation ({}) < 0", self.attenuation))
+        }
+        if self.refractive_index < 1.0 {
+            errors.push(format!("refractive index ({}) < 1", self.refractive_index))
+        }
+        if self.attempt_rate < 0.0 {
+            errors.push(format!("attempt rate ({}) < 0", self.attempt_rate))
+        }
+        if self.fidelity < 0.0 || self.fidelity > 1.0 {
+            errors.push(format!("invalid fidelity ({})", self.fidelity))
+        }
+        if !errors.is_empty() {
+            anyhow::bail!("invalid fiber parameters: {}", errors.join(","))
+        }
+        Ok(())
+    }
+
+    /// Return the probability that a photon crosses a fiber of given length,
+    /// in m.
+    fn transmissivity(&self, distance: f64) -> f64 {
+        db_to_prob(self.attenuation * distance / 1000.0)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialOrd, PartialEq)]
+pub enum LinkType {
+    /// Free-space optical link, with or from a satellite.
+    #[default]
+    FreeSpace,
+    /// Terrestrial fiber link.
+    Fiber(FiberParams),
+}
+
 #[derive(Debug, Clone, Copy, Default, PartialOrd, PartialEq)]
 pub struct EdgeWeight {
     /// Distance between two nodes, in m.
     distance: f64,
+    /// Type of the link.
+    link: LinkType,
 }
 
 impl std::fmt::Display for EdgeWeight {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.distance)
+        match self.link {
+            LinkType::FreeSpace => write!(f, "{}", self.distance),
+            LinkType::Fiber(_) => write!(f, "{} (fiber)", self.distance),
+        }
     }
 }
 
-impl petgraph::algo::FloatMeasure for EdgeWeight {
-    fn zero() -> Self {
+impl EdgeWeight {
+    fn free_space(distance: f64) -> Self {
         Self {
-            distance: f64::zero(),
+            distance,
+            link: LinkType::FreeSpace,
         }
     }
 
-    fn infinite() -> Self {
+    fn fiber(distance: f64, fiber_params: FiberParams) -> Self {
         Self {
-            distance: f64::infinite(),
+            distance,
+            link: LinkType::Fiber(fiber_params),
+        }
+    }
+
+    /// Return the type of the link.
+    pub fn link(&self) -> &LinkType {
+        &self.link
+    }
+
+    /// Return the latency to cross the link, in s.
+    pub fn latency(&self) -> f64 {
+        let latency = crate::utils::distance_to_latency(self.distance);
+        match self.link {
+            LinkType::FreeSpace => latency,
+            LinkType::Fiber(fiber_params) => latency * fiber_params.refractive_index,
         }
     }
 }
 
+impl petgraph::algo::FloatMeasure for EdgeWeight {
+    fn zero() -> Self {
+        Self::free_space(f64::zero())
+    }
+
+    fn infinite() -> Self {
+        Self::free_space(f64::infinite())
+    }
+}
+
 impl std::ops::Add for EdgeWeight {
     type Output = EdgeWeight;
 
     fn add(self, rhs: Self) -> Self::Output {
-        EdgeWeight {
-            distance: self.distance + rhs.distance,
-        }
+        EdgeWeight::free_space(self.distance + rhs.distance)
     }
 }
 
@@ -171,6 +586,18 @@ pub struct StaticFidelities {
     pub f_og: f64,
     /// Two hops, ground-to-ground.
     pub f_gg: f64,
+    /// One hop, ground-to-orbit uplink (the source is on an OGS).
+    #[serde(default = "default_one")]
+    pub f_u: f64,
+    /// Two hops, ground-to-orbit uplinks (the source is on an OGS).
+    #[serde(default = "default_one")]
+    pub f_uu: f64,
+    /// One hop, with a high-altitude platform at either end.
+    #[serde(default = "default_one")]
+    pub f_h: f64,
+    /// Two hops, with a high-altitude platform as source or receiver.
+    #[serde(default = "default_one")]
+    pub f_hh: f64,
 }
 
 impl Default for StaticFidelities {
@@ -181,6 +608,10 @@ impl Default for StaticFidelities {
             f_oo: 1.0,
             f_og: 1.0,
             f_gg: 1.0,
+            f_u: 1.0,
+            f_uu: 1.0,
+            f_h: 1.0,
+            f_hh: 1.0,
         }
     }
 }
@@ -193,6 +624,10 @@ impl StaticFidelities {
             (self.f_oo, "two-hops, orbit-to-orbit"),
             (self.f_og, "two-hops, orbit-to-ground"),
             (self.f_gg, "two-hops, ground-to-ground"),
+            (self.f_u, "one-hop, ground-to-orbit uplink"),
+            (self.f_uu, "two-hops, ground-to-orbit uplinks"),
+            (self.f_h, "one-hop, high-altitude platform"),
+            (self.f_hh, "two-hops, high-altitude platform"),
         ];
         let mut errors = vec![];
         for (fidelity, name) in fidelities {
@@ -219,6 +654,10 @@ pub struct GridParams {
     pub num_orbits: u32,
     /// Number of satellites in each orbit.
     pub orbit_length: u32,
+    /// Loss of ground-to-orbit uplinks, i.e., when the source is on an OGS,
+    /// in dB. Uplinks have no additional loss if not specified.
+    #[serde(default)]
+    pub uplink_loss: f64,
 }
 
 impl Default for GridParams {
@@ -228,10 +667,180 @@ impl Default for GridParams {
             ground_to_orbit_distance: 1000000.0,
             num_orbits: 3,
             orbit_length: 4,
+            uplink_loss: 0.0,
+        }
+    }
+}
+
+/// Return the probability that a photon is not lost with a given loss, in dB.
+fn db_to_prob(loss: f64) -> f64 {
+    10.0_f64.powf(-loss / 10.0)
+}
+
+/// Entanglement swapping parameters of an OGS acting as a relay.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct OgsRelay {
+    /// Index of the OGS, in the order returned by `ogs_indices()`.
+    pub ogs: u32,
+    /// Entanglement swapping success probability.
+    pub swapping_success_prob: f64,
+    /// Entanglement swapping duration, in s.
+    pub swapping_duration: f64,
+}
+
+/// High-altitude platform placed above an OGS, with free-space links towards
+/// the OGS and the satellites that the OGS can reach.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct HapParams {
+    /// Index of the OGS, in the order returned by `ogs_indices()`.
+    pub ogs: u32,
+    /// Distance between the OGS and the HAP, in m.
+    pub ground_to_hap_distance: f64,
+    /// Distance between the HAP and the satellites, in m.
+    pub hap_to_orbit_distance: f64,
+    /// Characteristics of the HAP.
+    pub hap_weight: NodeWeight,
+}
+
+/// End-user nodes served by an OGS via fiber last-mile links.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct UserParams {
+    /// Index of the OGS, in the order returned by `ogs_indices()`.
+    pub ogs: u32,
+    /// Number of end-user nodes.
+    pub num_users: u32,
+    /// Length of the last mile, in m.
+    pub last_mile_distance: f64,
+    /// Characteristics of the last-mile fiber links.
+    pub last_mile: FiberParams,
+    /// Characteristics of the end-user nodes.
+    pub user_weight: NodeWeight,
+}
+
+/// Selector of nodes in a grid or chain topology.
+///
+/// A chain is handled as a single orbit, with a single row of two OGS nodes.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub enum NodeSelector {
+    /// Node with the given index.
+    Index(u32),
+    /// Satellites in the given orbit.
+    Orbit(u32),
+    /// Satellites at the given position in their orbit.
+    Position(u32),
+    /// OGS nodes in the given row, i.e., the row between orbits i-1 and i.
+    OgsRow(u32),
+}
+
+/// Characteristics of a node that override those of the default weight.
+/// The fields that are not specified are left unchanged.
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct NodeWeightOverride {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memory_qubits: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub decay_rate: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub write_efficiency: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub read_efficiency: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub write_latency: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub read_latency: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub storage_qubits: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub storage_decay_rate: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transfer_duration: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transfer_fidelity: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub swapping_success_prob: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub swapping_duration: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub correction_duration: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub processing_units: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detectors: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transmitters: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub capacity: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<SpdcSource>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub multiplexing: Option<MultiplexedSource>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memoryless: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detection_jitter: Option<f64>,
+}
+
+impl NodeWeightOverride {
+    fn apply(&self, weight: &mut NodeWeight) {
+        macro_rules! apply_field {
+            ($($field:ident),*) => {
+                $(
+                    if let Some(value) = self.$field {
+                        weight.$field = value;
+                    }
+                )*
+            };
+        }
+        apply_field!(
+            memory_qubits,
+            decay_rate,
+            write_efficiency,
+            read_efficiency,
+            write_latency,
+            read_latency,
+            storage_qubits,
+            storage_decay_rate,
+            transfer_duration,
+            transfer_fidelity,
+            swapping_success_prob,
+            swapping_duration,
+            correction_duration,
+            processing_units,
+            detectors,
+            transmitters,
+            capacity,
+            memoryless,
+            detection_jitter
+        );
+        if let Some(source) = &self.source {
+            weight.source = Some(source.clone());
+        }
+        if let Some(multiplexing) = self.multiplexing {
+            weight.multiplexing = Some(multiplexing);
         }
     }
 }
 
+/// Override of the characteristics of the selected nodes.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct NodeOverride {
+    pub selector: NodeSelector,
+    pub weight: NodeWeightOverride,
+}
+
+/// Layout of the nodes in the physical topology.
+#[derive(Debug, Default)]
+enum Layout {
+    /// See `PhysicalTopology::from_grid_static()`.
+    Grid { num_orbits: u32, orbit_length: u32 },
+    /// See `PhysicalTopology::from_chain_static()`.
+    Chain { num_repeaters: u32 },
+    /// Any other topology, where nodes can only be selected by index.
+    #[default]
+    Other,
+}
+
 fn err_if_not_empty(errors: &[String]) -> anyhow::Result<()> {
     if !errors.is_empty() {
         anyhow::bail!(
@@ -263,6 +872,9 @@ impl GridParams {
         if self.orbit_length == 0 {
             errors.push(String::from("vanishing orbit length"));
         }
+        if self.uplink_loss < 0.0 {
+            errors.push(format!("uplink loss ({}) < 0", self.uplink_loss))
+        }
         err_if_not_empty(&errors)
     }
 }
@@ -275,6 +887,10 @@ pub struct ChainParams {
     pub ground_to_orbit_distance: f64,
     /// Number of satellite repeaters.
     pub num_repeaters: u32,
+    /// Loss of ground-to-orbit uplinks, i.e., when the source is on an OGS,
+    /// in dB. Uplinks have no additional loss if not specified.
+    #[serde(default)]
+    pub uplink_loss: f64,
 }
 
 impl Default for ChainParams {
@@ -283,6 +899,7 @@ impl Default for ChainParams {
             orbit_to_orbit_distance: 3000000.0,
             ground_to_orbit_distance: 1000000.0,
             num_repeaters: 1,
+            uplink_loss: 0.0,
         }
     }
 }
@@ -305,10 +922,54 @@ impl ChainParams {
         if self.num_repeaters == 0 {
             errors.push(String::from("vanishing number of repeaters"));
         }
+        if self.uplink_loss < 0.0 {
+            errors.push(format!("uplink loss ({}) < 0", self.uplink_loss))
+        }
         err_if_not_empty(&errors)
     }
 }
 
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub enum FiberLayout {
+    /// The OGS nodes are connected in a ring, in index order.
+    Ring,
+    /// The OGS nodes are connected in a mesh that follows the grid
+    /// positions, i.e., each OGS is connected to its neighbors along and
+    /// across the orbits.
+    Mesh,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct GridFiberParams {
+    /// Layout of the fiber links between the OGS nodes.
+    pub layout: FiberLayout,
+    /// Length of the fiber between two neighbor OGS nodes, in m.
+    pub ogs_to_ogs_distance: f64,
+    /// Characteristics of the fiber links.
+    pub fiber: FiberParams,
+}
+
+impl Default for GridFiberParams {
+    fn default() -> Self {
+        Self {
+            layout: FiberLayout::Ring,
+            ogs_to_ogs_distance: 50000.0,
+            fiber: FiberParams::default(),
+        }
+    }
+}
+
+impl GridFiberParams {
+    fn valid(&self) -> anyhow::Result<()> {
+        anyhow::ensure!(
+            self.ogs_to_ogs_distance >= 0.0,
+            "invalid fiber parameters: OGS-to-OGS distance ({}) < 0",
+            self.ogs_to_ogs_distance
+        );
+        self.fiber.valid()
+    }
+}
+
 macro_rules! valid_node {
     ($node:expr, $graph:expr) => {
         anyhow::ensure!(
@@ -334,14 +995,28 @@ type Graph = petgraph::Graph<NodeWeight, EdgeWeight, petgraph::Undirected, u32>;
 /// A simple fidelity model for the EPR pairs generated is used, with fixed
 /// values depending only on whether the generation is one or two hops and
 /// if it is STA-STA or STA-OGS.
+///
+/// Sources may also be on board of OGS nodes (uplink configuration), in
+/// which case the photons sent towards satellites suffer an additional
+/// fixed loss.
+///
+/// Terrestrial fiber links can be mixed with free-space ones: they generate
+/// EPR pairs with their own heralded source, fidelity and length-dependent
+/// attenuation, and their classical latency depends on the refractive index.
 #[derive(Debug, Default)]
 pub struct PhysicalTopology {
     graph: Graph,
     fidelities: StaticFidelities,
+    /// Loss of ground-to-orbit uplinks, in dB.
+    uplink_loss: f64,
+    /// Layout of the nodes, used to resolve node selectors.
+    layout: Layout,
     paths: std::collections::HashMap<
         u32,
         petgraph::algo::bellman_ford::Paths<petgraph::graph::NodeIndex, EdgeWeight>,
     >,
+    latencies:
+        std::collections::HashMap<u32, std::collections::HashMap<petgraph::graph::NodeIndex, f64>>,
 }
 
 impl PhysicalTopology {
@@ -386,14 +1061,12 @@ impl PhysicalTopology {
         }
 
         // Add orbit-to-orbit edges.
-        let orbit_weight = EdgeWeight {
-            distance: grid_params.orbit_to_orbit_distance,
-        };
+        let orbit_weight = EdgeWeight::free_space(grid_params.orbit_to_orbit_distance);
         for i in 0..grid_params.num_orbits {
             for j in 0..grid_params.orbit_length {
                 let ndx = j + i * grid_params.orbit_length;
                 assert!(ndx < num_sat);
-                let mut others = std::collections::HashSet::new();
+                let mut others = std::collections::BTreeSet::new();
                 // Right
                 others.insert(i * grid_params.orbit_length + (j + 1) % grid_params.orbit_length);
                 // Left
@@ -419,14 +1092,12 @@ impl PhysicalTopology {
         }
 
         // Add ground-to-orbit edges.
-        let ground_weight = EdgeWeight {
-            distance: grid_params.ground_to_orbit_distance,
-        };
+        let ground_weight = EdgeWeight::free_space(grid_params.ground_to_orbit_distance);
         for i in 0..=grid_params.num_orbits {
             for j in 0..grid_params.orbit_length {
                 let ndx = num_sat + j + i * grid_params.orbit_length;
                 assert!(ndx < num_sat + num_ogs);
-                let mut sats = std::collections::HashSet::new();
+                let mut sats = std::collections::BTreeSet::new();
                 // Up
                 if i != 0 {
                     sats.insert((i - 1) * grid_params.orbit_length + j);
@@ -455,7 +1126,13 @@ impl PhysicalTopology {
         Ok(Self {
             graph,
             fidelities,
+            uplink_loss: grid_params.uplink_loss,
+            layout: Layout::Grid {
+                num_orbits: grid_params.num_orbits,
+                orbit_length: grid_params.orbit_length,
+            },
             paths: std::collections::HashMap::new(),
+            latencies: std::collections::HashMap::new(),
         })
     }
 
@@ -499,9 +1176,7 @@ impl PhysicalTopology {
                 graph.add_edge(
                     ndx.into(),
                     0.into(),
-                    EdgeWeight {
-                        distance: chain_params.ground_to_orbit_distance,
-                    },
+                    EdgeWeight::free_space(chain_params.ground_to_orbit_distance),
                 );
             }
 
@@ -511,18 +1186,14 @@ impl PhysicalTopology {
                 graph.add_edge(
                     ndx.into(),
                     1.into(),
-                    EdgeWeight {
-                        distance: chain_params.ground_to_orbit_distance,
-                    },
+                    EdgeWeight::free_space(chain_params.ground_to_orbit_distance),
                 );
             } else {
                 // Connect to right-hand satellite.
                 graph.add_edge(
                     ndx.into(),
                     (ndx + 1).into(),
-                    EdgeWeight {
-                        distance: chain_params.orbit_to_orbit_distance,
-                    },
+                    EdgeWeight::free_space(chain_params.orbit_to_orbit_distance),
                 );
             }
         }
@@ -530,71 +1201,582 @@ impl PhysicalTopology {
         Ok(Self {
             graph,
             fidelities,
+            uplink_loss: chain_params.uplink_loss,
+            layout: Layout::Chain {
+                num_repeaters: chain_params.num_repeaters,
+            },
             paths: std::collections::HashMap::new(),
+            latencies: std::collections::HashMap::new(),
         })
     }
 
-    /// Return the indices of the in-orbit satelites.
-    pub fn sat_indices(&self) -> Vec<u32> {
-        self.node_indices(NodeType::SAT)
-    }
-
-    /// Return the indices of the on-ground stations.
-    pub fn ogs_indices(&self) -> Vec<u32> {
-        self.node_indices(NodeType::OGS)
-    }
-
-    fn node_indices(&self, node_type: NodeType) -> Vec<u32> {
-        let mut ret = vec![];
-        for (ndx, w) in self.graph.node_weights().enumerate() {
-            if w.node_type == node_type {
-                ret.push(ndx as u32);
-            }
-        }
-        ret
-    }
-
-    /// Return the distance from node u to node v, in m.
-    /// The paths are computed in a lazy manner.
-    pub fn distance(&mut self, u: u32, v: u32) -> anyhow::Result<f64> {
-        valid_node!(u, self.graph);
-        valid_node!(v, self.graph);
-        if let Some(paths) = self.paths.get(&u) {
-            if let Some(_pred) = paths.predecessors[v as usize] {
-                Ok(paths.distances[v as usize].distance)
-            } else {
-                anyhow::bail!("no connection between {:?} and {:?}", u, v);
+    /// Build a physical topology consisting of a grid as in
+    /// `from_grid_static()`, where the OGS nodes are also connected to one
+    /// another via terrestrial fiber links, with the given layout.
+    pub fn from_grid_fiber(
+        grid_params: GridParams,
+        fiber_params: GridFiberParams,
+        sat_weight: NodeWeight,
+        ogs_weight: NodeWeight,
+        fidelities: StaticFidelities,
+    ) -> anyhow::Result<Self> {
+        fiber_params.valid()?;
+        let mut topology =
+            Self::from_grid_static(grid_params.clone(), sat_weight, ogs_weight, fidelities)?;
+
+        let ogs_indices = topology.ogs_indices();
+        let fiber_weight = EdgeWeight::fiber(fiber_params.ogs_to_ogs_distance, fiber_params.fiber);
+        let mut fiber_edges = vec![];
+        match fiber_params.layout {
+            FiberLayout::Ring => {
+                for i in 0..ogs_indices.len() {
+                    fiber_edges.push((ogs_indices[i], ogs_indices[(i + 1) % ogs_indices.len()]));
+                }
             }
-        } else {
-            match petgraph::algo::bellman_ford(&self.graph, u.into()) {
-                Ok(paths) => {
-                    self.paths.insert(u, paths);
-                    self.distance(u, v)
+            FiberLayout::Mesh => {
+                // The OGS nodes are arranged in num_orbits + 1 rows, each
+                // with orbit_length stations, and wrap around like the orbits.
+                let row_len = grid_params.orbit_length as usize;
+                for (i, ogs) in ogs_indices.iter().enumerate() {
+                    let (row, col) = (i / row_len, i % row_len);
+                    // Right
+                    fiber_edges.push((*ogs, ogs_indices[row * row_len + (col + 1) % row_len]));
+                    // Down
+                    if i + row_len < ogs_indices.len() {
+                        fiber_edges.push((*ogs, ogs_indices[i + row_len]));
+                    }
                 }
-                Err(_err) => anyhow::bail!(
-                    "cannot compute distance from {:?} to {:?}: negative cycle",
-                    u,
-                    v
-                ),
             }
         }
+        for (u, v) in fiber_edges {
+            if u != v && !topology.graph.contains_edge(u.into(), v.into()) {
+                topology.graph.add_edge(u.into(), v.into(), fiber_weight);
+            }
+        }
+
+        Ok(topology)
     }
 
-    /// Return the initial fidelity of the EPR pairs generated by the given
-    /// transmitter towards the two nodes specified. Return error if `tx` does not
-    /// have a transmitter or there is no edge between `tx` and `u` or `v`.
+    /// Build a terrestrial physical topology with the shape returned by the
+    /// given generator, where all the edges are fiber links with the same
+    /// characteristics.
+    ///
+    /// The end nodes, selected as specified, are OGS nodes with the
+    /// characteristics of `end_node_weight`, whereas all the other nodes are
+    /// terrestrial repeaters with the characteristics of `repeater_weight`.
+    ///
+    /// Return an error if the generated topology is not connected.
+    pub fn from_generator(
+        generator: &dyn crate::topology_generators::Generator,
+        end_nodes: &crate::topology_generators::EndNodes,
+        repeater_weight: NodeWeight,
+        end_node_weight: NodeWeight,
+        fiber_params: FiberParams,
+        rng: &mut rand::rngs::StdRng,
+    ) -> anyhow::Result<Self> {
+        repeater_weight.valid()?;
+        anyhow::ensure!(
+            repeater_weight.node_type == NodeType::REP,
+            "invalid repeater node type: {}",
+            repeater_weight.node_type
+        );
+        end_node_weight.valid()?;
+        anyhow::ensure!(
+            end_node_weight.node_type == NodeType::OGS,
+            "invalid end node type: {}",
+            end_node_weight.node_type
+        );
+        fiber_params.valid()?;
+
+        let shape = generator.generate(rng)?;
+        let end_nodes = end_nodes.select(&shape, rng)?;
+
+        let mut graph = petgraph::Graph::new_undirected();
+        for ndx in 0..shape.num_nodes {
+            graph.add_node(if end_nodes.contains(&ndx) {
+                end_node_weight.clone()
+            } else {
+                repeater_weight.clone()
+            });
+        }
+        for (u, v, distance) in shape.edges {
+            graph.add_edge(
+                u.into(),
+                v.into(),
+                EdgeWeight::fiber(distance, fiber_params),
+            );
+        }
+        anyhow::ensure!(
+            petgraph::algo::connected_components(&graph) == 1,
+            "the generated topology is not connected"
+        );
+
+        Ok(Self {
+            graph,
+            fidelities: StaticFidelities::default(),
+            uplink_loss: 0.0,
+            layout: Layout::Other,
+            paths: std::collections::HashMap::new(),
+            latencies: std::collections::HashMap::new(),
+        })
+    }
+
+    /// Build a physical topology with the given nodes and edges, as tuples
+    /// (u, v, distance in m, link type), e.g., imported from a file.
+    ///
+    /// All the characteristics are checked for consistency.
+    pub fn from_nodes_edges(
+        nodes: Vec<NodeWeight>,
+        edges: Vec<(u32, u32, f64, LinkType)>,
+        fidelities: StaticFidelities,
+        uplink_loss: f64,
+    ) -> anyhow::Result<Self> {
+        fidelities.valid()?;
+        anyhow::ensure!(uplink_loss >= 0.0, "uplink loss ({}) < 0", uplink_loss);
+
+        let mut graph = petgraph::Graph::new_undirected();
+        for (ndx, node_weight) in nodes.into_iter().enumerate() {
+            node_weight
+                .valid()
+                .map_err(|err| anyhow::anyhow!("node {}: {}", ndx, err))?;
+            graph.add_node(node_weight);
+        }
+        for (u, v, distance, link) in edges {
+            valid_node!(u, graph);
+            valid_node!(v, graph);
+            anyhow::ensure!(u != v, "self-loop on node {}", u);
+            anyhow::ensure!(
+                !graph.contains_edge(u.into(), v.into()),
+                "duplicate edge between {} and {}",
+                u,
+                v
+            );
+            anyhow::ensure!(
+                distance >= 0.0,
+                "invalid distance of edge between {} and {}: {} < 0",
+                u,
+                v,
+                distance
+            );
+            if let LinkType::Fiber(fiber_params) = &link {
+                fiber_params
+                    .valid()
+                    .map_err(|err| anyhow::anyhow!("edge between {} and {}: {}", u, v, err))?;
+            }
+            graph.add_edge(u.into(), v.into(), EdgeWeight { distance, link });
+        }
+
+        Ok(Self {
+            graph,
+            fidelities,
+            uplink_loss,
+            layout: Layout::Other,
+            paths: std::collections::HashMap::new(),
+            latencies: std::collections::HashMap::new(),
+        })
+    }
+
+    /// Enable entanglement swapping on the given OGS nodes, which can then
+    /// act as intermediate repeaters.
+    pub fn set_ogs_relays(&mut self, relays: &[OgsRelay]) -> anyhow::Result<()> {
+        let ogs_indices = self.ogs_indices();
+        for relay in relays {
+            anyhow::ensure!(
+                (relay.ogs as usize) < ogs_indices.len(),
+                "invalid OGS relay {}, there are only {} OGS nodes",
+                relay.ogs,
+                ogs_indices.len()
+            );
+            anyhow::ensure!(
+                relay.swapping_duration >= 0.0,
+                "invalid swapping duration of OGS relay {}: {}",
+                relay.ogs,
+                relay.swapping_duration
+            );
+            let weight = self
+                .graph
+                .node_weight_mut(ogs_indices[relay.ogs as usize].into())
+                .unwrap();
+            let mut new_weight = weight.clone();
+            new_weight.swapping_success_prob = relay.swapping_success_prob;
+            new_weight.swapping_duration = relay.swapping_duration;
+            new_weight.valid()?;
+            *weight = new_weight;
+        }
+        Ok(())
+    }
+
+    /// Add high-altitude platforms above the given OGS nodes.
+    pub fn add_haps(&mut self, haps: &[HapParams]) -> anyhow::Result<()> {
+        let ogs_indices = self.ogs_indices();
+        for hap in haps {
+            let ogs = *ogs_indices.get(hap.ogs as usize).ok_or_else(|| {
+                anyhow::anyhow!(
+                    "invalid HAP OGS {}, there are only {} OGS nodes",
+                    hap.ogs,
+                    ogs_indices.len()
+                )
+            })?;
+            hap.hap_weight.valid()?;
+            anyhow::ensure!(
+                hap.hap_weight.node_type == NodeType::HAP,
+                "invalid HAP node type: {}",
+                hap.hap_weight.node_type
+            );
+            anyhow::ensure!(
+                hap.ground_to_hap_distance >= 0.0 && hap.hap_to_orbit_distance >= 0.0,
+                "invalid HAP distances: {} and {}",
+                hap.ground_to_hap_distance,
+                hap.hap_to_orbit_distance
+            );
+            let sats = self
+                .graph
+                .neighbors(ogs.into())
+                .filter(|x| self.graph.node_weight(*x).unwrap().node_type == NodeType::SAT)
+                .collect::<Vec<_>>();
+            let ndx = self.graph.add_node(hap.hap_weight.clone());
+            self.graph.add_edge(
+                ogs.into(),
+                ndx,
+                EdgeWeight::free_space(hap.ground_to_hap_distance),
+            );
+            for sat in sats {
+                self.graph
+                    .add_edge(sat, ndx, EdgeWeight::free_space(hap.hap_to_orbit_distance));
+            }
+        }
+        self.paths.clear();
+        self.latencies.clear();
+        Ok(())
+    }
+
+    /// Add end-user nodes served by the given OGS nodes.
+    ///
+    /// The OGS nodes must be able to perform entanglement swapping for
+    /// the end-user nodes to be reachable from other nodes.
+    pub fn add_users(&mut self, users: &[UserParams]) -> anyhow::Result<()> {
+        let ogs_indices = self.ogs_indices();
+        for user in users {
+            let ogs = *ogs_indices.get(user.ogs as usize).ok_or_else(|| {
+                anyhow::anyhow!(
+                    "invalid user OGS {}, there are only {} OGS nodes",
+                    user.ogs,
+                    ogs_indices.len()
+                )
+            })?;
+            user.user_weight.valid()?;
+            anyhow::ensure!(
+                user.user_weight.node_type == NodeType::USER,
+                "invalid user node type: {}",
+                user.user_weight.node_type
+            );
+            anyhow::ensure!(
+                user.last_mile_distance >= 0.0,
+                "invalid last-mile distance: {} < 0",
+                user.last_mile_distance
+            );
+            user.last_mile.valid()?;
+            for _ in 0..user.num_users {
+                let ndx = self.graph.add_node(user.user_weight.clone());
+                self.graph.add_edge(
+                    ogs.into(),
+                    ndx,
+                    EdgeWeight::fiber(user.last_mile_distance, user.last_mile),
+                );
+            }
+        }
+        self.paths.clear();
+        self.latencies.clear();
+        Ok(())
+    }
+
+    /// Return the indices of the nodes matching the given selector.
+    pub fn select(&self, selector: &NodeSelector) -> anyhow::Result<Vec<u32>> {
+        let num_nodes = self.graph.node_count() as u32;
+        let ret = match (selector, &self.layout) {
+            (NodeSelector::Index(ndx), _) => {
+                anyhow::ensure!(
+                    *ndx < num_nodes,
+                    "invalid node index {}, there are only {} nodes",
+                    ndx,
+                    num_nodes
+                );
+                vec![*ndx]
+            }
+            (
+                NodeSelector::Orbit(orbit),
+                Layout::Grid {
+                    num_orbits,
+                    orbit_length,
+                },
+            ) => {
+                anyhow::ensure!(
+                    orbit < num_orbits,
+                    "invalid orbit {}, there are only {} orbits",
+                    orbit,
+                    num_orbits
+                );
+                (0..*orbit_length)
+                    .map(|j| j + orbit * orbit_length)
+                    .collect()
+            }
+            (
+                NodeSelector::Position(position),
+                Layout::Grid {
+                    num_orbits,
+                    orbit_length,
+                },
+            ) => {
+                anyhow::ensure!(
+                    position < orbit_length,
+                    "invalid position {}, the orbit length is {}",
+                    position,
+                    orbit_length
+                );
+                (0..*num_orbits)
+                    .map(|i| position + i * orbit_length)
+                    .collect()
+            }
+            (
+                NodeSelector::OgsRow(row),
+                Layout::Grid {
+                    num_orbits,
+                    orbit_length,
+                },
+            ) => {
+                anyhow::ensure!(
+                    row <= num_orbits,
+                    "invalid OGS row {}, there are only {} rows",
+                    row,
+                    num_orbits + 1
+                );
+                let num_sat = num_orbits * orbit_length;
+                (0..*orbit_length)
+                    .map(|j| num_sat + j + row * orbit_length)
+                    .collect()
+            }
+            (NodeSelector::Orbit(orbit), Layout::Chain { num_repeaters }) => {
+                anyhow::ensure!(*orbit == 0, "invalid orbit {} in a chain", orbit);
+                (2..(2 + num_repeaters)).collect()
+            }
+            (NodeSelector::Position(position), Layout::Chain { num_repeaters }) => {
+                anyhow::ensure!(
+                    position < num_repeaters,
+                    "invalid position {}, there are only {} repeaters",
+                    position,
+                    num_repeaters
+                );
+                vec![2 + position]
+            }
+            (NodeSelector::OgsRow(row), Layout::Chain { num_repeaters: _ }) => {
+                anyhow::ensure!(*row == 0, "invalid OGS row {} in a chain", row);
+                vec![0, 1]
+            }
+            (_, Layout::Other) => {
+                anyhow::bail!("nodes can only be selected by index: {:?}", selector)
+            }
+        };
+        Ok(ret)
+    }
+
+    /// Override the characteristics of the selected nodes, in order.
+    /// The resulting nodes are checked for consistency.
+    pub fn set_node_overrides(&mut self, overrides: &[NodeOverride]) -> anyhow::Result<()> {
+        for node_override in overrides {
+            for ndx in self.select(&node_override.selector)? {
+                let weight = self.graph.node_weight_mut(ndx.into()).unwrap();
+                let mut new_weight = weight.clone();
+                node_override.weight.apply(&mut new_weight);
+                new_weight
+                    .valid()
+                    .map_err(|err| anyhow::anyhow!("node {}: {}", ndx, err))?;
+                *weight = new_weight;
+            }
+        }
+        Ok(())
+    }
+
+    /// Return the indices of the nodes that can act as intermediate
+    /// repeaters, i.e., that can perform entanglement swapping.
+    pub fn relay_indices(&self) -> Vec<u32> {
+        let mut ret = vec![];
+        for (ndx, w) in self.graph.node_weights().enumerate() {
+            if w.can_swap() {
+                ret.push(ndx as u32);
+            }
+        }
+        ret
+    }
+
+    /// Return the indices of the in-orbit satelites.
+    pub fn sat_indices(&self) -> Vec<u32> {
+        self.node_indices(NodeType::SAT)
+    }
+
+    /// Return the indices of the on-ground stations.
+    pub fn ogs_indices(&self) -> Vec<u32> {
+        self.node_indices(NodeType::OGS)
+    }
+
+    /// Return the indices of the high-altitude platforms.
+    pub fn hap_indices(&self) -> Vec<u32> {
+        self.node_indices(NodeType::HAP)
+    }
+
+    /// Return the indices of the end-user nodes.
+    pub fn user_indices(&self) -> Vec<u32> {
+        self.node_indices(NodeType::USER)
+    }
+
+    /// Return the indices of the nodes where applications run, which must
+    /// be able to reach one another: the end-user nodes, if any, otherwise
+    /// the on-ground stations.
+    pub fn end_node_indices(&self) -> Vec<u32> {
+        let user_indices = self.user_indices();
+        if user_indices.is_empty() {
+            self.ogs_indices()
+        } else {
+            user_indices
+        }
+    }
+
+    fn node_indices(&self, node_type: NodeType) -> Vec<u32> {
+        let mut ret = vec![];
+        for (ndx, w) in self.graph.node_weights().enumerate() {
+            if w.node_type == node_type {
+                ret.push(ndx as u32);
+            }
+        }
+        ret
+    }
+
+    /// Return the distance from node u to node v, in m.
+    /// The paths are computed in a lazy manner.
+    pub fn distance(&mut self, u: u32, v: u32) -> anyhow::Result<f64> {
+        valid_node!(u, self.graph);
+        valid_node!(v, self.graph);
+        if let Some(paths) = self.paths.get(&u) {
+            if let Some(_pred) = paths.predecessors[v as usize] {
+                Ok(paths.distances[v as usize].distance)
+            } else {
+                anyhow::bail!("no connection between {:?} and {:?}", u, v);
+            }
+        } else {
+            match petgraph::algo::bellman_ford(&self.graph, u.into()) {
+                Ok(paths) => {
+                    self.paths.insert(u, paths);
+                    self.distance(u, v)
+                }
+                Err(_err) => anyhow::bail!(
+                    "cannot compute distance from {:?} to {:?}: negative cycle",
+                    u,
+                    v
+                ),
+            }
+        }
+    }
+
+    /// Return the classical communication latency from node u to node v, in s.
+    /// The latencies are computed in a lazy manner, along the fastest path,
+    /// which may differ from the shortest one because of fiber links.
+    pub fn latency(&mut self, u: u32, v: u32) -> anyhow::Result<f64> {
+        valid_node!(u, self.graph);
+        valid_node!(v, self.graph);
+        let latencies = self.latencies.entry(u).or_insert_with(|| {
+            petgraph::algo::dijkstra(&self.graph, u.into(), None, |e| e.weight().latency())
+        });
+        match latencies.get(&v.into()) {
+            Some(latency) => Ok(*latency),
+            None => anyhow::bail!("no connection between {:?} and {:?}", u, v),
+        }
+    }
+
+    /// Return the sequence of nodes along the path with minimum latency
+    /// from u to v, both included.
+    pub fn latency_path(&self, u: u32, v: u32) -> anyhow::Result<Vec<u32>> {
+        valid_node!(u, self.graph);
+        valid_node!(v, self.graph);
+        match petgraph::algo::astar(
+            &self.graph,
+            u.into(),
+            |n| n == v.into(),
+            |e| e.weight().latency(),
+            |_| 0.0,
+        ) {
+            Some((_, path)) => Ok(path.iter().map(|n| n.index() as u32).collect()),
+            None => anyhow::bail!("no connection between {:?} and {:?}", u, v),
+        }
+    }
+
+    /// Return the weight of the link between u and v, if any.
+    pub fn link(&self, u: u32, v: u32) -> Option<&EdgeWeight> {
+        self.graph
+            .find_edge(u.into(), v.into())
+            .and_then(|e| self.graph.edge_weight(e))
+    }
+
+    /// Return the fiber parameters of the link between u and v, if any.
+    fn fiber_params(
+        &self,
+        u: petgraph::graph::NodeIndex,
+        v: petgraph::graph::NodeIndex,
+    ) -> Option<FiberParams> {
+        match self.graph.find_edge(u, v) {
+            Some(e) => match self.graph.edge_weight(e).unwrap().link {
+                LinkType::Fiber(fiber_params) => Some(fiber_params),
+                LinkType::FreeSpace => None,
+            },
+            None => None,
+        }
+    }
+
+    /// Return the initial fidelity of the EPR pairs generated by the given
+    /// transmitter towards the two nodes specified. Return error if `tx` does not
+    /// have a transmitter or there is no edge between `tx` and `u` or `v`.
+    ///
+    /// If `tx` is an OGS, then it can only generate EPR pairs towards
+    /// satellites (uplink configuration).
+    ///
+    /// Fiber links generate EPR pairs with their own embedded source: in this
+    /// case `tx` must be one of the two end nodes and the fidelity is that of
+    /// the fiber, regardless of the transmitters on board of `tx`.
     ///
     /// Parameters:
     /// - `tx`: the node that generates EPR pairs
     /// - `u`: one of the nodes that receives one photon of the EPR pairs
     /// - `v`: the other one
     pub fn fidelity(&self, tx: u32, u: u32, v: u32) -> anyhow::Result<f64> {
+        let fidelity = self.link_fidelity(tx, u, v)?;
+
+        // The multi-pair emissions of the source of the transmitter, if any,
+        // depolarize the EPR pairs, unless generated by a fiber link.
+        let fiber =
+            u != v && (tx == u || tx == v) && self.fiber_params(u.into(), v.into()).is_some();
+        match &self.graph.node_weight(tx.into()).unwrap().source {
+            Some(source) if !fiber => Ok(0.25 + source.visibility() * (fidelity - 0.25)),
+            _ => Ok(fidelity),
+        }
+    }
+
+    /// Return the fidelity of the EPR pairs generated by `tx` towards `u`
+    /// and `v` that only depends on the links.
+    fn link_fidelity(&self, tx: u32, u: u32, v: u32) -> anyhow::Result<f64> {
         valid_node!(tx, self.graph);
         valid_node!(u, self.graph);
         valid_node!(v, self.graph);
         let tx = petgraph::graph::NodeIndex::from(tx);
         let u = petgraph::graph::NodeIndex::from(u);
         let v = petgraph::graph::NodeIndex::from(v);
+        if u != v && (tx == u || tx == v) {
+            if let Some(fiber_params) = self.fiber_params(u, v) {
+                return Ok(fiber_params.fidelity);
+            }
+        }
+        anyhow::ensure!(
+            self.fiber_params(tx, u).is_none() && self.fiber_params(tx, v).is_none(),
+            "free-space EPR pairs cannot be generated by {} over fiber links",
+            tx.index()
+        );
         anyhow::ensure!(
             self.graph.node_weight(tx).unwrap().transmitters > 0,
             "there are no transmitters on board of {}",
@@ -606,33 +1788,39 @@ impl PhysicalTopology {
             u.index(),
             v.index()
         );
-        anyhow::ensure!(
-            matches!(self.graph.node_weight(tx).unwrap().node_type, NodeType::SAT),
-            "node is an OGS and cannot be a transmitter: {}",
-            tx.index()
-        );
 
-        if tx == u {
+        let node_type =
+            |ndx: petgraph::graph::NodeIndex| &self.graph.node_weight(ndx).unwrap().node_type;
+        let tx_type = node_type(tx);
+        if tx == u || tx == v {
+            let other = if tx == u { v } else { u };
             anyhow::ensure!(
-                self.graph.find_edge(tx, v).is_some(),
+                self.graph.find_edge(tx, other).is_some(),
                 "there is no edge between nodes {} and {}",
                 tx.index(),
-                v.index()
+                other.index()
             );
-            match self.graph.node_weight(v).unwrap().node_type {
-                NodeType::SAT => Ok(self.fidelities.f_o),
-                NodeType::OGS => Ok(self.fidelities.f_g),
-            }
-        } else if tx == v {
+            let other_type = node_type(other);
             anyhow::ensure!(
-                self.graph.find_edge(tx, u).is_some(),
-                "there is no edge between nodes {} and {}",
+                tx_type.free_space() && other_type.free_space(),
+                "no free-space EPR pairs between {} {} and {} {}",
+                tx_type,
                 tx.index(),
-                u.index()
+                other_type,
+                other.index()
             );
-            match self.graph.node_weight(u).unwrap().node_type {
-                NodeType::SAT => Ok(self.fidelities.f_o),
-                NodeType::OGS => Ok(self.fidelities.f_g),
+            match (tx_type, other_type) {
+                (NodeType::HAP, _) | (_, NodeType::HAP) => Ok(self.fidelities.f_h),
+                (NodeType::SAT, NodeType::SAT) => Ok(self.fidelities.f_o),
+                (NodeType::SAT, NodeType::OGS) => Ok(self.fidelities.f_g),
+                (NodeType::OGS, NodeType::SAT) => Ok(self.fidelities.f_u),
+                _ => anyhow::bail!(
+                    "{} {} cannot generate EPR pairs towards {} {}",
+                    tx_type,
+                    tx.index(),
+                    other_type,
+                    other.index()
+                ),
             }
         } else {
             anyhow::ensure!(
@@ -647,44 +1835,135 @@ impl PhysicalTopology {
                 tx.index(),
                 v.index()
             );
-            match self.graph.node_weight(u).unwrap().node_type {
-                NodeType::SAT => match self.graph.node_weight(v).unwrap().node_type {
-                    NodeType::SAT => Ok(self.fidelities.f_oo),
-                    NodeType::OGS => Ok(self.fidelities.f_og),
-                },
-                NodeType::OGS => match self.graph.node_weight(v).unwrap().node_type {
-                    NodeType::SAT => Ok(self.fidelities.f_og),
-                    NodeType::OGS => Ok(self.fidelities.f_gg),
-                },
+            let u_type = node_type(u);
+            let v_type = node_type(v);
+            anyhow::ensure!(
+                tx_type.free_space() && u_type.free_space() && v_type.free_space(),
+                "no free-space EPR pairs from {} {} towards {} {} and {} {}",
+                tx_type,
+                tx.index(),
+                u_type,
+                u.index(),
+                v_type,
+                v.index()
+            );
+            match (tx_type, u_type, v_type) {
+                (NodeType::HAP, _, _) | (_, NodeType::HAP, _) | (_, _, NodeType::HAP) => {
+                    Ok(self.fidelities.f_hh)
+                }
+                (NodeType::SAT, NodeType::SAT, NodeType::SAT) => Ok(self.fidelities.f_oo),
+                (NodeType::SAT, NodeType::SAT, NodeType::OGS)
+                | (NodeType::SAT, NodeType::OGS, NodeType::SAT) => Ok(self.fidelities.f_og),
+                (NodeType::SAT, NodeType::OGS, NodeType::OGS) => Ok(self.fidelities.f_gg),
+                (NodeType::OGS, NodeType::SAT, NodeType::SAT) => Ok(self.fidelities.f_uu),
+                _ => anyhow::bail!(
+                    "{} {} cannot generate EPR pairs towards {} {} and {} {}",
+                    tx_type,
+                    tx.index(),
+                    u_type,
+                    u.index(),
+                    v_type,
+                    v.index()
+                ),
             }
         }
     }
 
+    /// Return the probability that both photons of an EPR pair generated by
+    /// the given transmitter reach the two nodes specified.
+    ///
+    /// Only the additional loss of ground-to-orbit uplinks and the
+    /// attenuation of fiber links are considered.
+    /// The consistency of the nodes is not checked, see `fidelity()`.
+    ///
+    /// Parameters:
+    /// - `tx`: the node that generates EPR pairs
+    /// - `u`: one of the nodes that receives one photon of the EPR pairs
+    /// - `v`: the other one
+    pub fn transmission_prob(&self, tx: u32, u: u32, v: u32) -> anyhow::Result<f64> {
+        valid_node!(tx, self.graph);
+        valid_node!(u, self.graph);
+        valid_node!(v, self.graph);
+        let tx_type = &self.graph.node_weight(tx.into()).unwrap().node_type;
+        if tx == u || tx == v {
+            if let Some(fiber_params) = self.fiber_params(u.into(), v.into()) {
+                let distance = self
+                    .graph
+                    .edge_weight(self.graph.find_edge(u.into(), v.into()).unwrap())
+                    .unwrap()
+                    .distance;
+                return Ok(fiber_params.transmissivity(distance));
+            }
+        }
+        let mut prob = 1.0;
+        for rx in [u, v] {
+            if rx == tx {
+                continue;
+            }
+            let rx_type = &self.graph.node_weight(rx.into()).unwrap().node_type;
+            if *tx_type == NodeType::OGS && *rx_type == NodeType::SAT {
+                prob *= db_to_prob(self.uplink_loss);
+            }
+        }
+        Ok(prob)
+    }
+
+    /// Return the OGSs at either end of the free-space links crossed by the
+    /// photons of the EPR pairs generated by the given transmitter.
+    /// The consistency of the nodes is not checked, see `fidelity()`.
+    ///
+    /// Parameters:
+    /// - `tx`: the node that generates EPR pairs
+    /// - `u`: one of the nodes that receives one photon of the EPR pairs
+    /// - `v`: the other one
+    pub fn ground_stations(&self, tx: u32, u: u32, v: u32) -> anyhow::Result<Vec<u32>> {
+        valid_node!(tx, self.graph);
+        valid_node!(u, self.graph);
+        valid_node!(v, self.graph);
+        let is_ogs =
+            |ndx: u32| self.graph.node_weight(ndx.into()).unwrap().node_type == NodeType::OGS;
+        let mut stations = vec![];
+        for rx in [u, v] {
+            if rx == tx || self.fiber_params(tx.into(), rx.into()).is_some() {
+                continue;
+            }
+            for ndx in [tx, rx] {
+                if is_ogs(ndx) && !stations.contains(&ndx) {
+                    stations.push(ndx);
+                }
+            }
+        }
+        Ok(stations)
+    }
+
     /// Create a topology of default nodes with given distances.
     #[cfg(test)]
     fn from_distances(edges: Vec<(u32, u32, f64)>, fidelities: StaticFidelities) -> Self {
         let mut graph = petgraph::Graph::new_undirected();
 
-        graph.extend_with_edges(edges.iter().map(|(u, v, distance)| {
-            (
-                *u,
-                *v,
-                EdgeWeight {
-                    distance: *distance,
-                },
-            )
-        }));
+        graph.extend_with_edges(
+            edges
+                .iter()
+                .map(|(u, v, distance)| (*u, *v, EdgeWeight::free_space(*distance))),
+        );
         Self {
             graph,
             fidelities,
+            uplink_loss: 0.0,
+            layout: Layout::Other,
             paths: std::collections::HashMap::new(),
+            latencies: std::collections::HashMap::new(),
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::physical_topology::{ChainParams, GridParams, NodeWeight};
+    use crate::physical_topology::{
+        ChainParams, FiberLayout, FiberParams, GridFiberParams, GridParams, HapParams,
+        MultiplexedSource, NodeOverride, NodeSelector, NodeWeight, NodeWeightOverride, OgsRelay,
+        SpdcSource, UserParams,
+    };
 
     use super::{NodeType, PhysicalTopology, StaticFidelities};
 
@@ -754,6 +2033,7 @@ mod tests {
                 ground_to_orbit_distance: 1000.0,
                 num_orbits: 0,
                 orbit_length: 1,
+                uplink_loss: 0.0,
             },
             NodeWeight::default_sat(),
             NodeWeight::default_ogs(),
@@ -766,6 +2046,7 @@ mod tests {
                 ground_to_orbit_distance: 1000.0,
                 num_orbits: 1,
                 orbit_length: 0,
+                uplink_loss: 0.0,
             },
             NodeWeight::default_sat(),
             NodeWeight::default_ogs(),
@@ -778,6 +2059,7 @@ mod tests {
                 ground_to_orbit_distance: 1000.0,
                 num_orbits: 1,
                 orbit_length: 1,
+                uplink_loss: 0.0,
             },
             NodeWeight::default_sat(),
             NodeWeight::default_ogs(),
@@ -790,6 +2072,7 @@ mod tests {
                 ground_to_orbit_distance: -1.0,
                 num_orbits: 1,
                 orbit_length: 1,
+                uplink_loss: 0.0,
             },
             NodeWeight::default_sat(),
             NodeWeight::default_ogs(),
@@ -804,6 +2087,7 @@ mod tests {
                 ground_to_orbit_distance: 1000.0,
                 num_orbits: 1,
                 orbit_length: 1,
+                uplink_loss: 0.0,
             },
             NodeWeight::default_sat(),
             NodeWeight::default_ogs(),
@@ -820,6 +2104,7 @@ mod tests {
                 ground_to_orbit_distance: 1000.0,
                 num_orbits: 1,
                 orbit_length: 2,
+                uplink_loss: 0.0,
             },
             NodeWeight::default_sat(),
             NodeWeight::default_ogs(),
@@ -836,6 +2121,7 @@ mod tests {
                 ground_to_orbit_distance: 1000.0,
                 num_orbits: 2,
                 orbit_length: 1,
+                uplink_loss: 0.0,
             },
             NodeWeight::default_sat(),
             NodeWeight::default_ogs(),
@@ -852,6 +2138,7 @@ mod tests {
                 ground_to_orbit_distance: 1000.0,
                 num_orbits: 2,
                 orbit_length: 2,
+                uplink_loss: 0.0,
             },
             NodeWeight::default_sat(),
             NodeWeight::default_ogs(),
@@ -868,6 +2155,7 @@ mod tests {
                 ground_to_orbit_distance: 1000.0,
                 num_orbits: 3,
                 orbit_length: 4,
+                uplink_loss: 0.0,
             },
             NodeWeight::default_sat(),
             NodeWeight::default_ogs(),
@@ -895,6 +2183,7 @@ mod tests {
                 orbit_to_orbit_distance: 3000.0,
                 ground_to_orbit_distance: 1000.0,
                 num_repeaters: 0,
+                uplink_loss: 0.0,
             },
             NodeWeight::default_sat(),
             NodeWeight::default_ogs(),
@@ -908,6 +2197,7 @@ mod tests {
                 orbit_to_orbit_distance: 3000.0,
                 ground_to_orbit_distance: 1000.0,
                 num_repeaters: 4,
+                uplink_loss: 0.0,
             },
             NodeWeight::default_sat(),
             NodeWeight::default_ogs(),
@@ -934,6 +2224,10 @@ mod tests {
             f_oo: 0.8,
             f_og: 0.9,
             f_gg: 1.0,
+            f_u: 0.55,
+            f_uu: 0.65,
+            f_h: 0.75,
+            f_hh: 0.85,
         };
 
         let mut topo = PhysicalTopology::from_distances(
@@ -970,4 +2264,482 @@ mod tests {
         assert!(topo.fidelity(0, 99, 1).is_err());
         assert!(topo.fidelity(99, 1, 2).is_err());
     }
+
+    #[test]
+    fn test_spdc_source() {
+        let source = SpdcSource {
+            mean_photon_number: 1.0,
+            repetition_rate: 1e6,
+            heralding_efficiency: 0.5,
+        };
+        assert_float_eq::assert_f64_near!(2.5e5, source.rate());
+        assert_float_eq::assert_f64_near!(1.0 / 3.0, source.visibility());
+
+        // A weak pump emits at most one pair per pulse, with full visibility.
+        let source = SpdcSource {
+            mean_photon_number: 1e-9,
+            repetition_rate: 1e6,
+            heralding_efficiency: 1.0,
+        };
+        assert!(source.rate() < 1e-2);
+        assert!(source.visibility() > 1.0 - 1e-8);
+
+        for (mean_photon_number, repetition_rate, heralding_efficiency, valid) in [
+            (0.1, 1e6, 1.0, true),
+            (0.1, 0.0, 0.0, true),
+            (0.0, 1e6, 1.0, false),
+            (-0.1, 1e6, 1.0, false),
+            (0.1, -1.0, 1.0, false),
+            (0.1, 1e6, -0.1, false),
+            (0.1, 1e6, 1.1, false),
+        ] {
+            let mut errors = vec![];
+            SpdcSource {
+                mean_photon_number,
+                repetition_rate,
+                heralding_efficiency,
+            }
+            .valid(&mut errors);
+            assert_eq!(valid, errors.is_empty(), "{:?}", errors);
+        }
+    }
+
+    #[test]
+    fn test_physical_topology_spdc_source() {
+        let fidelities = StaticFidelities {
+            f_gg: 0.85,
+            ..Default::default()
+        };
+        let mut topo = PhysicalTopology::from_distances(vec![(0, 1, 1.0), (0, 2, 1.0)], fidelities);
+        topo.graph.node_weight_mut(0.into()).unwrap().node_type = NodeType::SAT;
+        topo.graph.node_weight_mut(1.into()).unwrap().node_type = NodeType::OGS;
+        topo.graph.node_weight_mut(2.into()).unwrap().node_type = NodeType::OGS;
+
+        let mut prev_rate = 0.0;
+        let mut prev_fidelity = 1.0;
+        for mean_photon_number in [0.01, 0.1, 0.5] {
+            let source = SpdcSource {
+                mean_photon_number,
+                repetition_rate: 1e6,
+                heralding_efficiency: 0.5,
+            };
+            let weight = topo.graph.node_weight_mut(0.into()).unwrap();
+            weight.source = Some(source.clone());
+            assert_eq!(source.rate(), weight.tx_capacity());
+            assert!(weight.valid().is_ok());
+
+            let fidelity = topo.fidelity(0, 1, 2).unwrap();
+            assert_float_eq::assert_f64_near!(0.25 + source.visibility() * (0.85 - 0.25), fidelity);
+            assert!(source.rate() > prev_rate);
+            assert!(fidelity < prev_fidelity);
+            prev_rate = source.rate();
+            prev_fidelity = fidelity;
+        }
+
+        let weight = topo.graph.node_weight_mut(0.into()).unwrap();
+        weight.source = Some(SpdcSource {
+            mean_photon_number: 0.0,
+            repetition_rate: -1.0,
+            heralding_efficiency: 1.5,
+        });
+        assert!(weight.valid().is_err());
+
+        // The multiplexing determines the capacity, regardless of the source.
+        weight.source = None;
+        weight.multiplexing = Some(MultiplexedSource {
+            pulse_rate: 1e3,
+            num_modes: 50,
+            mode_success_prob: 0.1,
+        });
+        assert!(weight.valid().is_ok());
+        assert_float_eq::assert_f64_near!(5e3, weight.tx_capacity());
+        weight.multiplexing = Some(MultiplexedSource {
+            pulse_rate: 1e3,
+            num_modes: 0,
+            mode_success_prob: 0.1,
+        });
+        assert!(weight.valid().is_err());
+    }
+
+    #[test]
+    fn test_physical_topology_uplink() {
+        let fidelities = StaticFidelities {
+            f_u: 0.55,
+            f_uu: 0.65,
+            ..Default::default()
+        };
+
+        //
+        // 1 (SAT) -- 0 (OGS) -- 2 (SAT)
+        //              |
+        //            3 (OGS)
+        //
+        let mut topo = PhysicalTopology::from_distances(
+            vec![(0, 1, 1.0), (0, 2, 1.0), (0, 3, 1.0)],
+            fidelities.clone(),
+        );
+        topo.uplink_loss = 10.0;
+
+        topo.graph.node_weight_mut(0.into()).unwrap().node_type = NodeType::OGS;
+        topo.graph.node_weight_mut(1.into()).unwrap().node_type = NodeType::SAT;
+        topo.graph.node_weight_mut(2.into()).unwrap().node_type = NodeType::SAT;
+        topo.graph.node_weight_mut(3.into()).unwrap().node_type = NodeType::OGS;
+
+        assert_eq!(fidelities.f_u, topo.fidelity(0, 0, 1).unwrap());
+        assert_eq!(fidelities.f_u, topo.fidelity(0, 2, 0).unwrap());
+        assert_eq!(fidelities.f_uu, topo.fidelity(0, 1, 2).unwrap());
+
+        // No ground-to-ground EPR pairs from an OGS.
+        assert!(topo.fidelity(0, 0, 3).is_err());
+        assert!(topo.fidelity(0, 1, 3).is_err());
+
+        // No transmitters.
+        topo.graph.node_weight_mut(0.into()).unwrap().transmitters = 0;
+        assert!(topo.fidelity(0, 1, 2).is_err());
+
+        assert_float_eq::assert_f64_near!(0.1, topo.transmission_prob(0, 0, 1).unwrap());
+        assert_float_eq::assert_f64_near!(0.01, topo.transmission_prob(0, 1, 2).unwrap());
+        assert_float_eq::assert_f64_near!(1.0, topo.transmission_prob(1, 1, 0).unwrap());
+
+        assert_eq!(vec![0], topo.ground_stations(0, 1, 2).unwrap());
+        assert_eq!(vec![0], topo.ground_stations(1, 1, 0).unwrap());
+        assert_eq!(vec![0, 3], topo.ground_stations(0, 0, 3).unwrap());
+    }
+
+    #[test]
+    fn test_physical_topology_ogs_relays() {
+        let mut ogs_weight = NodeWeight::default_ogs();
+        ogs_weight.swapping_success_prob = 0.0;
+        let mut graph = PhysicalTopology::from_chain_static(
+            ChainParams::default(),
+            NodeWeight::default_sat(),
+            ogs_weight,
+            StaticFidelities::default(),
+        )
+        .unwrap();
+        assert_eq!(graph.sat_indices(), graph.relay_indices());
+
+        let relay = |ogs, swapping_success_prob| OgsRelay {
+            ogs,
+            swapping_success_prob,
+            swapping_duration: 0.01,
+        };
+
+        assert!(graph.set_ogs_relays(&[relay(2, 0.5)]).is_err());
+        assert!(graph.set_ogs_relays(&[relay(0, 1.5)]).is_err());
+
+        graph.set_ogs_relays(&[relay(1, 0.5)]).unwrap();
+        assert_eq!(vec![1, 2], graph.relay_indices());
+        let w = graph.graph().node_weight(1.into()).unwrap();
+        assert_float_eq::assert_f64_near!(0.5, w.swapping_success_prob);
+        assert_float_eq::assert_f64_near!(0.01, w.swapping_duration);
+    }
+
+    #[test]
+    fn test_physical_topology_from_grid_fiber() {
+        let grid_params = GridParams {
+            orbit_to_orbit_distance: 3000.0,
+            ground_to_orbit_distance: 1000000.0,
+            num_orbits: 2,
+            orbit_length: 3,
+            uplink_loss: 0.0,
+        };
+        let fiber_params = |layout| GridFiberParams {
+            layout,
+            ogs_to_ogs_distance: 50000.0,
+            fiber: FiberParams {
+                attenuation: 0.2,
+                refractive_index: 1.5,
+                attempt_rate: 1000.0,
+                fidelity: 0.9,
+            },
+        };
+        let build = |fiber_params| {
+            PhysicalTopology::from_grid_fiber(
+                grid_params.clone(),
+                fiber_params,
+                NodeWeight::default_sat(),
+                NodeWeight::default_ogs(),
+                StaticFidelities::default(),
+            )
+        };
+
+        // Invalid params.
+        let mut invalid = fiber_params(FiberLayout::Ring);
+        invalid.fiber.refractive_index = 0.5;
+        assert!(build(invalid).is_err());
+        let mut invalid = fiber_params(FiberLayout::Ring);
+        invalid.ogs_to_ogs_distance = -1.0;
+        assert!(build(invalid).is_err());
+
+        let num_free_space = PhysicalTopology::from_grid_static(
+            grid_params.clone(),
+            NodeWeight::default_sat(),
+            NodeWeight::default_ogs(),
+            StaticFidelities::default(),
+        )
+        .unwrap()
+        .graph()
+        .edge_count();
+
+        // Ring: one fiber link per OGS.
+        let graph = build(fiber_params(FiberLayout::Ring)).unwrap();
+        assert_eq!(num_free_space + 9, graph.graph().edge_count());
+
+        // Mesh: 3 rows of 3 OGS each, wrapping around along the rows.
+        let mut graph = build(fiber_params(FiberLayout::Mesh)).unwrap();
+        assert_eq!(num_free_space + 9 + 6, graph.graph().edge_count());
+
+        // Neighbor OGS nodes, along and across the orbits.
+        let ogs = graph.ogs_indices();
+        let (u, v, w) = (ogs[0], ogs[1], ogs[3]);
+        assert_eq!(0.9, graph.fidelity(u, u, v).unwrap());
+        assert_eq!(0.9, graph.fidelity(v, u, v).unwrap());
+        assert_eq!(0.9, graph.fidelity(w, u, w).unwrap());
+        assert!(graph.fidelity(u, v, w).is_err());
+
+        // 0.2 dB/km over 50 km.
+        assert_float_eq::assert_f64_near!(0.1, graph.transmission_prob(u, u, v).unwrap());
+        assert!(graph.ground_stations(u, u, v).unwrap().is_empty());
+
+        // Fiber is faster than going through the satellites, even though it
+        // is not the shortest path.
+        let fiber_latency = 1.5 * crate::utils::distance_to_latency(50000.0);
+        assert_float_eq::assert_f64_near!(fiber_latency, graph.latency(u, v).unwrap());
+        assert_float_eq::assert_f64_near!(fiber_latency, graph.latency(v, u).unwrap());
+        assert_float_eq::assert_f64_near!(2.0 * fiber_latency, graph.latency(u, ogs[4]).unwrap());
+        assert_float_eq::assert_f64_near!(0.0, graph.latency(u, u).unwrap());
+        assert!(graph.latency(u, 99).is_err());
+        assert_eq!(vec![u, v], graph.latency_path(u, v).unwrap());
+        assert_eq!(3, graph.latency_path(u, ogs[4]).unwrap().len());
+        assert!(graph.latency_path(u, 99).is_err());
+        assert!(graph.link(u, v).is_some());
+    }
+
+    #[test]
+    fn test_physical_topology_from_generator() {
+        let mut rng = rand::SeedableRng::seed_from_u64(42);
+        let build = |generator: &dyn crate::topology_generators::Generator,
+                     rng: &mut rand::rngs::StdRng| {
+            PhysicalTopology::from_generator(
+                generator,
+                &crate::topology_generators::EndNodes::LowestDegree(4),
+                NodeWeight::default_rep(),
+                NodeWeight {
+                    swapping_success_prob: 0.0,
+                    ..NodeWeight::default_ogs()
+                },
+                FiberParams::default(),
+                rng,
+            )
+        };
+
+        let mut graph = build(
+            &crate::topology_generators::StarParams {
+                num_leaves: 4,
+                link_length: 1000.0,
+            },
+            &mut rng,
+        )
+        .unwrap();
+        assert_eq!(vec![1, 2, 3, 4], graph.ogs_indices());
+        assert_eq!(vec![0], graph.relay_indices());
+        assert_eq!(0.95, graph.fidelity(0, 0, 1).unwrap());
+        assert!(graph.fidelity(0, 1, 2).is_err());
+        assert_float_eq::assert_f64_near!(2000.0, graph.distance(1, 2).unwrap());
+
+        // Disconnected topology.
+        assert!(build(
+            &crate::topology_generators::RandomGeometricParams {
+                num_nodes: 10,
+                side: 1000.0,
+                radius: 0.0,
+            },
+            &mut rng
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_physical_topology_node_overrides() {
+        let mut graph = PhysicalTopology::from_grid_static(
+            GridParams {
+                num_orbits: 3,
+                orbit_length: 4,
+                ..Default::default()
+            },
+            NodeWeight::default_sat(),
+            NodeWeight::default_ogs(),
+            StaticFidelities::default(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            vec![4, 5, 6, 7],
+            graph.select(&NodeSelector::Orbit(1)).unwrap()
+        );
+        assert_eq!(
+            vec![2, 6, 10],
+            graph.select(&NodeSelector::Position(2)).unwrap()
+        );
+        assert_eq!(
+            vec![24, 25, 26, 27],
+            graph.select(&NodeSelector::OgsRow(3)).unwrap()
+        );
+        assert_eq!(vec![27], graph.select(&NodeSelector::Index(27)).unwrap());
+        assert!(graph.select(&NodeSelector::Orbit(3)).is_err());
+        assert!(graph.select(&NodeSelector::Position(4)).is_err());
+        assert!(graph.select(&NodeSelector::OgsRow(4)).is_err());
+        assert!(graph.select(&NodeSelector::Index(28)).is_err());
+
+        let memory = |memory_qubits| NodeWeightOverride {
+            memory_qubits: Some(memory_qubits),
+            ..Default::default()
+        };
+        graph
+            .set_node_overrides(&[
+                NodeOverride {
+                    selector: NodeSelector::Orbit(1),
+                    weight: memory(5),
+                },
+                NodeOverride {
+                    selector: NodeSelector::Index(5),
+                    weight: NodeWeightOverride {
+                        capacity: Some(42.0),
+                        ..memory(7)
+                    },
+                },
+            ])
+            .unwrap();
+        let weight = |ndx: u32| graph.graph().node_weight(ndx.into()).unwrap().clone();
+        assert_eq!(1, weight(0).memory_qubits);
+        assert_eq!(5, weight(4).memory_qubits);
+        assert_eq!(7, weight(5).memory_qubits);
+        assert_eq!(42.0, weight(5).capacity);
+        assert_eq!(NodeWeight::default_sat().capacity, weight(4).capacity);
+
+        // Invalid resulting node: previous overrides are kept.
+        assert!(graph
+            .set_node_overrides(&[NodeOverride {
+                selector: NodeSelector::Index(4),
+                weight: NodeWeightOverride {
+                    swapping_success_prob: Some(1.5),
+                    ..Default::default()
+                },
+            }])
+            .is_err());
+        for weight in [
+            NodeWeightOverride {
+                read_efficiency: Some(1.5),
+                ..Default::default()
+            },
+            NodeWeightOverride {
+                write_latency: Some(-1.0),
+                ..Default::default()
+            },
+        ] {
+            assert!(graph
+                .set_node_overrides(&[NodeOverride {
+                    selector: NodeSelector::Index(4),
+                    weight,
+                }])
+                .is_err());
+        }
+        assert_eq!(
+            1.0,
+            graph
+                .graph()
+                .node_weight(4.into())
+                .unwrap()
+                .swapping_success_prob
+        );
+
+        // Chain: a single orbit and a single OGS row.
+        let graph = PhysicalTopology::from_chain_static(
+            ChainParams {
+                num_repeaters: 3,
+                ..Default::default()
+            },
+            NodeWeight::default_sat(),
+            NodeWeight::default_ogs(),
+            StaticFidelities::default(),
+        )
+        .unwrap();
+        assert_eq!(
+            vec![2, 3, 4],
+            graph.select(&NodeSelector::Orbit(0)).unwrap()
+        );
+        assert_eq!(vec![3], graph.select(&NodeSelector::Position(1)).unwrap());
+        assert_eq!(vec![0, 1], graph.select(&NodeSelector::OgsRow(0)).unwrap());
+        assert!(graph.select(&NodeSelector::Orbit(1)).is_err());
+    }
+
+    #[test]
+    fn test_physical_topology_haps_users() {
+        let fidelities = StaticFidelities {
+            f_h: 0.75,
+            f_hh: 0.85,
+            ..Default::default()
+        };
+        let mut graph = PhysicalTopology::from_chain_static(
+            ChainParams::default(),
+            NodeWeight::default_sat(),
+            NodeWeight::default_ogs(),
+            fidelities.clone(),
+        )
+        .unwrap();
+        assert_eq!(vec![0, 1], graph.end_node_indices());
+
+        let hap = |ogs, hap_weight| HapParams {
+            ogs,
+            ground_to_hap_distance: 20000.0,
+            hap_to_orbit_distance: 980000.0,
+            hap_weight,
+        };
+        assert!(graph
+            .add_haps(&[hap(2, NodeWeight::default_hap())])
+            .is_err());
+        assert!(graph
+            .add_haps(&[hap(0, NodeWeight::default_sat())])
+            .is_err());
+        graph
+            .add_haps(&[hap(0, NodeWeight::default_hap())])
+            .unwrap();
+        assert_eq!(vec![3], graph.hap_indices());
+        assert_float_eq::assert_f64_near!(20000.0, graph.distance(0, 3).unwrap());
+        assert_float_eq::assert_f64_near!(980000.0, graph.distance(2, 3).unwrap());
+        assert_eq!(fidelities.f_h, graph.fidelity(3, 3, 0).unwrap());
+        assert_eq!(fidelities.f_h, graph.fidelity(2, 2, 3).unwrap());
+        assert_eq!(fidelities.f_hh, graph.fidelity(2, 3, 1).unwrap());
+
+        let last_mile = FiberParams {
+            fidelity: 0.9,
+            ..Default::default()
+        };
+        let user = |ogs, user_weight| UserParams {
+            ogs,
+            num_users: 2,
+            last_mile_distance: 10000.0,
+            last_mile,
+            user_weight,
+        };
+        assert!(graph
+            .add_users(&[user(2, NodeWeight::default_user())])
+            .is_err());
+        assert!(graph
+            .add_users(&[user(1, NodeWeight::default_ogs())])
+            .is_err());
+        graph
+            .add_users(&[user(1, NodeWeight::default_user())])
+            .unwrap();
+        assert_eq!(vec![4, 5], graph.user_indices());
+        assert_eq!(vec![4, 5], graph.end_node_indices());
+        assert_eq!(0.9, graph.fidelity(1, 1, 4).unwrap());
+        assert_eq!(0.9, graph.fidelity(5, 5, 1).unwrap());
+        assert!(graph.fidelity(2, 1, 4).is_err());
+        assert_float_eq::assert_f64_near!(
+            10f64.powf(-0.2),
+            graph.transmission_prob(1, 1, 4).unwrap()
+        );
+    }
 }
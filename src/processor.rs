@@ -0,0 +1,312 @@
+// SPDX-FileCopyrightText: © 2025 Claudio Cicconetti <c.cicconetti@iit.cnr.it>
+// SPDX-License-Identifier: MIT
+
+use crate::event::{EprFiveTuple, EsRequestData, Event, EventType, NodeEventData};
+use crate::output::Sample;
+
+/// Local quantum operation performed by a node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum Operation {
+    /// Bell-state measurement for entanglement swapping.
+    Swap,
+    /// X/Z corrections of an end-to-end EPR pair.
+    Correction,
+}
+
+/// Order in which the local operations waiting for a processing unit
+/// are served.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum QueueDiscipline {
+    /// First in, first out.
+    #[default]
+    Fifo,
+    /// Last in, first out.
+    Lifo,
+    /// Shortest operation first, FIFO among those with the same duration.
+    ShortestFirst,
+    /// Corrections first, since they complete end-to-end EPR pairs, then
+    /// the other operations, FIFO within each class.
+    CorrectionsFirst,
+}
+
+/// Local operation waiting for a processing unit.
+#[derive(Debug)]
+struct Job {
+    /// Type of operation.
+    operation: Operation,
+    /// Duration of the operation, in s.
+    duration: f64,
+    /// Time when the operation was submitted, in ns.
+    submitted: u64,
+    /// Request for which the operation is performed.
+    data: EsRequestData,
+}
+
+/// Processing units of a node, which perform the local quantum operations
+/// one at a time each.
+#[derive(Debug)]
+pub struct Processor {
+    /// Node's identifier.
+    node_id: u32,
+    /// Number of processing units, 0 for unlimited.
+    units: u32,
+    /// Queue discipline.
+    discipline: QueueDiscipline,
+    /// Operations in progress, identified by EPR and local pair identifier.
+    running: Vec<(EprFiveTuple, u64)>,
+    /// Operations waiting for a processing unit.
+    queue: Vec<Job>,
+}
+
+impl Processor {
+    /// Create a processor with the given number of units, 0 for unlimited.
+    pub fn new(node_id: u32, units: u32) -> Self {
+        Self {
+            node_id,
+            units,
+            discipline: QueueDiscipline::default(),
+            running: vec![],
+            queue: vec![],
+        }
+    }
+
+    /// Set the queue discipline.
+    pub fn set_discipline(&mut self, discipline: QueueDiscipline) {
+        self.discipline = discipline;
+    }
+
+    /// Submit a local operation, which completes with an `EsLocalComplete`
+    /// event after its duration once a processing unit is available.
+    ///
+    /// Operations with zero duration do not require a processing unit.
+    /// The queue length, occupancy, and waiting time are reported only if
+    /// the number of processing units is limited.
+    pub fn submit(
+        &mut self,
+        now: u64,
+        operation: Operation,
+        duration: f64,
+        data: EsRequestData,
+    ) -> (Vec<Event>, Vec<Sample>) {
+        if duration == 0.0 || self.units == 0 {
+            return (
+                vec![Event::new(
+                    duration,
+                    EventType::NodeEvent(NodeEventData::EsLocalComplete(data)),
+                )],
+                vec![],
+            );
+        }
+
+        let job = Job {
+            operation,
+            duration,
+            submitted: now,
+            data,
+        };
+        if (self.running.len() as u32) < self.units {
+            let (event, mut samples) = self.start(now, job);
+            samples.push(self.queue_len());
+            (vec![event], samples)
+        } else {
+            self.queue.push(job);
+            (vec![], vec![self.queue_len()])
+        }
+    }
+
+    /// Notify that the local operation for the given request is complete.
+    ///
+    /// If it was holding a processing unit, start the next operation in
+    /// the queue, if any.
+    pub fn complete(&mut self, now: u64, data: &EsRequestData) -> (Vec<Event>, Vec<Sample>) {
+        let Some(ndx) = self.running.iter().position(|(epr, local_pair_id)| {
+            *epr == data.epr && *local_pair_id == data.local_pair_id
+        }) else {
+            return (vec![], vec![]);
+        };
+        self.running.swap_remove(ndx);
+
+        match self.next_job() {
+            Some(job) => {
+                let (event, mut samples) = self.start(now, job);
+                samples.push(self.queue_len());
+                (vec![event], samples)
+            }
+            None => (vec![], vec![self.occupancy()]),
+        }
+    }
+
+    /// Withdraw the local operation for the given request if still waiting
+    /// for a processing unit, e.g., because the ES has been aborted, while
+    /// an operation in progress is not interrupted.
+    pub fn cancel(&mut self, epr: &EprFiveTuple, local_pair_id: u64) -> Vec<Sample> {
+        let len = self.queue.len();
+        self.queue
+            .retain(|job| job.data.epr != *epr || job.data.local_pair_id != local_pair_id);
+        if self.queue.len() == len {
+            vec![]
+        } else {
+            vec![self.queue_len()]
+        }
+    }
+
+    /// Remove the next job to be served from the queue.
+    fn next_job(&mut self) -> Option<Job> {
+        if self.queue.is_empty() {
+            return None;
+        }
+        let ndx = match self.discipline {
+            QueueDiscipline::Fifo => 0,
+            QueueDiscipline::Lifo => self.queue.len() - 1,
+            QueueDiscipline::ShortestFirst => {
+                let mut ndx = 0;
+                for (i, job) in self.queue.iter().enumerate() {
+                    if job.duration < self.queue[ndx].duration {
+                        ndx = i;
+                    }
+                }
+                ndx
+            }
+            QueueDiscipline::CorrectionsFirst => self
+                .queue
+                .iter()
+                .position(|job| job.operation == Operation::Correction)
+                .unwrap_or(0),
+        };
+        Some(self.queue.remove(ndx))
+    }
+
+    /// Start a job on a processing unit.
+    fn start(&mut self, now: u64, job: Job) -> (Event, Vec<Sample>) {
+        self.running
+            .push((job.data.epr.clone(), job.data.local_pair_id));
+        let samples = vec![
+            Sample::Series(
+                "processing-wait".to_string(),
+                vec![self.node_id.to_string()],
+                crate::utils::to_seconds(now - job.submitted),
+            ),
+            self.occupancy(),
+        ];
+        (
+            Event::new(
+                job.duration,
+                EventType::NodeEvent(NodeEventData::EsLocalComplete(job.data)),
+            ),
+            samples,
+        )
+    }
+
+    fn occupancy(&self) -> Sample {
+        Sample::Series(
+            "processing-occupancy".to_string(),
+            vec![self.node_id.to_string()],
+            self.running.len() as f64 / self.units as f64,
+        )
+    }
+
+    fn queue_len(&self) -> Sample {
+        Sample::Series(
+            "processing-queue-len".to_string(),
+            vec![self.node_id.to_string()],
+            self.queue.len() as f64,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::event::{EprFiveTuple, EsRequestData, EventType, NodeEventData};
+
+    use super::{Operation, Processor, QueueDiscipline};
+
+    fn data(request_id: u64) -> EsRequestData {
+        EsRequestData {
+            epr: EprFiveTuple::new(0, 0, 2, 0, request_id),
+            prev_hop: 0,
+            next_hop: 1,
+            path: vec![0, 1, 2],
+            local_pair_id: request_id,
+            received: 0,
+            reserved: false,
+        }
+    }
+
+    fn request_id(events: &[crate::event::Event]) -> u64 {
+        match &events[0].event_type {
+            EventType::NodeEvent(NodeEventData::EsLocalComplete(data)) => data.epr.request_id,
+            _ => panic!("unexpected event"),
+        }
+    }
+
+    #[test]
+    fn test_processor_unlimited() {
+        let mut processor = Processor::new(1, 0);
+        for i in 0..10 {
+            let (events, samples) = processor.submit(0, Operation::Swap, 1.0, data(i));
+            assert_eq!(1, events.len());
+            assert_eq!(1_000_000_000, events[0].time());
+            assert!(samples.is_empty());
+        }
+        assert!(processor.complete(0, &data(0)).0.is_empty());
+    }
+
+    #[test]
+    fn test_processor_disciplines() {
+        // Operations submitted to a single unit, while busy with request 0.
+        let submitted = [
+            (1, Operation::Swap, 3.0),
+            (2, Operation::Correction, 2.0),
+            (3, Operation::Swap, 1.0),
+        ];
+        for (discipline, expected) in [
+            (QueueDiscipline::Fifo, [1, 2, 3]),
+            (QueueDiscipline::Lifo, [3, 2, 1]),
+            (QueueDiscipline::ShortestFirst, [3, 2, 1]),
+            (QueueDiscipline::CorrectionsFirst, [2, 1, 3]),
+        ] {
+            let mut processor = Processor::new(1, 1);
+            processor.set_discipline(discipline);
+            let (events, _) = processor.submit(0, Operation::Swap, 1.0, data(0));
+            assert_eq!(1, events.len());
+            for (request_id, operation, duration) in submitted {
+                let (events, _) = processor.submit(0, operation, duration, data(request_id));
+                assert!(events.is_empty());
+            }
+
+            // Zero-duration operations do not wait.
+            let (events, _) = processor.submit(0, Operation::Correction, 0.0, data(99));
+            assert_eq!(1, events.len());
+            assert!(processor.complete(0, &data(99)).0.is_empty());
+
+            let mut prev = 0;
+            for expected in expected {
+                let (events, samples) = processor.complete(10, &data(prev));
+                assert_eq!(expected, request_id(&events), "{:?}", discipline);
+                assert_eq!(3, samples.len());
+                prev = expected;
+            }
+            let (events, samples) = processor.complete(10, &data(prev));
+            assert!(events.is_empty());
+            assert_eq!(1, samples.len());
+        }
+    }
+
+    #[test]
+    fn test_processor_cancel() {
+        let mut processor = Processor::new(1, 1);
+        for i in 0..3 {
+            processor.submit(0, Operation::Swap, 1.0, data(i));
+        }
+
+        // The operation in progress is not interrupted.
+        assert!(processor.cancel(&data(0).epr, 0).is_empty());
+        assert!(processor.cancel(&data(1).epr, 99).is_empty());
+        assert_eq!(1, processor.cancel(&data(1).epr, 1).len());
+
+        let (events, _) = processor.complete(10, &data(0));
+        assert_eq!(2, request_id(&events));
+        let (events, _) = processor.complete(10, &data(2));
+        assert!(events.is_empty());
+    }
+}
@@ -0,0 +1,162 @@
+// SPDX-FileCopyrightText: © 2025 Claudio Cicconetti <c.cicconetti@iit.cnr.it>
+// SPDX-License-Identifier: MIT
+
+/// Order in which the requests pending at a node are served, depending on
+/// the traffic class of the applications.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum SchedulingPolicy {
+    /// First in, first out, regardless of the traffic class.
+    /// A request that is retried after a failure is queued again as a new
+    /// one, i.e., behind the requests that arrived in the meanwhile.
+    #[default]
+    Fifo,
+    /// Lower traffic classes first, FIFO within each class.
+    StrictPriority,
+    /// Weighted fair queuing among the traffic classes.
+    WeightedFair,
+    /// Earliest deadline first, FIFO among the requests without deadline,
+    /// which are served last.
+    EarliestDeadlineFirst,
+}
+
+/// Scheduling policy of a specific node.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct NodePolicy {
+    /// Node identifier.
+    pub node_id: u32,
+    /// Scheduling policy of the node.
+    pub policy: SchedulingPolicy,
+}
+
+/// Characteristics of a request pending at a node used to schedule it.
+#[derive(Debug, Clone)]
+pub struct QueuedRequest {
+    /// Traffic class.
+    pub class: u32,
+    /// Time by which the request must be served, in ns, if any.
+    pub deadline: Option<u64>,
+    /// Virtual finish time, with weighted fair queuing.
+    pub tag: f64,
+}
+
+/// Scheduler of the requests pending at a node.
+#[derive(Debug, Default)]
+pub struct RequestScheduler {
+    /// Scheduling policy.
+    policy: SchedulingPolicy,
+    /// Weights of the traffic classes, by class, with weighted fair queuing.
+    /// The classes without a weight have weight 1.
+    weights: Vec<f64>,
+    /// Virtual time, with weighted fair queuing.
+    virtual_time: f64,
+    /// Virtual finish time of the last request of every traffic class, with
+    /// weighted fair queuing.
+    last_finish: std::collections::HashMap<u32, f64>,
+}
+
+impl RequestScheduler {
+    /// Create a scheduler with the given policy and weights of the traffic
+    /// classes.
+    pub fn new(policy: SchedulingPolicy, weights: Vec<f64>) -> Self {
+        Self {
+            policy,
+            weights,
+            virtual_time: 0.0,
+            last_finish: std::collections::HashMap::new(),
+        }
+    }
+
+    /// Return the virtual finish time of a new request of the given class.
+    pub fn tag(&mut self, class: u32) -> f64 {
+        let weight = self.weights.get(class as usize).copied().unwrap_or(1.0);
+        let last_finish = self.last_finish.entry(class).or_insert(0.0);
+        let tag = last_finish.max(self.virtual_time) + 1.0 / weight;
+        *last_finish = tag;
+        tag
+    }
+
+    /// Notify that a request with the given virtual finish time is served.
+    pub fn served(&mut self, tag: f64) {
+        self.virtual_time = self.virtual_time.max(tag);
+    }
+
+    /// Return the indices of the requests in the order they must be served.
+    /// The requests must be passed in the order they were queued.
+    pub fn order(&self, requests: &[QueuedRequest]) -> Vec<usize> {
+        let mut indices = (0..requests.len()).collect::<Vec<usize>>();
+        // The sort is stable, hence requests with the same key are served
+        // in the order they were queued.
+        match self.policy {
+            SchedulingPolicy::Fifo => {}
+            SchedulingPolicy::StrictPriority => indices.sort_by_key(|ndx| requests[*ndx].class),
+            SchedulingPolicy::WeightedFair => {
+                indices.sort_by(|a, b| requests[*a].tag.total_cmp(&requests[*b].tag))
+            }
+            SchedulingPolicy::EarliestDeadlineFirst => {
+                indices.sort_by_key(|ndx| requests[*ndx].deadline.unwrap_or(u64::MAX))
+            }
+        }
+        indices
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{QueuedRequest, RequestScheduler, SchedulingPolicy};
+
+    #[test]
+    fn test_request_scheduler_order() {
+        let request = |class, deadline| QueuedRequest {
+            class,
+            deadline,
+            tag: 0.0,
+        };
+        let requests = vec![
+            request(1, Some(100)),
+            request(0, None),
+            request(1, None),
+            request(0, Some(50)),
+        ];
+
+        for (policy, expected) in [
+            (SchedulingPolicy::Fifo, vec![0, 1, 2, 3]),
+            (SchedulingPolicy::StrictPriority, vec![1, 3, 0, 2]),
+            (SchedulingPolicy::EarliestDeadlineFirst, vec![3, 0, 1, 2]),
+        ] {
+            let scheduler = RequestScheduler::new(policy, vec![]);
+            assert_eq!(expected, scheduler.order(&requests), "{policy:?}");
+        }
+    }
+
+    #[test]
+    fn test_request_scheduler_weighted_fair() {
+        // Class 0 has three times the weight of class 1.
+        let mut scheduler = RequestScheduler::new(SchedulingPolicy::WeightedFair, vec![3.0, 1.0]);
+        let mut requests = vec![];
+        for _ in 0..8 {
+            for class in [0, 1] {
+                requests.push(QueuedRequest {
+                    class,
+                    deadline: None,
+                    tag: scheduler.tag(class),
+                });
+            }
+        }
+
+        let order = scheduler.order(&requests);
+        let served = order[..8]
+            .iter()
+            .map(|ndx| requests[*ndx].class)
+            .collect::<Vec<u32>>();
+        assert_eq!(6, served.iter().filter(|class| **class == 0).count());
+        assert_eq!(2, served.iter().filter(|class| **class == 1).count());
+
+        // The virtual time advances with the requests served.
+        for ndx in &order {
+            scheduler.served(requests[*ndx].tag);
+        }
+        let tag_0 = scheduler.tag(0);
+        let tag_1 = scheduler.tag(1);
+        assert!(tag_0 < tag_1);
+    }
+}
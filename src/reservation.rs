@@ -0,0 +1,165 @@
+// SPDX-FileCopyrightText: © 2025 Claudio Cicconetti <c.cicconetti@iit.cnr.it>
+// SPDX-License-Identifier: MIT
+
+/// Resources held by an advance reservation during its time window.
+#[derive(Debug, Clone)]
+struct Booking {
+    /// Start of the time window, in ns.
+    start: u64,
+    /// End of the time window, in ns.
+    end: u64,
+    /// Rate at which EPR pairs must be generated, in EPR/s.
+    rate: f64,
+    /// Number of memory qubits held.
+    memory_qubits: u32,
+}
+
+/// Calendar of the advance reservations of EPR pairs on a logical link.
+///
+/// A reservation of a number of EPR pairs in a time window holds the
+/// generation capacity to establish them uniformly over the window, and
+/// one memory qubit per EPR pair, so that all of them can be stored until
+/// the end of the window.
+#[derive(Debug, Default)]
+pub struct ReservationCalendar {
+    /// Bookings whose time window has not ended yet.
+    bookings: Vec<Booking>,
+}
+
+impl ReservationCalendar {
+    /// Return true if the resources for a reservation would not exceed the
+    /// capacity or the memory qubits at any time in its window, which must
+    /// not be empty.
+    ///
+    /// Parameters:
+    /// - `start`: the start of the time window, in ns
+    /// - `end`: the end of the time window, in ns
+    /// - `number_of_pairs`: the number of EPR pairs reserved
+    /// - `capacity`: the capacity of the logical link, in EPR/s
+    /// - `memory_qubits`: the memory qubits of the logical link
+    pub fn fits(
+        &self,
+        start: u64,
+        end: u64,
+        number_of_pairs: u32,
+        capacity: f64,
+        memory_qubits: u32,
+    ) -> bool {
+        if start >= end {
+            return false;
+        }
+        let rate = Self::rate(start, end, number_of_pairs);
+        let overlapping = self
+            .bookings
+            .iter()
+            .filter(|booking| booking.start < end && start < booking.end)
+            .collect::<Vec<&Booking>>();
+
+        // The resources held only increase when a booking starts, hence it
+        // is enough to check them at the start of the new window and at
+        // the start of the bookings within it.
+        std::iter::once(start)
+            .chain(
+                overlapping
+                    .iter()
+                    .map(|booking| booking.start)
+                    .filter(|time| *time > start),
+            )
+            .all(|time| {
+                let active = overlapping
+                    .iter()
+                    .filter(|booking| booking.start <= time && time < booking.end);
+                let rate_held = active.clone().map(|booking| booking.rate).sum::<f64>();
+                let memory_held = active.map(|booking| booking.memory_qubits).sum::<u32>();
+                rate_held + rate <= capacity && memory_held + number_of_pairs <= memory_qubits
+            })
+    }
+
+    /// Book the resources for a reservation, if they fit.
+    /// Return true if the reservation is accepted.
+    ///
+    /// The parameters are the same as in `fits()`.
+    pub fn book(
+        &mut self,
+        start: u64,
+        end: u64,
+        number_of_pairs: u32,
+        capacity: f64,
+        memory_qubits: u32,
+    ) -> bool {
+        let accepted = self.fits(start, end, number_of_pairs, capacity, memory_qubits);
+        if accepted {
+            self.bookings.push(Booking {
+                start,
+                end,
+                rate: Self::rate(start, end, number_of_pairs),
+                memory_qubits: number_of_pairs,
+            });
+        }
+        accepted
+    }
+
+    /// Return the memory qubits held by the bookings whose time window is
+    /// in progress.
+    pub fn held(&self, now: u64) -> u32 {
+        self.bookings
+            .iter()
+            .filter(|booking| booking.start <= now && now < booking.end)
+            .map(|booking| booking.memory_qubits)
+            .sum()
+    }
+
+    fn rate(start: u64, end: u64, number_of_pairs: u32) -> f64 {
+        number_of_pairs as f64 / crate::utils::to_seconds(end - start)
+    }
+
+    /// Release the resources of the bookings whose time window has ended.
+    pub fn release(&mut self, now: u64) {
+        self.bookings.retain(|booking| booking.end > now);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ReservationCalendar;
+
+    #[test]
+    fn test_reservation_calendar() {
+        let s = crate::utils::to_nanoseconds;
+        let mut calendar = ReservationCalendar::default();
+
+        // 10 EPR/s out of 20 EPR/s and 10 memory qubits out of 15.
+        assert!(calendar.book(s(1.0), s(2.0), 10, 20.0, 15));
+
+        // Not enough memory qubits while overlapping, on either side.
+        assert!(!calendar.book(s(0.5), s(1.5), 6, 20.0, 15));
+        assert!(!calendar.book(s(1.5), s(2.5), 6, 20.0, 15));
+        assert!(calendar.book(s(2.0), s(3.0), 6, 20.0, 15));
+
+        // Not enough capacity while overlapping.
+        assert!(!calendar.book(s(0.0), s(0.5), 11, 20.0, 100));
+        assert!(calendar.book(s(0.0), s(0.5), 10, 20.0, 100));
+        assert!(!calendar.book(s(1.75), s(2.0), 3, 20.0, 100));
+
+        // A window including another one is checked when the latter starts.
+        assert!(!calendar.book(s(0.5), s(4.0), 6, 20.0, 15));
+        assert!(calendar.book(s(3.0), s(4.0), 15, 20.0, 15));
+
+        // Empty windows are never accepted.
+        assert!(!calendar.fits(s(5.0), s(5.0), 1, 20.0, 15));
+        assert!(!calendar.book(s(6.0), s(5.0), 1, 20.0, 15));
+
+        // The memory qubits are held only while the windows are in progress.
+        assert_eq!(0, calendar.held(s(0.75)));
+        assert_eq!(10, calendar.held(s(1.0)));
+        assert_eq!(6, calendar.held(s(2.5)));
+        assert_eq!(15, calendar.held(s(3.0)));
+        assert_eq!(0, calendar.held(s(4.0)));
+
+        // The resources are released at the end of the windows.
+        calendar.release(s(3.0));
+        assert!(!calendar.book(s(3.5), s(4.5), 1, 20.0, 15));
+        calendar.release(s(4.0));
+        assert!(calendar.book(s(3.5), s(4.5), 1, 20.0, 15));
+    }
+}
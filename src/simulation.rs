@@ -1,6 +1,7 @@
 // SPDX-FileCopyrightText: © 2025 Claudio Cicconetti <c.cicconetti@iit.cnr.it>
 // SPDX-License-Identifier: MIT
 
+use petgraph::visit::EdgeRef;
 use rand::SeedableRng;
 use rand_distr::Distribution;
 use std::io::Write;
@@ -52,7 +53,7 @@ impl Simulation {
         config: &crate::config::Config,
         physical_topology: crate::physical_topology::PhysicalTopology,
         save_to_dot: bool,
-    ) -> crate::network::Network {
+    ) -> anyhow::Result<crate::network::Network> {
         let mut rng = rand::rngs::StdRng::seed_from_u64(config.seed);
 
         let logical_topology = if let Ok(logical_topology) =
@@ -90,36 +91,251 @@ impl Simulation {
     pub fn new(config: crate::config::Config, save_to_dot: bool) -> anyhow::Result<Self> {
         anyhow::ensure!(config.user_config.duration > 0.0, "vanishing duration");
 
+        // A request for more EPR pairs than a full token bucket would be
+        // rejected forever.
+        if let crate::admission::AdmissionPolicy::TokenBucket { burst, .. } =
+            config.user_config.admission.policy
+        {
+            let number_of_pairs = match &config.user_config.applications {
+                crate::user_config::Applications::ConfPing(conf_ping) => conf_ping.number_of_pairs,
+                _ => 1,
+            };
+            anyhow::ensure!(
+                burst >= number_of_pairs as f64,
+                "admission token bucket of {} smaller than the {} EPR pairs per request",
+                burst,
+                number_of_pairs
+            );
+        }
+
+        if let crate::user_config::Applications::ConfPing(conf_ping) =
+            &config.user_config.applications
+        {
+            anyhow::ensure!(conf_ping.number_of_pairs > 0, "requests for no EPR pairs");
+            // A pinger issues a new request as soon as the previous one
+            // fails, hence it would be rejected forever at the same time.
+            anyhow::ensure!(
+                config.user_config.admission.policy == crate::admission::AdmissionPolicy::AcceptAll,
+                "admission control is not supported with ping applications"
+            );
+            anyhow::ensure!(
+                conf_ping.timeout.is_none_or(|timeout| timeout > 0.0),
+                "vanishing request timeout"
+            );
+            anyhow::ensure!(
+                conf_ping
+                    .min_fidelity
+                    .is_none_or(|min_fidelity| (0.0..=1.0).contains(&min_fidelity)),
+                "invalid minimum fidelity"
+            );
+        }
+
+        if let crate::user_config::Applications::ConfReservation(conf_reservation) =
+            &config.user_config.applications
+        {
+            anyhow::ensure!(
+                conf_reservation.number_of_pairs > 0,
+                "reservations of no EPR pairs"
+            );
+            anyhow::ensure!(
+                conf_reservation.lead_time >= 0.0,
+                "negative reservation lead time"
+            );
+            anyhow::ensure!(
+                conf_reservation.window > 0.0,
+                "vanishing reservation window"
+            );
+            anyhow::ensure!(
+                conf_reservation.interval > 0.0,
+                "vanishing interval between reservations"
+            );
+        }
+
         let physical_topology = config
             .user_config
             .physical_topology
-            .to_physical_topology()?;
+            .to_physical_topology(config.seed)?;
+
+        // The applications run between pairs of end nodes.
+        let num_end_nodes = physical_topology.end_node_indices().len();
+        anyhow::ensure!(
+            num_end_nodes > 1,
+            "at least two end nodes are needed, found {}",
+            num_end_nodes
+        );
 
         if save_to_dot {
             save_to_dot_file(physical_topology.graph(), "physical_topology.dot")?;
         }
 
-        let network = Self::create_network(&config, physical_topology, save_to_dot);
+        let mut network = Self::create_network(&config, physical_topology, save_to_dot)?;
+        network.set_storage()?;
 
         // Save to Graphviz files and terminate immediately.
         anyhow::ensure!(!save_to_dot, "saved to Dot files");
 
+        let faults = &config.user_config.faults;
+        network.set_faults(
+            &faults.random,
+            &faults.scripted,
+            faults.reroute,
+            config.seed,
+        )?;
+
+        let weather = &config.user_config.weather;
+        network.set_weather(
+            weather.clouds.clone(),
+            weather.daylight.clone(),
+            config.seed,
+        )?;
+
+        network.set_queue_discipline(config.user_config.processing.queue_discipline);
+        network.set_coincidence_window(config.user_config.logical_topology.coincidence_window);
+        network.set_memory_policy(&config.user_config.memory);
+
+        // A pinger issues a new request as soon as the previous one is
+        // rejected, hence there must be a route towards its peer, the EPR
+        // pairs to be established at once must fit the memory qubits, and
+        // the minimum fidelity must be attainable.
+        if let crate::user_config::Applications::ConfPing(conf_ping) =
+            &config.user_config.applications
+        {
+            let threshold = conf_ping.delivery.threshold(conf_ping.number_of_pairs);
+            let logical_topology = &network.logical_topology;
+            for (src, dst) in source_destination_pairs(
+                &conf_ping.source_dest_pairs,
+                network.physical_topology.end_node_indices(),
+                config.seed,
+            ) {
+                anyhow::ensure!(
+                    logical_topology.routable(src, dst),
+                    "no route for the EPR requests from {} to {}",
+                    src,
+                    dst
+                );
+                let memory_qubits =
+                    logical_topology.end_memory_qubits(&logical_topology.path(src, dst));
+                anyhow::ensure!(
+                    threshold <= memory_qubits,
+                    "{} EPR pairs must be established at once from {} to {}, with {} memory qubits",
+                    threshold,
+                    src,
+                    dst,
+                    memory_qubits
+                );
+                if let Some(min_fidelity) = conf_ping.min_fidelity {
+                    let fidelity = network.nodes[src as usize]
+                        .fresh_fidelity(&logical_topology.path(src, dst));
+                    anyhow::ensure!(
+                        min_fidelity <= fidelity,
+                        "minimum fidelity {} unattainable from {} to {}, at most {}",
+                        min_fidelity,
+                        src,
+                        dst,
+                        fidelity
+                    );
+                }
+            }
+        }
+        let scheduling = &config.user_config.scheduling;
+        network.set_scheduling(
+            scheduling.policy,
+            &scheduling.weights,
+            &scheduling.node_policies,
+        )?;
+        network.set_admission(config.user_config.admission.policy)?;
+
+        match (
+            &config
+                .user_config
+                .logical_topology
+                .physical_to_logical_policy,
+            &config.user_config.time_sharing,
+        ) {
+            (
+                crate::logical_topology::PhysicalToLogicalPolicy::RandomGreedyTimeShared,
+                Some(time_sharing),
+            ) => network.set_time_sharing(time_sharing)?,
+            (crate::logical_topology::PhysicalToLogicalPolicy::RandomGreedyTimeShared, None) => {
+                anyhow::bail!("time-shared logical links require a transmitter schedule")
+            }
+            _ => {}
+        }
+
+        if let Some(classical) = &config.user_config.classical {
+            network.set_classical(classical.clone(), config.seed)?;
+        }
+
         // Create data structure for scalar values.
         let mut single = crate::output::OutputScalar::default();
         single.init("bsm_prob", crate::output::ScalarMetricType::Avg);
         single.init("event_queue_len", crate::output::ScalarMetricType::TimeAvg);
         single.init("slave_fails", crate::output::ScalarMetricType::Count);
+        single.init("availability", crate::output::ScalarMetricType::TimeAvg);
+        single.init("recovery_time", crate::output::ScalarMetricType::Avg);
+        single.init("failures", crate::output::ScalarMetricType::Count);
+        single.init("es_remote_failed", crate::output::ScalarMetricType::Count);
+        single.init("ogs_reachable", crate::output::ScalarMetricType::TimeAvg);
+        single.init(
+            "weather_availability",
+            crate::output::ScalarMetricType::TimeAvg,
+        );
+        single.init("classical_delay", crate::output::ScalarMetricType::Avg);
+        single.init("classical_losses", crate::output::ScalarMetricType::Count);
+        single.init(
+            "memory_write_failures",
+            crate::output::ScalarMetricType::Count,
+        );
+        single.init(
+            "memory_read_failures",
+            crate::output::ScalarMetricType::Count,
+        );
+        single.init("memory_evictions", crate::output::ScalarMetricType::Count);
+        single.init("storage_transfers", crate::output::ScalarMetricType::Count);
+        single.init("storage_drops", crate::output::ScalarMetricType::Count);
+        single.init("tx_switches", crate::output::ScalarMetricType::Count);
+        single.init("epr_batch_size", crate::output::ScalarMetricType::Avg);
+        single.init("batch_drops", crate::output::ScalarMetricType::Count);
+        single.init("coincidences", crate::output::ScalarMetricType::Count);
+        single.init("qber", crate::output::ScalarMetricType::Avg);
+        single.init("coincidence_misses", crate::output::ScalarMetricType::Count);
+        single.init("timeout_rate", crate::output::ScalarMetricType::Avg);
+        single.init("abandon_rate", crate::output::ScalarMetricType::Avg);
+        single.init("fidelity_skips", crate::output::ScalarMetricType::Count);
+        single.init("fidelity_met", crate::output::ScalarMetricType::Avg);
+        single.init("acceptance_ratio", crate::output::ScalarMetricType::Avg);
+        single.init("reservation_blocking", crate::output::ScalarMetricType::Avg);
+        single.init(
+            "reservation_utilization",
+            crate::output::ScalarMetricType::Avg,
+        );
+
+        // All the elements are up and the sky is clear at the beginning.
+        single.time_avg("availability", 0, 1.0);
+        single.time_avg("ogs_reachable", 0, 1.0);
+        single.time_avg("weather_availability", 0, 1.0);
 
         // Create data structure for time series, also setting the headers
         let mut series = crate::output::OutputSeries::new(config.user_config.series_ignore.clone());
         series.set_headers("gen_fidelity", &["node_id"]);
         series.set_headers("fidelity", &["node_id", "port"]);
+        series.set_headers("class-fidelity", &["class"]);
+        series.set_headers("class-latency", &["class"]);
+        series.set_headers("acceptance", &["node_id", "port"]);
+        series.set_headers("reservation-utilization", &["node_id", "port"]);
         series.set_headers("occupancy", &["node_id", "peer_node_id"]);
         series.set_headers("epr-request-latency", &["node_id", "path_length"]);
+        series.set_headers("epr-first-pair-latency", &["node_id", "number_of_pairs"]);
+        series.set_headers("epr-last-pair-latency", &["node_id", "number_of_pairs"]);
         series.set_headers("client-latency", &["node_id", "port"]);
         series.set_headers("client-queue-len", &["node_id", "port"]);
         series.set_headers("ping-latency", &["node_id", "peer_node_id"]);
         series.set_headers("server-queue-len", &["node_id", "port"]);
+        series.set_headers("ogs-reachable", &["node_id"]);
+        series.set_headers("processing-wait", &["node_id"]);
+        series.set_headers("processing-occupancy", &["node_id"]);
+        series.set_headers("processing-queue-len", &["node_id"]);
+        series.set_headers("pool-occupancy", &["node_id"]);
 
         Ok(Self {
             network,
@@ -169,7 +385,7 @@ impl Simulation {
             .push(Event::new(conf.duration, EventType::ExperimentEnd));
         self.events.push(Event::new(0.0, EventType::Progress(0)));
         let initial_network_events = self.network.initial();
-        let logical_topology_found = if initial_network_events.is_empty() {
+        let logical_topology_found = if self.network.logical_topology.graph().edge_count() == 0 {
             0.0_f64
         } else {
             1.0_f64
@@ -213,10 +429,14 @@ impl Simulation {
                         log::debug!("W {}", now);
                         self.single.enable(now);
                         self.series.enable();
+                        self.network.start_measurements(now);
                         (vec![], vec![])
                     }
                     EventType::ExperimentEnd => {
                         log::debug!("E {}", now);
+                        let samples = self.network.finish(now);
+                        self.update(vec![], samples);
+                        self.single.finish(now);
                         break 'main_loop;
                     }
                     EventType::Progress(percentage) => {
@@ -276,18 +496,18 @@ impl Simulation {
 /// Find source/destination pairs, depending on the network and configuration.
 fn source_destination_pairs(
     conf: &crate::user_config::SourceDestPairs,
-    ogs_indices: Vec<u32>,
+    end_node_indices: Vec<u32>,
     seed: u64,
 ) -> Vec<(u32, u32)> {
     let mut source_dest_pairs = vec![];
     match conf {
         crate::user_config::SourceDestPairs::Random(num_applications) => {
             let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
-            let uniform = rand_distr::Uniform::new(0, ogs_indices.len());
+            let uniform = rand_distr::Uniform::new(0, end_node_indices.len());
             for _ in 0..*num_applications {
-                let this_node_id = uniform.sample(&mut rng) as u32;
+                let this_node_id = end_node_indices[uniform.sample(&mut rng)];
                 let peer_node_id = loop {
-                    let candidate = uniform.sample(&mut rng) as u32;
+                    let candidate = end_node_indices[uniform.sample(&mut rng)];
                     if candidate != this_node_id {
                         break candidate;
                     }
@@ -296,8 +516,8 @@ fn source_destination_pairs(
             }
         }
         crate::user_config::SourceDestPairs::AllToAll => {
-            for this_node_id in &ogs_indices {
-                for peer_node_id in &ogs_indices {
+            for this_node_id in &end_node_indices {
+                for peer_node_id in &end_node_indices {
                     if this_node_id == peer_node_id {
                         continue;
                     }
@@ -314,14 +534,14 @@ fn create_applications(
     conf: &crate::user_config::Applications,
     network: &mut crate::network::Network,
 ) {
-    let ogs_indices = network.physical_topology.ogs_indices();
-    assert!(!ogs_indices.is_empty(), "no OGS nodes");
-    assert!(ogs_indices.len() > 1, "there's a single OGS node");
+    let end_node_indices = network.physical_topology.end_node_indices();
     match &conf {
         crate::user_config::Applications::ConfPing(conf_ping) => {
             let max_requests = conf_ping.max_requests;
-            for (this_node_id, peer_node_id) in
-                source_destination_pairs(&conf_ping.source_dest_pairs, ogs_indices, seed)
+            for (app_ndx, (this_node_id, peer_node_id)) in
+                source_destination_pairs(&conf_ping.source_dest_pairs, end_node_indices, seed)
+                    .into_iter()
+                    .enumerate()
             {
                 let this_port = network.nodes[this_node_id as usize].next_port();
                 let peer_port = network.nodes[peer_node_id as usize].next_port();
@@ -335,13 +555,20 @@ fn create_applications(
                     max_requests
                 );
 
-                let pinger = Box::new(crate::apps::pinger::Pinger::new(
+                let mut pinger = Box::new(crate::apps::pinger::Pinger::new(
                     this_node_id,
                     this_port,
                     peer_node_id,
                     peer_port,
                     max_requests,
+                    conf_ping.request_type,
                 ));
+                pinger.set_batch(conf_ping.number_of_pairs, conf_ping.delivery);
+                pinger.set_limits(conf_ping.timeout, conf_ping.max_retries);
+                pinger.set_min_fidelity(conf_ping.min_fidelity);
+                if !conf_ping.classes.is_empty() {
+                    pinger.set_class(conf_ping.classes[app_ndx % conf_ping.classes.len()]);
+                }
                 network.nodes[this_node_id as usize].add_applicaton(pinger, this_port);
 
                 let ponger = Box::new(crate::apps::ponger::Ponger::new(peer_node_id, peer_port));
@@ -349,9 +576,11 @@ fn create_applications(
             }
         }
         crate::user_config::Applications::ConfClientServer(conf_client_server) => {
-            for (this_node_id, peer_node_id) in
-                source_destination_pairs(&conf_client_server.source_dest_pairs, ogs_indices, seed)
-            {
+            for (this_node_id, peer_node_id) in source_destination_pairs(
+                &conf_client_server.source_dest_pairs,
+                end_node_indices,
+                seed,
+            ) {
                 let this_port = network.nodes[this_node_id as usize].next_port();
                 let peer_port = network.nodes[peer_node_id as usize].next_port();
 
@@ -384,13 +613,1136 @@ fn create_applications(
                 network.nodes[peer_node_id as usize].add_applicaton(server, peer_port);
             }
         }
+        crate::user_config::Applications::ConfReservation(conf_reservation) => {
+            for (this_node_id, peer_node_id) in source_destination_pairs(
+                &conf_reservation.source_dest_pairs,
+                end_node_indices,
+                seed,
+            ) {
+                let this_port = network.nodes[this_node_id as usize].next_port();
+                let peer_port = network.nodes[peer_node_id as usize].next_port();
+
+                log::debug!(
+                    "creating reserver/ponger between {}:{} and {}:{} ({} pairs every {} s)",
+                    this_node_id,
+                    this_port,
+                    peer_node_id,
+                    peer_port,
+                    conf_reservation.number_of_pairs,
+                    conf_reservation.interval
+                );
+
+                let mut reserver = Box::new(crate::apps::reserver::Reserver::new(
+                    this_node_id,
+                    this_port,
+                    peer_node_id,
+                    peer_port,
+                    conf_reservation.max_reservations,
+                    conf_reservation.number_of_pairs,
+                ));
+                reserver.set_timing(
+                    conf_reservation.lead_time,
+                    conf_reservation.window,
+                    conf_reservation.interval,
+                );
+                network.nodes[this_node_id as usize].add_applicaton(reserver, this_port);
+
+                let ponger = Box::new(crate::apps::ponger::Ponger::new(peer_node_id, peer_port));
+                network.nodes[peer_node_id as usize].add_applicaton(ponger, peer_port);
+            }
+        }
+        crate::user_config::Applications::ConfMeasure(conf_measure) => {
+            let memoryless_links = network
+                .logical_topology
+                .graph()
+                .edge_references()
+                .filter(|e| e.weight().memoryless)
+                .map(|e| (e.source().index() as u32, e.target().index() as u32))
+                .collect::<Vec<_>>();
+            for (master_node_id, slave_node_id) in memoryless_links {
+                for (this_node_id, peer_node_id) in [
+                    (master_node_id, slave_node_id),
+                    (slave_node_id, master_node_id),
+                ] {
+                    let this_port = network.nodes[this_node_id as usize].next_port();
+
+                    log::debug!(
+                        "creating measurer at {}:{} towards {} (max pairs {})",
+                        this_node_id,
+                        this_port,
+                        peer_node_id,
+                        conf_measure.max_pairs
+                    );
+
+                    let measurer = Box::new(crate::apps::measurer::Measurer::new(
+                        this_node_id,
+                        this_port,
+                        peer_node_id,
+                        conf_measure.max_pairs,
+                    ));
+                    network.nodes[this_node_id as usize].add_measurer(
+                        measurer,
+                        this_port,
+                        peer_node_id,
+                    );
+                }
+            }
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use rand::SeedableRng;
+
+    use crate::tests::{
+        chain_direct_conf, chain_ping_config, chain_ping_config_with, chain_relay_conf,
+        num_samples, run_simulation,
+    };
+
+    use super::Simulation;
+
     #[test]
     fn test_simulation_run() -> anyhow::Result<()> {
         Ok(())
     }
+
+    #[test]
+    fn test_simulation_faults() -> anyhow::Result<()> {
+        let (output, scalar) = run_simulation(chain_ping_config())?;
+        let fault_free = num_samples(&output, "ping-latency");
+        assert_eq!(0.0, scalar["failures"]);
+        assert_eq!(1.0, scalar["availability"]);
+
+        let mut user_config = chain_ping_config();
+        user_config.faults = crate::user_config::Faults {
+            random: vec![crate::faults::RandomFaults {
+                scope: crate::faults::FaultScope::LogicalLinks,
+                time_to_failure: crate::faults::DurationDistribution::Exponential(2.0),
+                time_to_repair: crate::faults::DurationDistribution::Exponential(0.5),
+            }],
+            scripted: vec![crate::faults::ScriptedFault {
+                target: crate::faults::FaultTarget::Node(2),
+                start: 3.0,
+                duration: 1.0,
+            }],
+            reroute: true,
+        };
+
+        let (output, scalar) = run_simulation(user_config)?;
+        assert_eq!(1.0, scalar["logical_topology_found"]);
+        assert!(scalar["failures"] > 1.0);
+        assert!(scalar["availability"] > 0.0 && scalar["availability"] < 1.0);
+        assert!(scalar["recovery_time"] > 0.0);
+        assert!(num_samples(&output, "ping-latency") < fault_free);
+
+        // While the only satellite is down, no EPR pairs are generated and
+        // the OGSs can only use those already in their memory.
+        let ogs_memory_qubits = crate::user_config::ConfChainStatic::default()
+            .ogs_weight
+            .memory_qubits;
+        let during_fault = output.series.series["ping-latency"]
+            .values
+            .iter()
+            .filter(|(_labels, time, _latency)| (3.1..4.0).contains(time))
+            .count();
+        assert!(
+            during_fault <= 2 * ogs_memory_qubits as usize,
+            "{during_fault}"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_simulation_weather() -> anyhow::Result<()> {
+        let (output, scalar) = run_simulation(chain_ping_config())?;
+        let clear_sky = num_samples(&output, "ping-latency");
+        assert_eq!(1.0, scalar["ogs_reachable"]);
+        assert_eq!(1.0, scalar["weather_availability"]);
+
+        let mut user_config = chain_ping_config();
+        user_config.weather = crate::user_config::Weather {
+            clouds: Some(crate::weather::WeatherParams {
+                mean_durations: [1.0, 0.5, 0.5],
+                ..Default::default()
+            }),
+            daylight: Some(crate::weather::DaylightParams {
+                period: 4.0,
+                ..Default::default()
+            }),
+        };
+
+        let (output, scalar) = run_simulation(user_config)?;
+        assert_eq!(1.0, scalar["logical_topology_found"]);
+        assert!(scalar["ogs_reachable"] > 0.0 && scalar["ogs_reachable"] < 1.0);
+        assert!(scalar["weather_availability"] < 1.0);
+
+        // Weather outages are not faults, but the OGSs receive fewer EPR
+        // pairs while they are not reachable.
+        assert_eq!(1.0, scalar["availability"]);
+        assert!(num_samples(&output, "ping-latency") < clear_sky);
+
+        let reachable = &output.series.series["ogs-reachable"].values;
+        assert_eq!(2, reachable.len());
+        for (_labels, _time, value) in reachable {
+            assert!((0.0..=1.0).contains(value));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_simulation_classical() -> anyhow::Result<()> {
+        // Without the classical network, there is only propagation latency.
+        let (_output, scalar) = run_simulation(chain_ping_config())?;
+        assert!(scalar["classical_delay"].is_nan());
+
+        // The delay increases as the bandwidth decreases.
+        let mut delays = vec![];
+        for free_space_bandwidth in [1e7, 1e6] {
+            let mut user_config = chain_ping_config();
+            user_config.classical = Some(crate::classical::ClassicalParams {
+                free_space_bandwidth,
+                free_space_loss_prob: 0.1,
+                ..Default::default()
+            });
+            let (_output, scalar) = run_simulation(user_config)?;
+            assert!(scalar["classical_losses"] > 0.0);
+            delays.push(scalar["classical_delay"]);
+        }
+        let propagation = 2.0 * crate::utils::distance_to_latency(1000000.0);
+        assert!(delays[0] > propagation, "{:?}", delays);
+        assert!(delays[1] > delays[0], "{:?}", delays);
+        Ok(())
+    }
+
+    #[test]
+    fn test_simulation_memory_efficiency() -> anyhow::Result<()> {
+        let min_latency = |output: &crate::output::Output| {
+            output.series.series["ping-latency"]
+                .values
+                .iter()
+                .map(|(_labels, _time, latency)| *latency)
+                .fold(f64::MAX, f64::min)
+        };
+
+        let (output, scalar) = run_simulation(chain_ping_config())?;
+        assert_eq!(0.0, scalar["memory_write_failures"]);
+        assert_eq!(0.0, scalar["memory_read_failures"]);
+        let ideal_pings = num_samples(&output, "ping-latency");
+        let ideal_latency = min_latency(&output);
+
+        // The qubit is read from the memory at the target node before
+        // the EPR pair is delivered.
+        let read_latency = 0.001;
+        let mut conf = crate::user_config::ConfChainStatic::default();
+        for weight in [&mut conf.sat_weight, &mut conf.ogs_weight] {
+            weight.write_latency = 0.001;
+            weight.read_latency = read_latency;
+        }
+        let (output, scalar) = run_simulation(chain_ping_config_with(conf.clone()))?;
+        assert_eq!(0.0, scalar["memory_write_failures"]);
+        assert_eq!(0.0, scalar["memory_read_failures"]);
+        assert!(min_latency(&output) >= ideal_latency + read_latency - 1e-9);
+
+        // Every failure loses an EPR pair, but the pings go on.
+        for weight in [&mut conf.sat_weight, &mut conf.ogs_weight] {
+            weight.write_efficiency = 0.8;
+            weight.read_efficiency = 0.8;
+        }
+        let (output, scalar) = run_simulation(chain_ping_config_with(conf))?;
+        assert!(scalar["memory_write_failures"] > 0.0);
+        assert!(scalar["memory_read_failures"] > 0.0);
+        let pings = num_samples(&output, "ping-latency");
+        assert!(pings > 0 && pings < ideal_pings, "{pings} {ideal_pings}");
+        Ok(())
+    }
+
+    #[test]
+    fn test_simulation_memory_pool() -> anyhow::Result<()> {
+        for policy in [
+            crate::memory_pool::MemoryPolicy::Partitioned,
+            crate::memory_pool::MemoryPolicy::FirstCome,
+            crate::memory_pool::MemoryPolicy::MinGuarantee,
+            crate::memory_pool::MemoryPolicy::WeightedShares,
+        ] {
+            let mut user_config = chain_ping_config();
+            user_config.memory = crate::user_config::Memory {
+                policy,
+                min_qubits: 2,
+            };
+            let (output, scalar) = run_simulation(user_config)?;
+            let pool_occupancy = output
+                .series
+                .series
+                .get("pool-occupancy")
+                .map(|x| x.values.clone())
+                .unwrap_or_default();
+            if policy == crate::memory_pool::MemoryPolicy::Partitioned {
+                assert!(pool_occupancy.is_empty());
+            } else {
+                assert!(!pool_occupancy.is_empty());
+                for (_labels, _time, value) in pool_occupancy {
+                    assert!((0.0..=1.0).contains(&value));
+                }
+            }
+            if policy == crate::memory_pool::MemoryPolicy::WeightedShares {
+                assert!(scalar["memory_evictions"] > 0.0);
+            } else {
+                assert_eq!(0.0, scalar["memory_evictions"], "{:?}", policy);
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_simulation_storage() -> anyhow::Result<()> {
+        for storage_qubits in [0, 4] {
+            let mut conf = crate::user_config::ConfChainStatic::default();
+            for weight in [&mut conf.sat_weight, &mut conf.ogs_weight] {
+                weight.storage_qubits = storage_qubits;
+                weight.transfer_duration = 0.0001;
+                weight.transfer_fidelity = 0.99;
+            }
+            let user_config = chain_ping_config_with(conf);
+
+            let (_output, scalar) = run_simulation(user_config)?;
+            assert_eq!(1.0, scalar["logical_topology_found"]);
+            if storage_qubits == 0 {
+                assert_eq!(0.0, scalar["storage_transfers"]);
+            } else {
+                assert!(scalar["storage_transfers"] > 0.0);
+            }
+        }
+
+        // A node needs at least one storage qubit per link, and either both
+        // ends of a link have storage qubits or none.
+        for (sat_storage_qubits, ogs_storage_qubits) in [(4, 1), (4, 0), (0, 4)] {
+            let mut conf = crate::user_config::ConfChainStatic::default();
+            conf.sat_weight.storage_qubits = sat_storage_qubits;
+            conf.ogs_weight.storage_qubits = ogs_storage_qubits;
+            let user_config = chain_ping_config_with(conf);
+
+            assert!(run_simulation(user_config).is_err());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_simulation_multiplexing() -> anyhow::Result<()> {
+        let mut pings = vec![];
+        for num_modes in [1, 100] {
+            let mut conf = crate::user_config::ConfChainStatic::default();
+            conf.sat_weight.multiplexing = Some(crate::physical_topology::MultiplexedSource {
+                pulse_rate: 10.0,
+                num_modes,
+                mode_success_prob: 0.5,
+            });
+            let user_config = chain_ping_config_with(conf);
+
+            let (output, scalar) = run_simulation(user_config)?;
+            assert_eq!(1.0, scalar["logical_topology_found"]);
+            assert!(scalar["epr_batch_size"] > 0.0);
+            if num_modes == 1 {
+                assert!(scalar["epr_batch_size"] <= 1.0);
+                assert_eq!(0.0, scalar["batch_drops"]);
+            } else {
+                assert!(scalar["epr_batch_size"] > 1.0);
+                assert!(scalar["batch_drops"] > 0.0);
+            }
+            pings.push(num_samples(&output, "ping-latency"));
+        }
+
+        // The same pulse rate yields more EPR pairs with more modes, even
+        // though a batch does not fit into the memory.
+        assert!(pings[0] > 0 && pings[1] > pings[0], "{:?}", pings);
+        Ok(())
+    }
+
+    #[test]
+    fn test_simulation_memoryless() -> anyhow::Result<()> {
+        // The satellite only hosts sources, the OGSs measure on detection.
+        let mut conf = crate::user_config::ConfChainStatic::default();
+        conf.sat_weight.memoryless = true;
+        conf.sat_weight.memory_qubits = 0;
+        conf.sat_weight.detectors = 0;
+        conf.ogs_weight.memoryless = true;
+        conf.ogs_weight.memory_qubits = 0;
+        conf.ogs_weight.detection_jitter = 1e-9;
+
+        for coincidence_window in [1e-9, 1e-10] {
+            let mut user_config = chain_ping_config_with(conf.clone());
+            user_config.logical_topology.coincidence_window = coincidence_window;
+            user_config.applications = crate::user_config::Applications::ConfMeasure(
+                crate::user_config::ConfMeasure::default(),
+            );
+
+            let (output, scalar) = run_simulation(user_config)?;
+            assert_eq!(1.0, scalar["logical_topology_found"]);
+            assert!(scalar["coincidences"] > 0.0);
+            assert!(!output.series.series["fidelity"].values.is_empty());
+            if coincidence_window == 1e-9 {
+                assert_eq!(0.0, scalar["coincidence_misses"]);
+            } else {
+                assert!(scalar["coincidence_misses"] > 0.0);
+            }
+        }
+
+        // The EPR requests cannot be routed over memoryless logical links.
+        let mut user_config = chain_ping_config_with(conf.clone());
+        user_config.applications = crate::user_config::Applications::ConfClientServer(
+            crate::user_config::ConfClientServer::default(),
+        );
+        let (_output, scalar) = run_simulation(user_config)?;
+        assert_eq!(0.0, scalar["acceptance_ratio"]);
+
+        // Memoryless nodes cannot have memory qubits.
+        conf.ogs_weight.memory_qubits = 1;
+        let user_config = chain_ping_config_with(conf);
+        assert!(user_config
+            .physical_topology
+            .to_physical_topology(42)
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_simulation_request_types() -> anyhow::Result<()> {
+        let conf = chain_direct_conf();
+
+        for request_type in [
+            crate::event::RequestType::CreateAndKeep,
+            crate::event::RequestType::MeasureDirectly(crate::event::Basis::Z),
+            crate::event::RequestType::MeasureDirectly(crate::event::Basis::Random),
+        ] {
+            let mut user_config = chain_ping_config_with(conf.clone());
+            user_config.applications =
+                crate::user_config::Applications::ConfPing(crate::user_config::ConfPing {
+                    source_dest_pairs: crate::user_config::SourceDestPairs::AllToAll,
+                    max_requests: 0,
+                    request_type,
+                    number_of_pairs: 1,
+                    delivery: crate::event::Delivery::Sequential,
+                    timeout: None,
+                    max_retries: None,
+                    min_fidelity: None,
+                    classes: vec![],
+                });
+
+            let (output, scalar) = run_simulation(user_config)?;
+            assert_eq!(1.0, scalar["logical_topology_found"]);
+            assert!(!output.series.series["fidelity"].values.is_empty());
+            assert!(!output.series.series["ping-latency"].values.is_empty());
+            match request_type {
+                crate::event::RequestType::CreateAndKeep => assert!(scalar["qber"].is_nan()),
+                crate::event::RequestType::MeasureDirectly(_) => {
+                    assert!(scalar["qber"] > 0.0, "{:?}", request_type);
+                    assert!(scalar["qber"] < 0.5, "{:?}", request_type);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_simulation_batch_requests() -> anyhow::Result<()> {
+        let mut conf = chain_direct_conf();
+        // The EPR pairs of a request are established one at a time.
+        conf.sat_weight.capacity = 10.0;
+
+        let mut first_latency = vec![];
+        for delivery in [
+            crate::event::Delivery::Sequential,
+            crate::event::Delivery::AtLeast(2),
+            crate::event::Delivery::AllAtOnce,
+        ] {
+            let mut user_config = chain_ping_config_with(conf.clone());
+            user_config.applications =
+                crate::user_config::Applications::ConfPing(crate::user_config::ConfPing {
+                    source_dest_pairs: crate::user_config::SourceDestPairs::AllToAll,
+                    max_requests: 0,
+                    request_type: crate::event::RequestType::CreateAndKeep,
+                    number_of_pairs: 3,
+                    delivery,
+                    timeout: None,
+                    max_retries: None,
+                    min_fidelity: None,
+                    classes: vec![],
+                });
+
+            let (output, scalar) = run_simulation(user_config)?;
+            assert_eq!(1.0, scalar["logical_topology_found"]);
+            let first = &output.series.series["epr-first-pair-latency"].values;
+            let last = &output.series.series["epr-last-pair-latency"].values;
+            assert!(!first.is_empty());
+            assert!(!last.is_empty());
+            let avg = |values: &Vec<(Vec<String>, f64, f64)>| {
+                values
+                    .iter()
+                    .map(|(_labels, _time, value)| value)
+                    .sum::<f64>()
+                    / values.len() as f64
+            };
+            if delivery == crate::event::Delivery::AllAtOnce {
+                assert_eq!(avg(first), avg(last));
+            } else {
+                assert!(avg(first) < avg(last), "{:?}", delivery);
+            }
+            first_latency.push(avg(first));
+        }
+        assert!(first_latency[0] < first_latency[1]);
+        assert!(first_latency[1] < first_latency[2]);
+
+        // The EPR pairs failed release the responses held for the others,
+        // without being accounted for in the latency of the EPR pairs.
+        let mut failing_conf = conf.clone();
+        failing_conf.ogs_weight.read_efficiency = 0.9;
+        for (timeout, max_retries) in [(Some(0.5), None), (None, Some(0))] {
+            let mut user_config = chain_ping_config_with(failing_conf.clone());
+            if let crate::user_config::Applications::ConfPing(conf_ping) =
+                &mut user_config.applications
+            {
+                conf_ping.number_of_pairs = 3;
+                conf_ping.delivery = crate::event::Delivery::AllAtOnce;
+                conf_ping.timeout = timeout;
+                conf_ping.max_retries = max_retries;
+            }
+
+            let (output, scalar) = run_simulation(user_config)?;
+            assert!(scalar["timeout_rate"] + scalar["abandon_rate"] > 0.0);
+            assert!(!output.series.series["ping-latency"].values.is_empty());
+            let last = &output.series.series["epr-last-pair-latency"].values;
+            assert!(!last.is_empty());
+            if let Some(timeout) = timeout {
+                for (_labels, _time, value) in last {
+                    assert!(*value <= timeout);
+                }
+            }
+        }
+
+        // Requests must be for at least one EPR pair.
+        let mut user_config = chain_ping_config();
+        if let crate::user_config::Applications::ConfPing(conf_ping) = &mut user_config.applications
+        {
+            conf_ping.number_of_pairs = 0;
+        }
+        assert!(Simulation::new(
+            crate::config::Config {
+                seed: 42,
+                user_config,
+            },
+            false,
+        )
+        .is_err());
+
+        // The EPR pairs held until delivered must fit the memory qubits.
+        let number_of_pairs = conf.ogs_weight.memory_qubits + 1;
+        for (delivery, valid) in [
+            (crate::event::Delivery::Sequential, true),
+            (crate::event::Delivery::AtLeast(2), true),
+            (crate::event::Delivery::AtLeast(number_of_pairs), false),
+            (crate::event::Delivery::AllAtOnce, false),
+        ] {
+            let mut user_config = chain_ping_config_with(conf.clone());
+            if let crate::user_config::Applications::ConfPing(conf_ping) =
+                &mut user_config.applications
+            {
+                conf_ping.number_of_pairs = number_of_pairs;
+                conf_ping.delivery = delivery;
+            }
+            let res = Simulation::new(
+                crate::config::Config {
+                    seed: 42,
+                    user_config,
+                },
+                false,
+            );
+            assert_eq!(valid, res.is_ok(), "{:?}", delivery);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_simulation_request_limits() -> anyhow::Result<()> {
+        // The satellite generates EPR pairs slowly, and the OGSs lose half of
+        // the qubits read from memory.
+        let mut conf = chain_direct_conf();
+        conf.sat_weight.capacity = 10.0;
+        conf.ogs_weight.read_efficiency = 0.5;
+
+        for (timeout, max_retries) in [(None, None), (Some(0.05), None), (None, Some(0))] {
+            let mut user_config = chain_ping_config_with(conf.clone());
+            if let crate::user_config::Applications::ConfPing(conf_ping) =
+                &mut user_config.applications
+            {
+                conf_ping.timeout = timeout;
+                conf_ping.max_retries = max_retries;
+            }
+
+            let (output, scalar) = run_simulation(user_config)?;
+            assert_eq!(1.0, scalar["logical_topology_found"]);
+            let latency = &output.series.series["ping-latency"].values;
+            assert!(!latency.is_empty());
+            match (timeout, max_retries) {
+                (None, None) => {
+                    assert_eq!(0.0, scalar["timeout_rate"]);
+                    assert_eq!(0.0, scalar["abandon_rate"]);
+                }
+                (Some(timeout), None) => {
+                    assert!(scalar["timeout_rate"] > 0.0);
+                    assert_eq!(0.0, scalar["abandon_rate"]);
+                    for (_labels, _time, value) in latency {
+                        assert!(*value <= timeout);
+                    }
+                }
+                _ => {
+                    assert_eq!(0.0, scalar["timeout_rate"]);
+                    assert!(scalar["abandon_rate"] > 0.0);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_simulation_multi_hop() -> anyhow::Result<()> {
+        let conf = chain_relay_conf();
+        let simulation = Simulation::new(
+            crate::config::Config {
+                seed: 42,
+                user_config: chain_ping_config_with(conf.clone()),
+            },
+            false,
+        )?;
+        let path = simulation.network.logical_topology.path(0, 1);
+        assert_eq!(3, path.len());
+
+        let (output, scalar) = run_simulation(chain_ping_config_with(conf.clone()))?;
+        assert!(num_samples(&output, "ping-latency") > 0);
+        assert!(scalar["bsm_prob"] > 0.0 && scalar["bsm_prob"] < 1.0);
+        for (labels, _time, value) in &output.series.series["epr-request-latency"].values {
+            assert_eq!("2", labels[1]);
+            assert!(*value > 0.0);
+        }
+        for (_labels, _time, value) in &output.series.series["fidelity"].values {
+            assert!(*value > 0.5 && *value < 1.0);
+        }
+
+        // The EPR pairs failed at the intermediate node, or aborted along
+        // the path, release the responses held for the others.
+        let mut failing_conf = conf;
+        failing_conf.sat_weight.swapping_success_prob = 0.5;
+        failing_conf.sat_weight.read_efficiency = 0.8;
+        for (timeout, max_retries) in [(Some(0.1), None), (None, Some(0))] {
+            let mut user_config = chain_ping_config_with(failing_conf.clone());
+            if let crate::user_config::Applications::ConfPing(conf_ping) =
+                &mut user_config.applications
+            {
+                conf_ping.number_of_pairs = 3;
+                conf_ping.delivery = crate::event::Delivery::AllAtOnce;
+                conf_ping.timeout = timeout;
+                conf_ping.max_retries = max_retries;
+            }
+
+            let (output, scalar) = run_simulation(user_config)?;
+            assert!(scalar["timeout_rate"] + scalar["abandon_rate"] > 0.0);
+            assert!(scalar["memory_read_failures"] > 0.0);
+            assert!(num_samples(&output, "ping-latency") > 0);
+            if let Some(timeout) = timeout {
+                for (_labels, _time, value) in &output.series.series["epr-last-pair-latency"].values
+                {
+                    assert!(*value <= timeout);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_simulation_min_fidelity() -> anyhow::Result<()> {
+        let mut conf = chain_direct_conf();
+
+        for min_fidelity in [None, Some(0.8), Some(1.0), Some(1.5)] {
+            let mut user_config = chain_ping_config_with(conf.clone());
+            if let crate::user_config::Applications::ConfPing(conf_ping) =
+                &mut user_config.applications
+            {
+                conf_ping.min_fidelity = min_fidelity;
+            }
+
+            if min_fidelity == Some(1.5) {
+                assert!(run_simulation(user_config).is_err());
+                continue;
+            }
+            let (output, scalar) = run_simulation(user_config)?;
+            assert!(!output.series.series["fidelity"].values.is_empty());
+            match min_fidelity {
+                None => {
+                    assert!(scalar["fidelity_met"].is_nan());
+                    assert_eq!(0.0, scalar["fidelity_skips"]);
+                }
+                Some(0.8) => {
+                    assert_eq!(1.0, scalar["fidelity_met"]);
+                    assert_eq!(0.0, scalar["fidelity_skips"]);
+                }
+                _ => {
+                    // Only fresh EPR pairs are used, but they decay until
+                    // consumed, and every request is skipped at most once.
+                    assert!(scalar["fidelity_met"] < 1.0);
+                    assert!(scalar["fidelity_skips"] > 0.0);
+                    let requests = output.series.series["acceptance"].values.len();
+                    assert!(scalar["fidelity_skips"] <= requests as f64);
+                }
+            }
+        }
+
+        // Fresh EPR pairs decay while read from the memory, hence a perfect
+        // fidelity cannot be attained.
+        conf.ogs_weight.read_latency = 0.001;
+        let mut user_config = chain_ping_config_with(conf.clone());
+        if let crate::user_config::Applications::ConfPing(conf_ping) = &mut user_config.applications
+        {
+            conf_ping.min_fidelity = Some(1.0);
+        }
+        assert!(run_simulation(user_config).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_simulation_scheduling() -> anyhow::Result<()> {
+        // The satellite generates EPR pairs slowly, for many applications in
+        // two traffic classes.
+        let mut conf = chain_direct_conf();
+        conf.sat_weight.capacity = 10.0;
+
+        let mut share = vec![];
+        for policy in [
+            crate::request_scheduler::SchedulingPolicy::Fifo,
+            crate::request_scheduler::SchedulingPolicy::StrictPriority,
+            crate::request_scheduler::SchedulingPolicy::WeightedFair,
+        ] {
+            let mut user_config = chain_ping_config_with(conf.clone());
+            if let crate::user_config::Applications::ConfPing(conf_ping) =
+                &mut user_config.applications
+            {
+                conf_ping.source_dest_pairs = crate::user_config::SourceDestPairs::Random(8);
+                conf_ping.classes = vec![0, 1];
+            }
+            user_config.scheduling.policy = policy;
+            user_config.scheduling.weights = vec![3.0, 1.0];
+
+            let (output, _scalar) = run_simulation(user_config)?;
+            let latency = |class: &str| {
+                let values = output.series.series["class-latency"]
+                    .values
+                    .iter()
+                    .filter(|(labels, _time, _value)| labels[0] == class)
+                    .map(|(_labels, _time, value)| *value)
+                    .collect::<Vec<f64>>();
+                (
+                    values.len(),
+                    values.iter().sum::<f64>() / values.len() as f64,
+                )
+            };
+            let (served_0, latency_0) = latency("0");
+            let (served_1, latency_1) = latency("1");
+            assert!(served_1 > 0, "{:?}", policy);
+            if policy != crate::request_scheduler::SchedulingPolicy::Fifo {
+                assert!(latency_0 < latency_1, "{:?}", policy);
+            }
+            assert!(!output.series.series["class-fidelity"].values.is_empty());
+            share.push(served_0 as f64 / (served_0 + served_1) as f64);
+        }
+        // Class 0 is served more and more from FIFO, to weighted fair
+        // queuing, to strict priority.
+        assert!(share[0] < share[2]);
+        assert!(share[2] < share[1]);
+
+        // Scheduling policies can only be set for existing nodes.
+        let mut user_config = chain_ping_config();
+        user_config
+            .scheduling
+            .node_policies
+            .push(crate::request_scheduler::NodePolicy {
+                node_id: 99,
+                policy: crate::request_scheduler::SchedulingPolicy::EarliestDeadlineFirst,
+            });
+        assert!(run_simulation(user_config).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_simulation_scheduling_multi_hop() -> anyhow::Result<()> {
+        // The requests of two traffic classes are served in FIFO order at
+        // the end nodes, while the relay node may prioritize class 0 when
+        // forwarding them to the next hop.
+        let mut conf = chain_relay_conf();
+        conf.sat_weight.capacity = 50.0;
+
+        let mut results = vec![];
+        for relay_policy in [
+            None,
+            Some(crate::request_scheduler::SchedulingPolicy::StrictPriority),
+        ] {
+            let mut user_config = chain_ping_config_with(conf.clone());
+            if let crate::user_config::Applications::ConfPing(conf_ping) =
+                &mut user_config.applications
+            {
+                conf_ping.source_dest_pairs = crate::user_config::SourceDestPairs::Random(8);
+                conf_ping.classes = vec![0, 1];
+            }
+            if let Some(policy) = relay_policy {
+                user_config
+                    .scheduling
+                    .node_policies
+                    .push(crate::request_scheduler::NodePolicy { node_id: 3, policy });
+            }
+
+            let (output, _scalar) = run_simulation(user_config)?;
+            let latency = |class: &str| {
+                let values = output.series.series["class-latency"]
+                    .values
+                    .iter()
+                    .filter(|(labels, _time, _value)| labels[0] == class)
+                    .map(|(_labels, _time, value)| *value)
+                    .collect::<Vec<f64>>();
+                (
+                    values.len(),
+                    values.iter().sum::<f64>() / values.len() as f64,
+                )
+            };
+            let (served_0, latency_0) = latency("0");
+            let (served_1, latency_1) = latency("1");
+            assert!(served_1 > 0);
+            results.push((
+                served_0 as f64 / (served_0 + served_1) as f64,
+                latency_0,
+                latency_1,
+            ));
+        }
+        // Prioritizing class 0 at the relay node only serves it more, and
+        // faster, than FIFO, at the expense of class 1.
+        assert!(results[0].0 < results[1].0);
+        assert!(results[0].1 > results[1].1);
+        assert!(results[0].2 < results[1].2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_simulation_admission() -> anyhow::Result<()> {
+        // The satellite generates EPR pairs slowly, for clients requesting
+        // them faster.
+        let mut conf = chain_direct_conf();
+        conf.sat_weight.capacity = 10.0;
+
+        let mut latencies = vec![];
+        for policy in [
+            crate::admission::AdmissionPolicy::AcceptAll,
+            crate::admission::AdmissionPolicy::QueueLength { max_pending: 2 },
+            crate::admission::AdmissionPolicy::PathCapacity { max_delay: 0.2 },
+            crate::admission::AdmissionPolicy::TokenBucket {
+                rate: 2.0,
+                burst: 2.0,
+            },
+        ] {
+            let user_config = crate::user_config::UserConfig {
+                physical_topology: crate::user_config::PhysicalTopology::ConfChainStatic(
+                    conf.clone(),
+                ),
+                applications: crate::user_config::Applications::ConfClientServer(
+                    crate::user_config::ConfClientServer {
+                        source_dest_pairs: crate::user_config::SourceDestPairs::AllToAll,
+                        operation_rate: 20.0,
+                        ..Default::default()
+                    },
+                ),
+                admission: crate::user_config::Admission { policy },
+                ..Default::default()
+            };
+
+            let (output, scalar) = run_simulation(user_config)?;
+            if policy == crate::admission::AdmissionPolicy::AcceptAll {
+                assert_eq!(1.0, scalar["acceptance_ratio"]);
+            } else {
+                assert!(scalar["acceptance_ratio"] > 0.0, "{policy:?}");
+                assert!(scalar["acceptance_ratio"] < 1.0, "{policy:?}");
+            }
+            assert!(!output.series.series["acceptance"].values.is_empty());
+            let values = &output.series.series["client-latency"].values;
+            assert!(!values.is_empty(), "{policy:?}");
+            latencies.push(
+                values
+                    .iter()
+                    .map(|(_labels, _time, value)| *value)
+                    .sum::<f64>()
+                    / values.len() as f64,
+            );
+        }
+        // The requests accepted are served faster than without admission.
+        for latency in &latencies[1..] {
+            assert!(*latency < latencies[0]);
+        }
+
+        // Admission control requires valid parameters and is not supported
+        // with ping applications.
+        let mut user_config = chain_ping_config();
+        user_config.admission.policy =
+            crate::admission::AdmissionPolicy::QueueLength { max_pending: 2 };
+        assert!(run_simulation(user_config).is_err());
+        let user_config = crate::user_config::UserConfig {
+            applications: crate::user_config::Applications::ConfClientServer(
+                crate::user_config::ConfClientServer::default(),
+            ),
+            admission: crate::user_config::Admission {
+                policy: crate::admission::AdmissionPolicy::TokenBucket {
+                    rate: 0.0,
+                    burst: 1.0,
+                },
+            },
+            ..Default::default()
+        };
+        assert!(run_simulation(user_config).is_err());
+
+        // The token bucket must hold the EPR pairs of a request.
+        let mut user_config = chain_ping_config();
+        user_config.admission.policy = crate::admission::AdmissionPolicy::TokenBucket {
+            rate: 2.0,
+            burst: 2.0,
+        };
+        if let crate::user_config::Applications::ConfPing(conf_ping) = &mut user_config.applications
+        {
+            conf_ping.number_of_pairs = 3;
+        }
+        let err = run_simulation(user_config)
+            .err()
+            .expect("token bucket too small");
+        assert!(err.to_string().contains("token bucket"), "{err}");
+        Ok(())
+    }
+
+    #[test]
+    fn test_simulation_reservation() -> anyhow::Result<()> {
+        // The satellite generates EPR pairs at 10 EPR/s.
+        let mut conf = chain_direct_conf();
+        conf.sat_weight.capacity = 10.0;
+        let reservation_config_with =
+            |conf: crate::user_config::ConfChainStatic,
+             num_applications,
+             number_of_pairs,
+             window| crate::user_config::UserConfig {
+                physical_topology: crate::user_config::PhysicalTopology::ConfChainStatic(conf),
+                applications: crate::user_config::Applications::ConfReservation(
+                    crate::user_config::ConfReservation {
+                        source_dest_pairs: crate::user_config::SourceDestPairs::Random(
+                            num_applications,
+                        ),
+                        number_of_pairs,
+                        window,
+                        ..Default::default()
+                    },
+                ),
+                ..Default::default()
+            };
+        let reservation_config = |num_applications, number_of_pairs, window| {
+            reservation_config_with(conf.clone(), num_applications, number_of_pairs, window)
+        };
+
+        // Every reservation fits the capacity of the link.
+        let (output, scalar) = run_simulation(reservation_config(2, 5, 1.0))?;
+        assert_eq!(0.0, scalar["reservation_blocking"]);
+        assert!(scalar["reservation_utilization"] > 0.9);
+        assert!(!output.series.series["reservation-utilization"]
+            .values
+            .is_empty());
+        assert!(!output.series.series["fidelity"].values.is_empty());
+
+        // No reservation fits the capacity of the link.
+        let (output, scalar) = run_simulation(reservation_config(1, 20, 1.0))?;
+        assert_eq!(1.0, scalar["reservation_blocking"]);
+        assert!(output.series.series["fidelity"].values.is_empty());
+
+        // Only some of the overlapping reservations fit the capacity of the
+        // link, and those accepted are served.
+        let (_output, scalar) = run_simulation(reservation_config(4, 5, 1.0))?;
+        assert!(scalar["reservation_blocking"] > 0.0);
+        assert!(scalar["reservation_blocking"] < 1.0);
+        assert!(scalar["reservation_utilization"] > 0.9);
+
+        // The reservations between end nodes behind a relay node are booked
+        // on both logical links of the path, and those accepted are served
+        // by swapping the EPR pairs at the relay node.
+        let mut relay_conf = chain_relay_conf();
+        relay_conf.sat_weight.capacity = 50.0;
+        let (output, scalar) = run_simulation(reservation_config_with(relay_conf, 8, 10, 1.0))?;
+        assert!(scalar["reservation_blocking"] > 0.0);
+        assert!(scalar["reservation_blocking"] < 1.0);
+        assert!(scalar["reservation_utilization"] > 0.9);
+        assert!(scalar["bsm_prob"] > 0.0);
+        assert!(!output.series.series["fidelity"].values.is_empty());
+
+        assert!(run_simulation(reservation_config(1, 5, 0.0)).is_err());
+
+        // The reservations are booked on every logical link of their path,
+        // i.e., also on those of the paths of other sources.
+        let mut simulation = Simulation::new(
+            crate::config::Config {
+                seed: 42,
+                user_config: crate::user_config::UserConfig {
+                    physical_topology: crate::user_config::PhysicalTopology::ConfChainStatic(
+                        crate::user_config::ConfChainStatic {
+                            chain_params: crate::physical_topology::ChainParams {
+                                num_repeaters: 3,
+                                ..Default::default()
+                            },
+                            ..Default::default()
+                        },
+                    ),
+                    ..reservation_config(1, 5, 1.0)
+                },
+            },
+            false,
+        )?;
+        let logical_topology = simulation.network.logical_topology.clone();
+        let num_nodes = logical_topology.graph().node_count() as u32;
+        let path = (0..num_nodes)
+            .flat_map(|src| (0..num_nodes).map(move |dst| (src, dst)))
+            .filter(|(src, dst)| src != dst && logical_topology.routable(*src, *dst))
+            .map(|(src, dst)| logical_topology.path(src, dst))
+            .find(|path| path.len() > 2)
+            .expect("no path with more than one logical link");
+        let fill = |path: &[u32]| {
+            (logical_topology.capacity(path).floor() as u32)
+                .min(logical_topology.memory_qubits(path))
+        };
+        assert!(fill(&path) > 0);
+        assert!(reserve(&mut simulation, 0.0, &path, fill(&path), 1.0, 2.0));
+        assert!(!reserve(
+            &mut simulation,
+            0.0,
+            &path[1..],
+            fill(&path[1..]),
+            1.0,
+            2.0
+        ));
+        assert!(reserve(
+            &mut simulation,
+            0.0,
+            &path[1..],
+            fill(&path[1..]),
+            2.0,
+            3.0
+        ));
+
+        // A reservation whose time window is empty or already started is
+        // blocked.
+        for (now, start, end) in [(0.0, 4.0, 4.0), (0.0, 5.0, 4.0), (5.0, 4.0, 6.0)] {
+            assert!(!reserve(&mut simulation, now, &path, 1, start, end));
+        }
+        Ok(())
+    }
+
+    /// Request a reservation at the first node of a path, directly, and
+    /// return true if accepted.
+    fn reserve(
+        simulation: &mut Simulation,
+        now: f64,
+        path: &[u32],
+        number_of_pairs: u32,
+        start: f64,
+        end: f64,
+    ) -> bool {
+        use crate::event::{AppEventData, Event, EventHandler, EventType, NodeEventData};
+        let data = crate::event::ReservationData {
+            epr: crate::event::EprFiveTuple {
+                number_of_pairs,
+                ..crate::event::EprFiveTuple::new(path[0], 0, *path.last().unwrap(), 0, 0)
+            },
+            start: crate::utils::to_nanoseconds(start),
+            end: crate::utils::to_nanoseconds(end),
+        };
+        let (events, _samples) = simulation.network.nodes[path[0] as usize].handle(Event::new(
+            now,
+            EventType::NodeEvent(NodeEventData::EprReservationApp(data)),
+        ));
+        events.iter().any(|event| {
+            matches!(
+                event.event_type,
+                EventType::AppEvent(AppEventData::ReservationResponse(_, true))
+            )
+        })
+    }
+
+    #[test]
+    fn test_simulation_end_nodes() -> anyhow::Result<()> {
+        let ring_config = |num_end_nodes| crate::user_config::UserConfig {
+            physical_topology: crate::user_config::PhysicalTopology::ConfRing(
+                crate::user_config::ConfTerrestrial {
+                    end_nodes: crate::topology_generators::EndNodes::LowestDegree(num_end_nodes),
+                    ..Default::default()
+                },
+            ),
+            ..chain_ping_config()
+        };
+        run_simulation(ring_config(2))?;
+        for num_end_nodes in [0, 1] {
+            let err = run_simulation(ring_config(num_end_nodes))
+                .err()
+                .expect("fewer than two end nodes");
+            assert!(err.to_string().contains("two end nodes"), "{err}");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_simulation_time_sharing() -> anyhow::Result<()> {
+        // The satellite has a single transmitter and detector for two OGSs.
+        let mut conf = crate::user_config::ConfChainStatic::default();
+        conf.sat_weight.transmitters = 1;
+        conf.sat_weight.detectors = 1;
+        let mut user_config = chain_ping_config_with(conf);
+
+        let physical_topology = user_config.physical_topology.to_physical_topology(42)?;
+        for (policy, feasible) in [
+            (
+                crate::logical_topology::PhysicalToLogicalPolicy::RandomGreedy,
+                false,
+            ),
+            (
+                crate::logical_topology::PhysicalToLogicalPolicy::RandomGreedyTimeShared,
+                true,
+            ),
+        ] {
+            let logical_topology = crate::logical_topology::LogicalTopology::from_physical_topology(
+                &policy,
+                &physical_topology,
+                &mut rand::rngs::StdRng::seed_from_u64(42),
+            );
+            assert_eq!(
+                feasible,
+                logical_topology.is_ok_and(|x| crate::logical_topology::is_valid(
+                    x.graph(),
+                    &physical_topology
+                )
+                .is_ok())
+            );
+        }
+
+        user_config.logical_topology.physical_to_logical_policy =
+            crate::logical_topology::PhysicalToLogicalPolicy::RandomGreedyTimeShared;
+        assert!(run_simulation(user_config.clone()).is_err());
+
+        for schedule in [
+            crate::time_sharing::Schedule::RoundRobin,
+            crate::time_sharing::Schedule::DemandDriven,
+            crate::time_sharing::Schedule::Tdma,
+        ] {
+            user_config.time_sharing = Some(crate::time_sharing::TimeSharingParams {
+                schedule,
+                ..Default::default()
+            });
+            let (_output, scalar) = run_simulation(user_config.clone())?;
+            assert_eq!(1.0, scalar["logical_topology_found"]);
+            assert!(scalar["tx_switches"] > 0.0, "{:?}", schedule);
+        }
+        Ok(())
+    }
 }
@@ -10,28 +10,55 @@ pub fn physical_topology_2_2() -> crate::physical_topology::PhysicalTopology {
             ground_to_orbit_distance: 1.0,
             num_orbits: 2,
             orbit_length: 2,
+            uplink_loss: 0.0,
         },
         crate::physical_topology::NodeWeight {
             node_type: crate::physical_topology::NodeType::SAT,
             memory_qubits: 10,
             decay_rate: 1.0,
+            write_efficiency: 1.0,
+            read_efficiency: 1.0,
+            write_latency: 0.0,
+            read_latency: 0.0,
+            storage_qubits: 0,
+            storage_decay_rate: 0.0,
+            transfer_duration: 0.0,
+            transfer_fidelity: 1.0,
             swapping_success_prob: 0.5,
             swapping_duration: 0.001,
             correction_duration: 0.0,
+            processing_units: 0,
             detectors: 10,
             transmitters: 10,
             capacity: 1.0,
+            source: None,
+            multiplexing: None,
+            memoryless: false,
+            detection_jitter: 0.0,
         },
         crate::physical_topology::NodeWeight {
             node_type: crate::physical_topology::NodeType::OGS,
             memory_qubits: 20,
             decay_rate: 1.0,
+            write_efficiency: 1.0,
+            read_efficiency: 1.0,
+            write_latency: 0.0,
+            read_latency: 0.0,
+            storage_qubits: 0,
+            storage_decay_rate: 0.0,
+            transfer_duration: 0.0,
+            transfer_fidelity: 1.0,
             swapping_success_prob: 0.0,
             swapping_duration: 0.0,
             correction_duration: 0.001,
+            processing_units: 0,
             detectors: 10,
             transmitters: 0,
             capacity: 0.0,
+            source: None,
+            multiplexing: None,
+            memoryless: false,
+            detection_jitter: 0.0,
         },
         crate::physical_topology::StaticFidelities::default(),
     )
@@ -54,7 +81,7 @@ pub fn logical_topology_2_2() -> (
                 &mut rng,
             )
         {
-            if crate::logical_topology::is_valid(&logical_topology.graph(), &physical_topology)
+            if crate::logical_topology::is_valid(logical_topology.graph(), &physical_topology)
                 .is_ok()
             {
                 return (physical_topology, logical_topology);
@@ -66,3 +93,87 @@ pub fn logical_topology_2_2() -> (
         num_tries
     );
 }
+
+/// Return a configuration with a chain topology and ping applications
+/// between the two OGSs, which request EPR pairs continuously.
+pub fn chain_ping_config() -> crate::user_config::UserConfig {
+    chain_ping_config_with(crate::user_config::ConfChainStatic::default())
+}
+
+/// Same as [`chain_ping_config`], with the given chain topology.
+pub fn chain_ping_config_with(
+    conf: crate::user_config::ConfChainStatic,
+) -> crate::user_config::UserConfig {
+    crate::user_config::UserConfig {
+        physical_topology: crate::user_config::PhysicalTopology::ConfChainStatic(conf),
+        applications: crate::user_config::Applications::ConfPing(crate::user_config::ConfPing {
+            source_dest_pairs: crate::user_config::SourceDestPairs::AllToAll,
+            max_requests: 0,
+            request_type: crate::event::RequestType::CreateAndKeep,
+            number_of_pairs: 1,
+            delivery: crate::event::Delivery::Sequential,
+            timeout: None,
+            max_retries: None,
+            min_fidelity: None,
+            classes: vec![],
+        }),
+        ..Default::default()
+    }
+}
+
+/// Return a chain topology in which the satellite only generates EPR pairs
+/// directly between the two OGSs, i.e., without storing them.
+pub fn chain_direct_conf() -> crate::user_config::ConfChainStatic {
+    let mut conf = crate::user_config::ConfChainStatic::default();
+    conf.sat_weight.memory_qubits = 0;
+    conf.sat_weight.detectors = 0;
+    conf
+}
+
+/// Return a chain topology in which the EPR pairs between the two OGSs are
+/// established by entanglement swapping at a satellite, which has enough
+/// memory qubits to hold its half EPR pairs until the ES requests arrive.
+pub fn chain_relay_conf() -> crate::user_config::ConfChainStatic {
+    let mut conf = crate::user_config::ConfChainStatic::default();
+    conf.chain_params.num_repeaters = 3;
+    conf.sat_weight.memory_qubits = 400;
+    conf
+}
+
+/// Run a simulation and return its output, with the scalar metrics
+/// also indexed by name.
+pub fn run_simulation(
+    user_config: crate::user_config::UserConfig,
+) -> anyhow::Result<(
+    crate::output::Output,
+    std::collections::HashMap<String, f64>,
+)> {
+    use crate::utils::CsvFriend;
+
+    let mut simulation = crate::simulation::Simulation::new(
+        crate::config::Config {
+            seed: 42,
+            user_config,
+        },
+        false,
+    )?;
+    let output = simulation.run();
+    let header = output.scalar.header();
+    let values = output.scalar.to_csv();
+    let scalar = header
+        .split(',')
+        .zip(values.split(','))
+        .map(|(k, v)| (k.to_string(), v.parse::<f64>().unwrap()))
+        .collect();
+    Ok((output, scalar))
+}
+
+/// Return the number of samples of a series, 0 if it does not exist.
+pub fn num_samples(output: &crate::output::Output, name: &str) -> usize {
+    output
+        .series
+        .series
+        .get(name)
+        .map(|x| x.values.len())
+        .unwrap_or_default()
+}
@@ -0,0 +1,204 @@
+// SPDX-FileCopyrightText: © 2025 Claudio Cicconetti <c.cicconetti@iit.cnr.it>
+// SPDX-License-Identifier: MIT
+
+/// Schedule of a transmitter shared by several logical links.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum Schedule {
+    /// Serve the logical links in turn, each for a slot.
+    #[default]
+    RoundRobin,
+    /// At the end of every slot, serve the logical link with the most
+    /// requests waiting for an EPR pair, in turn if there are none.
+    DemandDriven,
+    /// Serve the logical links in turn within a frame, which is divided into
+    /// equal slots that include the switching time.
+    Tdma,
+}
+
+/// Configuration of the transmitters shared by several logical links.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct TimeSharingParams {
+    /// Schedule of the logical links.
+    pub schedule: Schedule,
+    /// Time during which a logical link is served with `RoundRobin` and
+    /// `DemandDriven`, in s.
+    pub slot_duration: f64,
+    /// Duration of a frame with `Tdma`, in s.
+    pub frame_duration: f64,
+    /// Time to re-point the transmitter and acquire the detectors of another
+    /// logical link, during which no EPR pairs are generated, in s.
+    pub switching_time: f64,
+}
+
+impl Default for TimeSharingParams {
+    fn default() -> Self {
+        Self {
+            schedule: Schedule::default(),
+            slot_duration: 0.1,
+            frame_duration: 1.0,
+            switching_time: 0.01,
+        }
+    }
+}
+
+impl TimeSharingParams {
+    fn valid(&self) -> anyhow::Result<()> {
+        anyhow::ensure!(
+            self.slot_duration > 0.0,
+            "invalid slot duration: {} <= 0",
+            self.slot_duration
+        );
+        anyhow::ensure!(
+            self.frame_duration > 0.0,
+            "invalid frame duration: {} <= 0",
+            self.frame_duration
+        );
+        anyhow::ensure!(
+            self.switching_time >= 0.0,
+            "invalid switching time: {} < 0",
+            self.switching_time
+        );
+        Ok(())
+    }
+}
+
+/// Scheduler of a transmitter shared by several logical links.
+#[derive(Debug)]
+pub struct TransmitterScheduler {
+    /// Configuration parameters.
+    params: TimeSharingParams,
+    /// Number of logical links served.
+    num_links: usize,
+    /// Index of the logical link currently served.
+    current: usize,
+}
+
+impl TransmitterScheduler {
+    /// Create a scheduler serving the given number of logical links,
+    /// starting from the first one.
+    pub fn new(params: TimeSharingParams, num_links: usize) -> anyhow::Result<Self> {
+        params.valid()?;
+        anyhow::ensure!(num_links > 0, "no logical links to serve");
+        if params.schedule == Schedule::Tdma {
+            anyhow::ensure!(
+                params.frame_duration > num_links as f64 * params.switching_time,
+                "frame duration {} too short for {} slots with switching time {}",
+                params.frame_duration,
+                num_links,
+                params.switching_time
+            );
+        }
+        Ok(Self {
+            params,
+            num_links,
+            current: 0,
+        })
+    }
+
+    /// Return the index of the logical link currently served.
+    pub fn current(&self) -> usize {
+        self.current
+    }
+
+    /// Return the time during which the current logical link is served, in s.
+    pub fn dwell(&self) -> f64 {
+        match self.params.schedule {
+            Schedule::RoundRobin | Schedule::DemandDriven => self.params.slot_duration,
+            Schedule::Tdma => {
+                self.params.frame_duration / self.num_links as f64 - self.params.switching_time
+            }
+        }
+    }
+
+    /// Select the next logical link to be served at the end of a slot.
+    /// Return the switching time, which is zero if the same logical link
+    /// is served again.
+    ///
+    /// Parameters:
+    /// - `demand`: the number of requests waiting on every logical link
+    pub fn next(&mut self, demand: &[usize]) -> f64 {
+        assert_eq!(self.num_links, demand.len());
+        let next_in_turn = (self.current + 1) % self.num_links;
+        let next = match self.params.schedule {
+            Schedule::RoundRobin | Schedule::Tdma => next_in_turn,
+            Schedule::DemandDriven => {
+                // Break ties in turn, starting from the next logical link.
+                let mut next = next_in_turn;
+                for i in 0..self.num_links {
+                    let ndx = (next_in_turn + i) % self.num_links;
+                    if demand[ndx] > demand[next] {
+                        next = ndx;
+                    }
+                }
+                next
+            }
+        };
+        let switching_time = if next == self.current {
+            0.0
+        } else {
+            self.params.switching_time
+        };
+        self.current = next;
+        switching_time
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Schedule, TimeSharingParams, TransmitterScheduler};
+
+    #[test]
+    fn test_time_sharing_schedules() {
+        let params = TimeSharingParams {
+            slot_duration: 0.5,
+            frame_duration: 3.0,
+            switching_time: 0.1,
+            ..Default::default()
+        };
+
+        let mut scheduler = TransmitterScheduler::new(params.clone(), 3).unwrap();
+        assert_eq!(0.5, scheduler.dwell());
+        for expected in [1, 2, 0, 1] {
+            assert_eq!(0.1, scheduler.next(&[9, 9, 9]));
+            assert_eq!(expected, scheduler.current());
+        }
+
+        let mut scheduler = TransmitterScheduler::new(
+            TimeSharingParams {
+                schedule: Schedule::Tdma,
+                ..params.clone()
+            },
+            3,
+        )
+        .unwrap();
+        assert_float_eq::assert_f64_near!(0.9, scheduler.dwell());
+        scheduler.next(&[0, 0, 0]);
+        assert_eq!(1, scheduler.current());
+
+        let mut scheduler = TransmitterScheduler::new(
+            TimeSharingParams {
+                schedule: Schedule::DemandDriven,
+                ..params.clone()
+            },
+            3,
+        )
+        .unwrap();
+        assert_eq!(0.0, scheduler.next(&[5, 0, 1]));
+        assert_eq!(0, scheduler.current());
+        assert_eq!(0.1, scheduler.next(&[0, 0, 1]));
+        assert_eq!(2, scheduler.current());
+        scheduler.next(&[0, 0, 0]);
+        assert_eq!(0, scheduler.current());
+
+        assert!(TransmitterScheduler::new(params.clone(), 0).is_err());
+        assert!(TransmitterScheduler::new(
+            TimeSharingParams {
+                schedule: Schedule::Tdma,
+                frame_duration: 0.3,
+                ..params
+            },
+            3
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,520 @@
+// SPDX-FileCopyrightText: © 2025 Claudio Cicconetti <c.cicconetti@iit.cnr.it>
+// SPDX-License-Identifier: MIT
+
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+/// Shape of a generated topology, i.e., the number of nodes and the edges
+/// between them, as tuples (u, v, length in m).
+#[derive(Debug, Default)]
+pub struct Shape {
+    pub num_nodes: u32,
+    pub edges: Vec<(u32, u32, f64)>,
+}
+
+impl Shape {
+    /// Return the degree of each node.
+    pub fn degrees(&self) -> Vec<u32> {
+        let mut degrees = vec![0; self.num_nodes as usize];
+        for (u, v, _) in &self.edges {
+            degrees[*u as usize] += 1;
+            degrees[*v as usize] += 1;
+        }
+        degrees
+    }
+}
+
+/// Generator of the shape of a topology.
+pub trait Generator {
+    fn generate(&self, rng: &mut rand::rngs::StdRng) -> anyhow::Result<Shape>;
+}
+
+fn ensure_non_negative(value: f64, name: &str) -> anyhow::Result<()> {
+    anyhow::ensure!(value >= 0.0, "invalid {}: {} < 0", name, value);
+    Ok(())
+}
+
+/// Draw `num_nodes` positions uniformly at random in a square with given side.
+fn random_positions(num_nodes: u32, side: f64, rng: &mut rand::rngs::StdRng) -> Vec<(f64, f64)> {
+    (0..num_nodes)
+        .map(|_| (rng.gen::<f64>() * side, rng.gen::<f64>() * side))
+        .collect()
+}
+
+fn euclidean(a: (f64, f64), b: (f64, f64)) -> f64 {
+    ((a.0 - b.0).powi(2) + (a.1 - b.1).powi(2)).sqrt()
+}
+
+/// Random geometric graph: nodes are dropped uniformly at random in a square
+/// and two nodes are connected if they are within a given radius.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct RandomGeometricParams {
+    /// Number of nodes.
+    pub num_nodes: u32,
+    /// Side of the square area, in m.
+    pub side: f64,
+    /// Maximum distance between two connected nodes, in m.
+    pub radius: f64,
+}
+
+impl Default for RandomGeometricParams {
+    fn default() -> Self {
+        Self {
+            num_nodes: 20,
+            side: 100000.0,
+            radius: 40000.0,
+        }
+    }
+}
+
+impl Generator for RandomGeometricParams {
+    fn generate(&self, rng: &mut rand::rngs::StdRng) -> anyhow::Result<Shape> {
+        ensure_non_negative(self.side, "side")?;
+        ensure_non_negative(self.radius, "radius")?;
+        let positions = random_positions(self.num_nodes, self.side, rng);
+        let mut edges = vec![];
+        for u in 0..self.num_nodes {
+            for v in 0..u {
+                let distance = euclidean(positions[u as usize], positions[v as usize]);
+                if distance <= self.radius {
+                    edges.push((u, v, distance));
+                }
+            }
+        }
+        Ok(Shape {
+            num_nodes: self.num_nodes,
+            edges,
+        })
+    }
+}
+
+/// Waxman random graph: nodes are dropped uniformly at random in a square
+/// and two nodes at distance d are connected with probability
+/// beta * exp(-d / (alpha * L)), where L is the diagonal of the square.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct WaxmanParams {
+    /// Number of nodes.
+    pub num_nodes: u32,
+    /// Side of the square area, in m.
+    pub side: f64,
+    /// Ratio of long to short edges.
+    pub alpha: f64,
+    /// Density of edges.
+    pub beta: f64,
+}
+
+impl Default for WaxmanParams {
+    fn default() -> Self {
+        Self {
+            num_nodes: 20,
+            side: 100000.0,
+            alpha: 0.4,
+            beta: 0.4,
+        }
+    }
+}
+
+impl Generator for WaxmanParams {
+    fn generate(&self, rng: &mut rand::rngs::StdRng) -> anyhow::Result<Shape> {
+        ensure_non_negative(self.side, "side")?;
+        anyhow::ensure!(self.alpha > 0.0, "invalid alpha: {} <= 0", self.alpha);
+        anyhow::ensure!(
+            self.beta > 0.0 && self.beta <= 1.0,
+            "invalid beta: {}",
+            self.beta
+        );
+        let positions = random_positions(self.num_nodes, self.side, rng);
+        let max_distance = self.side * 2.0_f64.sqrt();
+        let mut edges = vec![];
+        for u in 0..self.num_nodes {
+            for v in 0..u {
+                let distance = euclidean(positions[u as usize], positions[v as usize]);
+                let prob = self.beta * (-distance / (self.alpha * max_distance)).exp();
+                if rng.gen::<f64>() < prob {
+                    edges.push((u, v, distance));
+                }
+            }
+        }
+        Ok(Shape {
+            num_nodes: self.num_nodes,
+            edges,
+        })
+    }
+}
+
+/// Barabási–Albert preferential attachment graph: starting from a full mesh
+/// of `m + 1` nodes, every new node is connected to `m` distinct existing
+/// nodes, drawn with probability proportional to their degree.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct BarabasiAlbertParams {
+    /// Number of nodes.
+    pub num_nodes: u32,
+    /// Number of edges added with every new node.
+    pub m: u32,
+    /// Length of all links, in m.
+    pub link_length: f64,
+}
+
+impl Default for BarabasiAlbertParams {
+    fn default() -> Self {
+        Self {
+            num_nodes: 20,
+            m: 2,
+            link_length: 20000.0,
+        }
+    }
+}
+
+impl Generator for BarabasiAlbertParams {
+    fn generate(&self, rng: &mut rand::rngs::StdRng) -> anyhow::Result<Shape> {
+        ensure_non_negative(self.link_length, "link length")?;
+        anyhow::ensure!(self.m > 0, "vanishing number of edges per new node");
+        anyhow::ensure!(
+            self.num_nodes > self.m,
+            "the number of nodes ({}) must be greater than m ({})",
+            self.num_nodes,
+            self.m
+        );
+        let mut edges = vec![];
+        // Every node appears once for each edge it is an end-point of.
+        let mut endpoints = vec![];
+        for u in 0..=self.m {
+            for v in 0..u {
+                edges.push((u, v, self.link_length));
+                endpoints.push(u);
+                endpoints.push(v);
+            }
+        }
+        for u in (self.m + 1)..self.num_nodes {
+            let mut targets = std::collections::BTreeSet::new();
+            while targets.len() < self.m as usize {
+                targets.insert(endpoints[rng.gen_range(0..endpoints.len())]);
+            }
+            for v in targets {
+                edges.push((u, v, self.link_length));
+                endpoints.push(u);
+                endpoints.push(v);
+            }
+        }
+        Ok(Shape {
+            num_nodes: self.num_nodes,
+            edges,
+        })
+    }
+}
+
+/// Ring of nodes at the same distance from their neighbors.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct RingParams {
+    /// Number of nodes.
+    pub num_nodes: u32,
+    /// Length of all links, in m.
+    pub link_length: f64,
+}
+
+impl Default for RingParams {
+    fn default() -> Self {
+        Self {
+            num_nodes: 10,
+            link_length: 20000.0,
+        }
+    }
+}
+
+impl Generator for RingParams {
+    fn generate(&self, _rng: &mut rand::rngs::StdRng) -> anyhow::Result<Shape> {
+        ensure_non_negative(self.link_length, "link length")?;
+        anyhow::ensure!(
+            self.num_nodes >= 3,
+            "a ring needs at least three nodes, found {}",
+            self.num_nodes
+        );
+        Ok(Shape {
+            num_nodes: self.num_nodes,
+            edges: (0..self.num_nodes)
+                .map(|u| (u, (u + 1) % self.num_nodes, self.link_length))
+                .collect(),
+        })
+    }
+}
+
+/// Star with the center node (index 0) connected to all the leaves.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct StarParams {
+    /// Number of leaves.
+    pub num_leaves: u32,
+    /// Length of all links, in m.
+    pub link_length: f64,
+}
+
+impl Default for StarParams {
+    fn default() -> Self {
+        Self {
+            num_leaves: 5,
+            link_length: 20000.0,
+        }
+    }
+}
+
+impl Generator for StarParams {
+    fn generate(&self, _rng: &mut rand::rngs::StdRng) -> anyhow::Result<Shape> {
+        ensure_non_negative(self.link_length, "link length")?;
+        anyhow::ensure!(self.num_leaves > 0, "vanishing number of leaves");
+        Ok(Shape {
+            num_nodes: self.num_leaves + 1,
+            edges: (1..=self.num_leaves)
+                .map(|v| (0, v, self.link_length))
+                .collect(),
+        })
+    }
+}
+
+/// Full tree where every non-leaf node has the same number of children.
+/// The root has index 0 and the nodes are numbered breadth-first.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct TreeParams {
+    /// Number of levels below the root.
+    pub depth: u32,
+    /// Number of children of each non-leaf node.
+    pub branching: u32,
+    /// Length of all links, in m.
+    pub link_length: f64,
+}
+
+impl Default for TreeParams {
+    fn default() -> Self {
+        Self {
+            depth: 2,
+            branching: 2,
+            link_length: 20000.0,
+        }
+    }
+}
+
+impl Generator for TreeParams {
+    fn generate(&self, _rng: &mut rand::rngs::StdRng) -> anyhow::Result<Shape> {
+        ensure_non_negative(self.link_length, "link length")?;
+        anyhow::ensure!(self.depth > 0, "vanishing tree depth");
+        anyhow::ensure!(self.branching > 0, "vanishing tree branching");
+        let mut edges = vec![];
+        let mut level = vec![0];
+        let mut num_nodes = 1;
+        for _ in 0..self.depth {
+            let mut next_level = vec![];
+            for parent in level {
+                for _ in 0..self.branching {
+                    edges.push((parent, num_nodes, self.link_length));
+                    next_level.push(num_nodes);
+                    num_nodes += 1;
+                }
+            }
+            level = next_level;
+        }
+        Ok(Shape { num_nodes, edges })
+    }
+}
+
+/// 2D lattice, without wrapping around at the borders.
+/// The nodes are numbered row by row.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct LatticeParams {
+    /// Number of rows.
+    pub rows: u32,
+    /// Number of columns.
+    pub cols: u32,
+    /// Length of all links, in m.
+    pub link_length: f64,
+}
+
+impl Default for LatticeParams {
+    fn default() -> Self {
+        Self {
+            rows: 4,
+            cols: 4,
+            link_length: 20000.0,
+        }
+    }
+}
+
+impl Generator for LatticeParams {
+    fn generate(&self, _rng: &mut rand::rngs::StdRng) -> anyhow::Result<Shape> {
+        ensure_non_negative(self.link_length, "link length")?;
+        anyhow::ensure!(self.rows > 0, "vanishing number of rows");
+        anyhow::ensure!(self.cols > 0, "vanishing number of columns");
+        let mut edges = vec![];
+        for i in 0..self.rows {
+            for j in 0..self.cols {
+                let ndx = i * self.cols + j;
+                // Right
+                if j != (self.cols - 1) {
+                    edges.push((ndx, ndx + 1, self.link_length));
+                }
+                // Down
+                if i != (self.rows - 1) {
+                    edges.push((ndx, ndx + self.cols, self.link_length));
+                }
+            }
+        }
+        Ok(Shape {
+            num_nodes: self.rows * self.cols,
+            edges,
+        })
+    }
+}
+
+/// Selection of the end nodes in a generated topology, all the other nodes
+/// are repeaters.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub enum EndNodes {
+    /// Given number of nodes drawn at random.
+    Random(u32),
+    /// Given number of nodes with the lowest degree, e.g., the leaves of a
+    /// tree or star. Ties are broken by the node index.
+    LowestDegree(u32),
+}
+
+impl Default for EndNodes {
+    fn default() -> Self {
+        Self::LowestDegree(2)
+    }
+}
+
+impl EndNodes {
+    /// Return the indices of the end nodes in the given shape, sorted.
+    pub fn select(&self, shape: &Shape, rng: &mut rand::rngs::StdRng) -> anyhow::Result<Vec<u32>> {
+        let num_end_nodes = match self {
+            EndNodes::Random(num) | EndNodes::LowestDegree(num) => *num,
+        };
+        anyhow::ensure!(
+            num_end_nodes <= shape.num_nodes,
+            "cannot select {} end nodes out of {}",
+            num_end_nodes,
+            shape.num_nodes
+        );
+        let mut candidates = (0..shape.num_nodes).collect::<Vec<u32>>();
+        match self {
+            EndNodes::Random(_) => {
+                candidates.shuffle(rng);
+            }
+            EndNodes::LowestDegree(_) => {
+                let degrees = shape.degrees();
+                candidates.sort_by_key(|ndx| degrees[*ndx as usize]);
+            }
+        }
+        let mut ret = candidates[0..num_end_nodes as usize].to_vec();
+        ret.sort();
+        Ok(ret)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+
+    use super::{
+        BarabasiAlbertParams, EndNodes, Generator, LatticeParams, RandomGeometricParams,
+        RingParams, StarParams, TreeParams, WaxmanParams,
+    };
+
+    #[test]
+    fn test_topology_generators_deterministic() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(42);
+
+        let ring = RingParams {
+            num_nodes: 5,
+            link_length: 1.0,
+        }
+        .generate(&mut rng)
+        .unwrap();
+        assert_eq!(5, ring.edges.len());
+        assert!(ring.degrees().iter().all(|d| *d == 2));
+
+        let star = StarParams {
+            num_leaves: 4,
+            link_length: 1.0,
+        }
+        .generate(&mut rng)
+        .unwrap();
+        assert_eq!(5, star.num_nodes);
+        assert_eq!(vec![4, 1, 1, 1, 1], star.degrees());
+
+        let tree = TreeParams {
+            depth: 2,
+            branching: 3,
+            link_length: 1.0,
+        }
+        .generate(&mut rng)
+        .unwrap();
+        assert_eq!(13, tree.num_nodes);
+        assert_eq!(12, tree.edges.len());
+        assert_eq!(
+            (4..13).collect::<Vec<u32>>(),
+            EndNodes::LowestDegree(9).select(&tree, &mut rng).unwrap()
+        );
+
+        let lattice = LatticeParams {
+            rows: 3,
+            cols: 4,
+            link_length: 1.0,
+        }
+        .generate(&mut rng)
+        .unwrap();
+        assert_eq!(12, lattice.num_nodes);
+        assert_eq!(3 * 3 + 2 * 4, lattice.edges.len());
+        assert_eq!(
+            vec![0, 3, 8, 11],
+            EndNodes::LowestDegree(4)
+                .select(&lattice, &mut rng)
+                .unwrap()
+        );
+
+        assert!(RingParams {
+            num_nodes: 2,
+            link_length: 1.0
+        }
+        .generate(&mut rng)
+        .is_err());
+        assert!(EndNodes::Random(13).select(&lattice, &mut rng).is_err());
+    }
+
+    #[test]
+    fn test_topology_generators_random() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(42);
+
+        let rgg = RandomGeometricParams {
+            num_nodes: 50,
+            side: 100.0,
+            radius: 30.0,
+        }
+        .generate(&mut rng)
+        .unwrap();
+        assert_eq!(50, rgg.num_nodes);
+        assert!(!rgg.edges.is_empty());
+        assert!(rgg.edges.iter().all(|(_, _, d)| *d <= 30.0));
+
+        let waxman = WaxmanParams {
+            num_nodes: 50,
+            side: 100.0,
+            alpha: 0.5,
+            beta: 0.5,
+        }
+        .generate(&mut rng)
+        .unwrap();
+        assert!(!waxman.edges.is_empty());
+        assert!(waxman.edges.len() < 50 * 49 / 2);
+
+        let ba = BarabasiAlbertParams {
+            num_nodes: 50,
+            m: 2,
+            link_length: 1.0,
+        }
+        .generate(&mut rng)
+        .unwrap();
+        assert_eq!(3 + 2 * 47, ba.edges.len());
+        assert!(ba.degrees().iter().all(|d| *d >= 2));
+
+        let end_nodes = EndNodes::Random(10).select(&ba, &mut rng).unwrap();
+        assert_eq!(10, end_nodes.len());
+        assert!(end_nodes.windows(2).all(|w| w[0] < w[1]));
+    }
+}
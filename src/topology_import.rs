@@ -0,0 +1,520 @@
+// SPDX-FileCopyrightText: © 2025 Claudio Cicconetti <c.cicconetti@iit.cnr.it>
+// SPDX-License-Identifier: MIT
+
+//! Import of physical topologies from files.
+//!
+//! Two formats are supported, which carry the same information:
+//!
+//! - JSON, with an object with the following fields:
+//!   - `nodes`: array of objects, each with an optional `id` (string or
+//!     number, the position in the array if missing) and any of the fields
+//!     of `NodeWeight`
+//!   - `edges`: array of objects, each with the `source` and `target` node
+//!     identifiers, the `distance` in m, an optional `link_type` (either
+//!     `FreeSpace`, the default, or `Fiber`) and, for fiber links only, any
+//!     of the fields of `FiberParams`
+//!   - `fidelities`: optional object with any of the fields of
+//!     `StaticFidelities`
+//!   - `uplink_loss`: optional loss of ground-to-orbit uplinks, in dB
+//! - GraphML, where the node and edge attributes are specified with `data`
+//!   elements using the same names as above (`attr.name` of the keys), while
+//!   the static fidelities and the uplink loss are attributes of the graph.
+//!
+//! Every node must have a `node_type`, which in GraphML can be also given as
+//! the default value of the key. Missing attributes fall back to the
+//! defaults of the corresponding node type, e.g., `NodeWeight::default_ogs()`,
+//! or structure. Unknown attributes and fields, e.g., the geographical
+//! coordinates of the nodes in the Internet Topology Zoo, are ignored with a
+//! warning.
+
+use crate::physical_topology::{FiberParams, LinkType, NodeType, NodeWeight, StaticFidelities};
+
+/// Node attributes that are not characteristics of the node.
+const NODE_RESERVED: [&str; 2] = ["id", "label"];
+
+/// Edge attributes that are not characteristics of the fiber.
+const EDGE_RESERVED: [&str; 5] = ["id", "source", "target", "distance", "link_type"];
+
+/// Return a value of type T obtained from the default one by overriding the
+/// fields with the given attributes, except those in `reserved`.
+/// The attributes that are not fields of T are ignored with a warning.
+/// Return an error if the value of an attribute has an invalid type.
+fn overlay<T: serde::Serialize + serde::de::DeserializeOwned>(
+    default: T,
+    attributes: &serde_json::Map<String, serde_json::Value>,
+    reserved: &[&str],
+    what: &str,
+) -> anyhow::Result<T> {
+    let mut fields = match serde_json::to_value(default)? {
+        serde_json::Value::Object(fields) => fields,
+        _ => anyhow::bail!("{} defaults are not a structure", what),
+    };
+    for (name, value) in attributes {
+        if reserved.contains(&name.as_str()) {
+            continue;
+        }
+        if !fields.contains_key(name) {
+            log::warn!("ignoring unknown attribute '{}' of {}", name, what);
+            continue;
+        }
+        fields.insert(name.clone(), value.clone());
+    }
+    serde_json::from_value(serde_json::Value::Object(fields))
+        .map_err(|err| anyhow::anyhow!("invalid attributes of {}: {}", what, err))
+}
+
+/// Return the node identifier as a string, if it is a string or a number.
+fn identifier(value: &serde_json::Value) -> Option<String> {
+    match value {
+        serde_json::Value::String(id) => Some(id.clone()),
+        serde_json::Value::Number(id) => Some(id.to_string()),
+        _ => None,
+    }
+}
+
+/// Return the object in `value`, or an error referring to `what`.
+fn as_object<'a>(
+    value: &'a serde_json::Value,
+    what: &str,
+) -> anyhow::Result<&'a serde_json::Map<String, serde_json::Value>> {
+    value
+        .as_object()
+        .ok_or_else(|| anyhow::anyhow!("{} is not an object", what))
+}
+
+fn parse_node(
+    ndx: usize,
+    attributes: &serde_json::Map<String, serde_json::Value>,
+) -> anyhow::Result<NodeWeight> {
+    let what = format!("node {}", ndx);
+    let default = match attributes.get("node_type") {
+        Some(node_type) => match serde_json::from_value(node_type.clone()) {
+            Ok(NodeType::SAT) => NodeWeight::default_sat(),
+            Ok(NodeType::OGS) => NodeWeight::default_ogs(),
+            Ok(NodeType::REP) => NodeWeight::default_rep(),
+            Ok(NodeType::HAP) => NodeWeight::default_hap(),
+            Ok(NodeType::USER) => NodeWeight::default_user(),
+            Err(_) => anyhow::bail!("invalid node type of {}: {}", what, node_type),
+        },
+        None => anyhow::bail!("missing node type of {}", what),
+    };
+    overlay(default, attributes, &NODE_RESERVED, &what)
+}
+
+fn parse_edge(
+    ndx: usize,
+    attributes: &serde_json::Map<String, serde_json::Value>,
+    node_ids: &std::collections::HashMap<String, u32>,
+) -> anyhow::Result<(u32, u32, f64, LinkType)> {
+    let what = format!("edge {}", ndx);
+    let mut end_points = vec![];
+    for end_point in ["source", "target"] {
+        let id = attributes
+            .get(end_point)
+            .and_then(identifier)
+            .ok_or_else(|| anyhow::anyhow!("missing or invalid {} of {}", end_point, what))?;
+        match node_ids.get(&id) {
+            Some(node) => end_points.push(*node),
+            None => anyhow::bail!("unknown {} node '{}' of {}", end_point, id, what),
+        }
+    }
+    let distance = attributes
+        .get("distance")
+        .and_then(|distance| distance.as_f64())
+        .ok_or_else(|| anyhow::anyhow!("missing or invalid distance of {}", what))?;
+    let link = match attributes.get("link_type").and_then(|x| x.as_str()) {
+        None | Some("FreeSpace") => {
+            overlay(
+                serde_json::Map::new(),
+                attributes,
+                &EDGE_RESERVED,
+                &format!("free-space {}", what),
+            )?;
+            LinkType::FreeSpace
+        }
+        Some("Fiber") => LinkType::Fiber(overlay(
+            FiberParams::default(),
+            attributes,
+            &EDGE_RESERVED,
+            &what,
+        )?),
+        Some(link_type) => anyhow::bail!("invalid link type of {}: {}", what, link_type),
+    };
+    Ok((end_points[0], end_points[1], distance, link))
+}
+
+/// Create a physical topology from its JSON description.
+pub fn from_json_value(
+    description: &serde_json::Value,
+) -> anyhow::Result<crate::physical_topology::PhysicalTopology> {
+    let description = as_object(description, "the topology description")?;
+    for name in description.keys() {
+        if !["nodes", "edges", "fidelities", "uplink_loss"].contains(&name.as_str()) {
+            log::warn!(
+                "ignoring unknown field '{}' in the topology description",
+                name
+            );
+        }
+    }
+    let array = |name: &str| -> anyhow::Result<Vec<serde_json::Value>> {
+        match description.get(name) {
+            Some(serde_json::Value::Array(values)) => Ok(values.clone()),
+            Some(_) => anyhow::bail!("the {} are not an array", name),
+            None => anyhow::bail!("missing {} in the topology description", name),
+        }
+    };
+
+    let mut nodes = vec![];
+    let mut node_ids = std::collections::HashMap::new();
+    for (ndx, node) in array("nodes")?.iter().enumerate() {
+        let attributes = as_object(node, &format!("node {}", ndx))?;
+        let id = match attributes.get("id") {
+            Some(id) => identifier(id)
+                .ok_or_else(|| anyhow::anyhow!("invalid identifier of node {}: {}", ndx, id))?,
+            None => ndx.to_string(),
+        };
+        anyhow::ensure!(
+            node_ids.insert(id.clone(), ndx as u32).is_none(),
+            "duplicate node identifier '{}'",
+            id
+        );
+        nodes.push(parse_node(ndx, attributes)?);
+    }
+
+    let mut edges = vec![];
+    for (ndx, edge) in array("edges")?.iter().enumerate() {
+        let attributes = as_object(edge, &format!("edge {}", ndx))?;
+        edges.push(parse_edge(ndx, attributes, &node_ids)?);
+    }
+
+    let fidelities = match description.get("fidelities") {
+        Some(fidelities) => overlay(
+            StaticFidelities::default(),
+            as_object(fidelities, "fidelities")?,
+            &[],
+            "fidelities",
+        )?,
+        None => StaticFidelities::default(),
+    };
+
+    let uplink_loss = match description.get("uplink_loss") {
+        Some(uplink_loss) => uplink_loss
+            .as_f64()
+            .ok_or_else(|| anyhow::anyhow!("invalid uplink loss: {}", uplink_loss))?,
+        None => 0.0,
+    };
+
+    crate::physical_topology::PhysicalTopology::from_nodes_edges(
+        nodes,
+        edges,
+        fidelities,
+        uplink_loss,
+    )
+}
+
+/// Create a physical topology from its JSON description.
+pub fn from_json(json: &str) -> anyhow::Result<crate::physical_topology::PhysicalTopology> {
+    let description: serde_json::Value =
+        serde_json::from_str(json).map_err(|err| anyhow::anyhow!("malformed JSON: {}", err))?;
+    from_json_value(&description)
+}
+
+/// GraphML attribute declared by a key element.
+struct GraphmlKey {
+    domain: String,
+    name: String,
+    attr_type: String,
+    default: Option<String>,
+}
+
+/// Convert the textual value of a GraphML attribute to JSON.
+fn graphml_value(key: &GraphmlKey, text: &str, what: &str) -> anyhow::Result<serde_json::Value> {
+    let text = text.trim();
+    let invalid = || {
+        anyhow::anyhow!(
+            "invalid {} value '{}' for attribute '{}' of {}",
+            key.attr_type,
+            text,
+            key.name,
+            what
+        )
+    };
+    match key.attr_type.as_str() {
+        "int" | "long" => Ok(serde_json::Value::from(
+            text.parse::<i64>().map_err(|_| invalid())?,
+        )),
+        "float" | "double" => Ok(serde_json::Value::from(
+            text.parse::<f64>().map_err(|_| invalid())?,
+        )),
+        "boolean" => Ok(serde_json::Value::from(
+            text.parse::<bool>().map_err(|_| invalid())?,
+        )),
+        _ => Ok(serde_json::Value::from(text)),
+    }
+}
+
+/// Return the attributes of a GraphML element, including the defaults.
+fn graphml_attributes(
+    element: roxmltree::Node,
+    keys: &std::collections::HashMap<String, GraphmlKey>,
+    what: &str,
+) -> anyhow::Result<serde_json::Map<String, serde_json::Value>> {
+    let mut attributes = serde_json::Map::new();
+    for key in keys.values() {
+        if let Some(default) = &key.default {
+            if key.domain == element.tag_name().name() || key.domain == "all" {
+                attributes.insert(key.name.clone(), graphml_value(key, default, what)?);
+            }
+        }
+    }
+    for data in element.children().filter(|x| x.has_tag_name("data")) {
+        let key_id = data
+            .attribute("key")
+            .ok_or_else(|| anyhow::anyhow!("data without key in {}", what))?;
+        let key = keys
+            .get(key_id)
+            .ok_or_else(|| anyhow::anyhow!("undeclared key '{}' in {}", key_id, what))?;
+        attributes.insert(
+            key.name.clone(),
+            graphml_value(key, data.text().unwrap_or_default(), what)?,
+        );
+    }
+    Ok(attributes)
+}
+
+/// Convert a GraphML document into the equivalent JSON description.
+fn graphml_to_json(graphml: &str) -> anyhow::Result<serde_json::Value> {
+    let doc = roxmltree::Document::parse(graphml)
+        .map_err(|err| anyhow::anyhow!("malformed GraphML: {}", err))?;
+
+    let mut keys = std::collections::HashMap::new();
+    for key in doc.descendants().filter(|x| x.has_tag_name("key")) {
+        let id = key
+            .attribute("id")
+            .ok_or_else(|| anyhow::anyhow!("GraphML key without id"))?;
+        let name = key.attribute("attr.name").unwrap_or(id);
+        keys.insert(
+            id.to_string(),
+            GraphmlKey {
+                domain: key.attribute("for").unwrap_or("all").to_string(),
+                name: name.to_string(),
+                attr_type: key.attribute("attr.type").unwrap_or("string").to_string(),
+                default: key
+                    .children()
+                    .find(|x| x.has_tag_name("default"))
+                    .map(|x| x.text().unwrap_or_default().to_string()),
+            },
+        );
+    }
+
+    let graph = doc
+        .descendants()
+        .find(|x| x.has_tag_name("graph"))
+        .ok_or_else(|| anyhow::anyhow!("no graph in GraphML"))?;
+
+    let mut description = serde_json::Map::new();
+    let mut fidelities = serde_json::Map::new();
+    for (name, value) in graphml_attributes(graph, &keys, "the graph")? {
+        if name == "uplink_loss" {
+            description.insert(name, value);
+        } else {
+            fidelities.insert(name, value);
+        }
+    }
+    description.insert("fidelities".to_string(), fidelities.into());
+
+    let mut nodes = vec![];
+    for (ndx, node) in graph
+        .children()
+        .filter(|x| x.has_tag_name("node"))
+        .enumerate()
+    {
+        let what = format!("node {}", ndx);
+        let mut attributes = graphml_attributes(node, &keys, &what)?;
+        let id = node
+            .attribute("id")
+            .ok_or_else(|| anyhow::anyhow!("{} without id", what))?;
+        attributes.insert("id".to_string(), id.into());
+        nodes.push(serde_json::Value::Object(attributes));
+    }
+    description.insert("nodes".to_string(), nodes.into());
+
+    let mut edges = vec![];
+    for (ndx, edge) in graph
+        .children()
+        .filter(|x| x.has_tag_name("edge"))
+        .enumerate()
+    {
+        let what = format!("edge {}", ndx);
+        let mut attributes = graphml_attributes(edge, &keys, &what)?;
+        for end_point in ["source", "target"] {
+            let id = edge
+                .attribute(end_point)
+                .ok_or_else(|| anyhow::anyhow!("{} without {}", what, end_point))?;
+            attributes.insert(end_point.to_string(), id.into());
+        }
+        edges.push(serde_json::Value::Object(attributes));
+    }
+    description.insert("edges".to_string(), edges.into());
+
+    Ok(serde_json::Value::Object(description))
+}
+
+/// Create a physical topology from a GraphML document.
+pub fn from_graphml(graphml: &str) -> anyhow::Result<crate::physical_topology::PhysicalTopology> {
+    from_json_value(&graphml_to_json(graphml)?)
+}
+
+/// Create a physical topology from a file, whose format is determined by
+/// the extension: `.json` for JSON, `.graphml` or `.xml` for GraphML.
+pub fn from_file(path: &str) -> anyhow::Result<crate::physical_topology::PhysicalTopology> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|err| anyhow::anyhow!("cannot read '{}': {}", path, err))?;
+    let extension = std::path::Path::new(path)
+        .extension()
+        .map(|x| x.to_string_lossy().to_lowercase());
+    let res = match extension.as_deref() {
+        Some("json") => from_json(&content),
+        Some("graphml") | Some("xml") => from_graphml(&content),
+        _ => anyhow::bail!("unknown format of topology file '{}'", path),
+    };
+    res.map_err(|err| anyhow::anyhow!("{}: {}", path, err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{from_graphml, from_json};
+    use crate::physical_topology::NodeType;
+
+    const JSON: &str = r#"{
+        "uplink_loss": 3.0,
+        "fidelities": { "f_g": 0.8 },
+        "Network": "Italy",
+        "nodes": [
+            { "id": "milano", "node_type": "OGS", "memory_qubits": 50 },
+            { "id": "bologna", "node_type": "REP" },
+            { "id": "roma", "node_type": "OGS" },
+            { "id": 42, "node_type": "SAT", "Latitude": 45.5 }
+        ],
+        "edges": [
+            { "source": "milano", "target": "bologna", "distance": 200000.0,
+              "link_type": "Fiber", "attenuation": 0.17 },
+            { "source": "bologna", "target": "roma", "distance": 300000.0,
+              "link_type": "Fiber" },
+            { "source": 42, "target": "roma", "distance": 1000000.0 }
+        ]
+    }"#;
+
+    const GRAPHML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<graphml xmlns="http://graphml.graphdrawing.org/xmlns">
+  <key id="g0" for="graph" attr.name="f_g" attr.type="double"/>
+  <key id="d0" for="node" attr.name="node_type" attr.type="string">
+    <default>OGS</default>
+  </key>
+  <key id="d1" for="node" attr.name="memory_qubits" attr.type="int"/>
+  <key id="e0" for="edge" attr.name="distance" attr.type="double"/>
+  <key id="e1" for="edge" attr.name="link_type" attr.type="string"/>
+  <key id="e2" for="edge" attr.name="attenuation" attr.type="double"/>
+  <key id="z0" for="graph" attr.name="Network" attr.type="string"/>
+  <key id="z1" for="node" attr.name="Latitude" attr.type="double"/>
+  <key id="z2" for="node" attr.name="Country" attr.type="string"/>
+  <key id="z3" for="edge" attr.name="LinkLabel" attr.type="string"/>
+  <graph id="G" edgedefault="undirected">
+    <data key="g0">0.8</data>
+    <data key="z0">Italy</data>
+    <node id="milano">
+      <data key="d1">50</data><data key="z1">45.46</data><data key="z2">Italy</data>
+    </node>
+    <node id="bologna"><data key="d0">REP</data></node>
+    <node id="roma"/>
+    <node id="42"><data key="d0">SAT</data></node>
+    <edge source="milano" target="bologna">
+      <data key="e0">200000</data><data key="e1">Fiber</data><data key="e2">0.17</data>
+    </edge>
+    <edge source="bologna" target="roma">
+      <data key="e0">300000</data><data key="e1">Fiber</data>
+    </edge>
+    <edge source="42" target="roma">
+      <data key="e0">1000000</data><data key="z3">uplink</data>
+    </edge>
+  </graph>
+</graphml>"#;
+
+    fn check(topology: &mut crate::physical_topology::PhysicalTopology) {
+        assert_eq!(vec![0, 2], topology.ogs_indices());
+        assert_eq!(vec![3], topology.sat_indices());
+        let graph = topology.graph();
+        assert_eq!(4, graph.node_count());
+        assert_eq!(3, graph.edge_count());
+        let milano = graph.node_weight(0.into()).unwrap();
+        assert_eq!(50, milano.memory_qubits);
+        assert_eq!(
+            crate::physical_topology::NodeWeight::default_ogs().detectors,
+            milano.detectors
+        );
+        assert_eq!(
+            NodeType::REP,
+            graph.node_weight(1.into()).unwrap().node_type
+        );
+
+        assert_float_eq::assert_f64_near!(500000.0, topology.distance(0, 2).unwrap());
+        assert_eq!(0.8, topology.fidelity(3, 3, 2).unwrap());
+        assert_float_eq::assert_f64_near!(
+            10.0_f64.powf(-0.17 * 200.0 / 10.0),
+            topology.transmission_prob(0, 0, 1).unwrap()
+        );
+        assert_float_eq::assert_f64_near!(
+            10.0_f64.powf(-0.2 * 300.0 / 10.0),
+            topology.transmission_prob(1, 1, 2).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_topology_import_json() {
+        check(&mut from_json(JSON).unwrap());
+
+        let replace = |from: &str, to: &str| from_json(&JSON.replace(from, to));
+
+        assert!(from_json("{").is_err());
+        assert!(replace("\"nodes\"", "\"vertices\"").is_err());
+        assert!(replace("\"REP\"", "\"HUB\"").is_err());
+        assert!(replace("\"memory_qubits\": 50", "\"memory_qubits\": -1").is_err());
+        assert!(replace("\"node_type\": \"SAT\", ", "").is_err());
+        assert!(replace("\"target\": \"roma\"", "\"target\": \"napoli\"").is_err());
+        assert!(replace("\"distance\": 200000.0,", "").is_err());
+        assert!(replace("\"attenuation\": 0.17", "\"attenuation\": -0.17").is_err());
+
+        assert!(replace("\"f_g\": 0.8", "\"f_g\": 1.8").is_err());
+        assert!(replace("\"id\": 42", "\"id\": \"roma\"").is_err());
+
+        // Unknown attributes are ignored.
+        let topology = replace("\"memory_qubits\"", "\"memory\"").unwrap();
+        assert_eq!(
+            crate::physical_topology::NodeWeight::default_ogs().memory_qubits,
+            topology
+                .graph()
+                .node_weight(0.into())
+                .unwrap()
+                .memory_qubits
+        );
+        let topology = replace(
+            "\"link_type\": \"Fiber\", \"attenuation\"",
+            "\"attenuation\"",
+        )
+        .unwrap();
+        assert_eq!(3, topology.graph().edge_count());
+    }
+
+    #[test]
+    fn test_topology_import_graphml() {
+        check(&mut from_graphml(GRAPHML).unwrap());
+
+        let replace = |from: &str, to: &str| from_graphml(&GRAPHML.replace(from, to));
+
+        assert!(from_graphml("<graphml>").is_err());
+        assert!(replace("<data key=\"d1\">50</data>", "<data key=\"d1\">many</data>").is_err());
+        assert!(replace("<data key=\"d1\">50</data>", "<data key=\"d9\">50</data>").is_err());
+        assert!(replace("source=\"42\"", "source=\"43\"").is_err());
+        assert!(replace("<default>OGS</default>", "").is_err());
+    }
+}
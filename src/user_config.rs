@@ -1,17 +1,32 @@
 // SPDX-FileCopyrightText: © 2025 Claudio Cicconetti <c.cicconetti@iit.cnr.it>
 // SPDX-License-Identifier: MIT
 
+use rand::SeedableRng;
+
 fn default_sat_weight() -> crate::physical_topology::NodeWeight {
     crate::physical_topology::NodeWeight {
         node_type: crate::physical_topology::NodeType::SAT,
         memory_qubits: 20,
         decay_rate: 1.0,
+        write_efficiency: 1.0,
+        read_efficiency: 1.0,
+        write_latency: 0.0,
+        read_latency: 0.0,
+        storage_qubits: 0,
+        storage_decay_rate: 0.0,
+        transfer_duration: 0.0,
+        transfer_fidelity: 1.0,
         swapping_success_prob: 0.95,
         swapping_duration: 0.001,
         correction_duration: 0.0,
+        processing_units: 0,
         detectors: 10,
         transmitters: 10,
         capacity: 1000.0,
+        source: None,
+        multiplexing: None,
+        memoryless: false,
+        detection_jitter: 0.0,
     }
 }
 
@@ -20,21 +35,83 @@ fn default_ogs_weight() -> crate::physical_topology::NodeWeight {
         node_type: crate::physical_topology::NodeType::OGS,
         memory_qubits: 100,
         decay_rate: 1.0,
+        write_efficiency: 1.0,
+        read_efficiency: 1.0,
+        write_latency: 0.0,
+        read_latency: 0.0,
+        storage_qubits: 0,
+        storage_decay_rate: 0.0,
+        transfer_duration: 0.0,
+        transfer_fidelity: 1.0,
         swapping_success_prob: 0.0,
         swapping_duration: 0.0,
         correction_duration: 0.001,
+        processing_units: 0,
+        detectors: 10,
+        transmitters: 0,
+        capacity: 0.0,
+        source: None,
+        multiplexing: None,
+        memoryless: false,
+        detection_jitter: 0.0,
+    }
+}
+
+fn default_rep_weight() -> crate::physical_topology::NodeWeight {
+    crate::physical_topology::NodeWeight {
+        node_type: crate::physical_topology::NodeType::REP,
+        memory_qubits: 20,
+        decay_rate: 1.0,
+        write_efficiency: 1.0,
+        read_efficiency: 1.0,
+        write_latency: 0.0,
+        read_latency: 0.0,
+        storage_qubits: 0,
+        storage_decay_rate: 0.0,
+        transfer_duration: 0.0,
+        transfer_fidelity: 1.0,
+        swapping_success_prob: 0.95,
+        swapping_duration: 0.001,
+        correction_duration: 0.0,
+        processing_units: 0,
         detectors: 10,
         transmitters: 0,
         capacity: 0.0,
+        source: None,
+        multiplexing: None,
+        memoryless: false,
+        detection_jitter: 0.0,
     }
 }
 
+/// Join the non-empty CSV fragments with commas.
+fn join_csv(fragments: &[String]) -> String {
+    fragments
+        .iter()
+        .filter(|x| !x.is_empty())
+        .cloned()
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct ConfGridStatic {
     pub grid_params: crate::physical_topology::GridParams,
     pub sat_weight: crate::physical_topology::NodeWeight,
     pub ogs_weight: crate::physical_topology::NodeWeight,
     pub fidelities: crate::physical_topology::StaticFidelities,
+    /// OGS nodes that can act as intermediate repeaters.
+    #[serde(default)]
+    pub ogs_relays: Vec<crate::physical_topology::OgsRelay>,
+    /// Per-node characteristics that override `sat_weight` and `ogs_weight`.
+    #[serde(default)]
+    pub node_overrides: Vec<crate::physical_topology::NodeOverride>,
+    /// High-altitude platforms attached to OGS nodes.
+    #[serde(default)]
+    pub haps: Vec<crate::physical_topology::HapParams>,
+    /// End users attached to OGS nodes via last-mile fiber links.
+    #[serde(default)]
+    pub users: Vec<crate::physical_topology::UserParams>,
 }
 
 impl Default for ConfGridStatic {
@@ -44,6 +121,48 @@ impl Default for ConfGridStatic {
             sat_weight: default_sat_weight(),
             ogs_weight: default_ogs_weight(),
             fidelities: Default::default(),
+            ogs_relays: vec![],
+            node_overrides: vec![],
+            haps: vec![],
+            users: vec![],
+        }
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ConfGridFiber {
+    pub grid_params: crate::physical_topology::GridParams,
+    /// Terrestrial fiber links between the OGS nodes.
+    pub fiber_params: crate::physical_topology::GridFiberParams,
+    pub sat_weight: crate::physical_topology::NodeWeight,
+    pub ogs_weight: crate::physical_topology::NodeWeight,
+    pub fidelities: crate::physical_topology::StaticFidelities,
+    /// OGS nodes that can act as intermediate repeaters.
+    #[serde(default)]
+    pub ogs_relays: Vec<crate::physical_topology::OgsRelay>,
+    /// Per-node characteristics that override `sat_weight` and `ogs_weight`.
+    #[serde(default)]
+    pub node_overrides: Vec<crate::physical_topology::NodeOverride>,
+    /// High-altitude platforms attached to OGS nodes.
+    #[serde(default)]
+    pub haps: Vec<crate::physical_topology::HapParams>,
+    /// End users attached to OGS nodes via last-mile fiber links.
+    #[serde(default)]
+    pub users: Vec<crate::physical_topology::UserParams>,
+}
+
+impl Default for ConfGridFiber {
+    fn default() -> Self {
+        Self {
+            grid_params: Default::default(),
+            fiber_params: Default::default(),
+            sat_weight: default_sat_weight(),
+            ogs_weight: default_ogs_weight(),
+            fidelities: Default::default(),
+            ogs_relays: vec![],
+            node_overrides: vec![],
+            haps: vec![],
+            users: vec![],
         }
     }
 }
@@ -54,6 +173,18 @@ pub struct ConfChainStatic {
     pub sat_weight: crate::physical_topology::NodeWeight,
     pub ogs_weight: crate::physical_topology::NodeWeight,
     pub fidelities: crate::physical_topology::StaticFidelities,
+    /// OGS nodes that can act as intermediate repeaters.
+    #[serde(default)]
+    pub ogs_relays: Vec<crate::physical_topology::OgsRelay>,
+    /// Per-node characteristics that override `sat_weight` and `ogs_weight`.
+    #[serde(default)]
+    pub node_overrides: Vec<crate::physical_topology::NodeOverride>,
+    /// High-altitude platforms attached to OGS nodes.
+    #[serde(default)]
+    pub haps: Vec<crate::physical_topology::HapParams>,
+    /// End users attached to OGS nodes via last-mile fiber links.
+    #[serde(default)]
+    pub users: Vec<crate::physical_topology::UserParams>,
 }
 
 impl Default for ConfChainStatic {
@@ -63,77 +194,302 @@ impl Default for ConfChainStatic {
             sat_weight: default_sat_weight(),
             ogs_weight: default_ogs_weight(),
             fidelities: Default::default(),
+            ogs_relays: vec![],
+            node_overrides: vec![],
+            haps: vec![],
+            users: vec![],
+        }
+    }
+}
+
+/// Terrestrial topology made of fiber links, whose shape is created by
+/// the generator with parameters `P`.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ConfTerrestrial<P> {
+    pub generator_params: P,
+    /// Selection of the end nodes, all the others are repeaters.
+    pub end_nodes: crate::topology_generators::EndNodes,
+    pub repeater_weight: crate::physical_topology::NodeWeight,
+    pub end_node_weight: crate::physical_topology::NodeWeight,
+    pub fiber_params: crate::physical_topology::FiberParams,
+}
+
+impl<P: Default> Default for ConfTerrestrial<P> {
+    fn default() -> Self {
+        Self {
+            generator_params: P::default(),
+            end_nodes: Default::default(),
+            repeater_weight: default_rep_weight(),
+            end_node_weight: default_ogs_weight(),
+            fiber_params: Default::default(),
         }
     }
 }
 
+impl<P: crate::topology_generators::Generator + serde::Serialize> ConfTerrestrial<P> {
+    fn to_physical_topology(
+        &self,
+        seed: u64,
+    ) -> anyhow::Result<crate::physical_topology::PhysicalTopology> {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
+        crate::physical_topology::PhysicalTopology::from_generator(
+            &self.generator_params,
+            &self.end_nodes,
+            self.repeater_weight.clone(),
+            self.end_node_weight.clone(),
+            self.fiber_params,
+            &mut rng,
+        )
+    }
+
+    fn header(&self) -> String {
+        join_csv(&[
+            crate::utils::struct_to_csv_header(&self.generator_params).unwrap(),
+            crate::utils::struct_to_csv_header(EndNodes {
+                end_nodes: &self.end_nodes,
+            })
+            .unwrap(),
+            crate::utils::struct_to_csv_header(&self.repeater_weight).unwrap(),
+            crate::utils::struct_to_csv_header(&self.end_node_weight).unwrap(),
+            crate::utils::struct_to_csv_header(self.fiber_params).unwrap(),
+        ])
+    }
+
+    fn to_csv(&self) -> String {
+        join_csv(&[
+            crate::utils::struct_to_csv(&self.generator_params).unwrap(),
+            crate::utils::struct_to_csv(EndNodes {
+                end_nodes: &self.end_nodes,
+            })
+            .unwrap(),
+            crate::utils::struct_to_csv(&self.repeater_weight).unwrap(),
+            crate::utils::struct_to_csv(&self.end_node_weight).unwrap(),
+            crate::utils::struct_to_csv(self.fiber_params).unwrap(),
+        ])
+    }
+}
+
+/// Physical topology imported from a JSON or GraphML file.
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct ConfImport {
+    /// Path of the file, whose format depends on the extension.
+    pub path: String,
+}
+
+/// Wrapper to serialize the selection of end nodes into CSV.
+#[derive(serde::Serialize)]
+struct EndNodes<'a> {
+    end_nodes: &'a crate::topology_generators::EndNodes,
+}
+
+/// Wrapper to serialize into CSV the OGS relays, per-node overrides,
+/// high-altitude platforms and end users of the satellite topologies.
+#[derive(serde::Serialize)]
+struct Extensions<'a> {
+    ogs_relays: &'a Vec<crate::physical_topology::OgsRelay>,
+    node_overrides: &'a Vec<crate::physical_topology::NodeOverride>,
+    haps: &'a Vec<crate::physical_topology::HapParams>,
+    users: &'a Vec<crate::physical_topology::UserParams>,
+}
+
+/// Wrapper to serialize the classical network into CSV.
+#[derive(serde::Serialize)]
+struct Classical<'a> {
+    classical: &'a Option<crate::classical::ClassicalParams>,
+}
+
+/// Wrapper to serialize the time sharing of transmitters into CSV.
+#[derive(serde::Serialize)]
+struct TimeSharing<'a> {
+    time_sharing: &'a Option<crate::time_sharing::TimeSharingParams>,
+}
+
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub enum PhysicalTopology {
     ConfGridStatic(ConfGridStatic),
+    ConfGridFiber(ConfGridFiber),
     ConfChainStatic(ConfChainStatic),
+    ConfRandomGeometric(ConfTerrestrial<crate::topology_generators::RandomGeometricParams>),
+    ConfWaxman(ConfTerrestrial<crate::topology_generators::WaxmanParams>),
+    ConfBarabasiAlbert(ConfTerrestrial<crate::topology_generators::BarabasiAlbertParams>),
+    ConfRing(ConfTerrestrial<crate::topology_generators::RingParams>),
+    ConfStar(ConfTerrestrial<crate::topology_generators::StarParams>),
+    ConfTree(ConfTerrestrial<crate::topology_generators::TreeParams>),
+    ConfLattice(ConfTerrestrial<crate::topology_generators::LatticeParams>),
+    ConfImport(ConfImport),
 }
 
 impl PhysicalTopology {
+    /// Create the physical topology. The seed is used only by the generators
+    /// of terrestrial topologies.
     pub fn to_physical_topology(
         &self,
+        seed: u64,
     ) -> anyhow::Result<crate::physical_topology::PhysicalTopology> {
         match self {
             PhysicalTopology::ConfGridStatic(conf) => {
-                crate::physical_topology::PhysicalTopology::from_grid_static(
-                    conf.grid_params.clone(),
-                    conf.sat_weight.clone(),
-                    conf.ogs_weight.clone(),
-                    conf.fidelities.clone(),
-                )
+                let mut physical_topology =
+                    crate::physical_topology::PhysicalTopology::from_grid_static(
+                        conf.grid_params.clone(),
+                        conf.sat_weight.clone(),
+                        conf.ogs_weight.clone(),
+                        conf.fidelities.clone(),
+                    )?;
+                physical_topology.set_ogs_relays(&conf.ogs_relays)?;
+                physical_topology.set_node_overrides(&conf.node_overrides)?;
+                physical_topology.add_haps(&conf.haps)?;
+                physical_topology.add_users(&conf.users)?;
+                Ok(physical_topology)
+            }
+            PhysicalTopology::ConfGridFiber(conf) => {
+                let mut physical_topology =
+                    crate::physical_topology::PhysicalTopology::from_grid_fiber(
+                        conf.grid_params.clone(),
+                        conf.fiber_params.clone(),
+                        conf.sat_weight.clone(),
+                        conf.ogs_weight.clone(),
+                        conf.fidelities.clone(),
+                    )?;
+                physical_topology.set_ogs_relays(&conf.ogs_relays)?;
+                physical_topology.set_node_overrides(&conf.node_overrides)?;
+                physical_topology.add_haps(&conf.haps)?;
+                physical_topology.add_users(&conf.users)?;
+                Ok(physical_topology)
             }
             PhysicalTopology::ConfChainStatic(conf) => {
-                crate::physical_topology::PhysicalTopology::from_chain_static(
-                    conf.chain_params.clone(),
-                    conf.sat_weight.clone(),
-                    conf.ogs_weight.clone(),
-                    conf.fidelities.clone(),
-                )
+                let mut physical_topology =
+                    crate::physical_topology::PhysicalTopology::from_chain_static(
+                        conf.chain_params.clone(),
+                        conf.sat_weight.clone(),
+                        conf.ogs_weight.clone(),
+                        conf.fidelities.clone(),
+                    )?;
+                physical_topology.set_ogs_relays(&conf.ogs_relays)?;
+                physical_topology.set_node_overrides(&conf.node_overrides)?;
+                physical_topology.add_haps(&conf.haps)?;
+                physical_topology.add_users(&conf.users)?;
+                Ok(physical_topology)
             }
+            PhysicalTopology::ConfRandomGeometric(conf) => conf.to_physical_topology(seed),
+            PhysicalTopology::ConfWaxman(conf) => conf.to_physical_topology(seed),
+            PhysicalTopology::ConfBarabasiAlbert(conf) => conf.to_physical_topology(seed),
+            PhysicalTopology::ConfRing(conf) => conf.to_physical_topology(seed),
+            PhysicalTopology::ConfStar(conf) => conf.to_physical_topology(seed),
+            PhysicalTopology::ConfTree(conf) => conf.to_physical_topology(seed),
+            PhysicalTopology::ConfLattice(conf) => conf.to_physical_topology(seed),
+            PhysicalTopology::ConfImport(conf) => crate::topology_import::from_file(&conf.path),
+        }
+    }
+
+    /// Return the extensions of the satellite topologies, if any.
+    fn extensions(&self) -> Option<Extensions<'_>> {
+        match &self {
+            PhysicalTopology::ConfGridStatic(ConfGridStatic {
+                ogs_relays,
+                node_overrides,
+                haps,
+                users,
+                ..
+            })
+            | PhysicalTopology::ConfGridFiber(ConfGridFiber {
+                ogs_relays,
+                node_overrides,
+                haps,
+                users,
+                ..
+            })
+            | PhysicalTopology::ConfChainStatic(ConfChainStatic {
+                ogs_relays,
+                node_overrides,
+                haps,
+                users,
+                ..
+            }) => Some(Extensions {
+                ogs_relays,
+                node_overrides,
+                haps,
+                users,
+            }),
+            _ => None,
         }
     }
 }
 
 impl crate::utils::CsvFriend for PhysicalTopology {
     fn header(&self) -> String {
-        match &self {
-            PhysicalTopology::ConfGridStatic(conf) => format!(
-                "{},{},{},{}",
+        let header = match &self {
+            PhysicalTopology::ConfGridStatic(conf) => join_csv(&[
+                crate::utils::struct_to_csv_header(&conf.grid_params).unwrap(),
+                crate::utils::struct_to_csv_header(&conf.sat_weight).unwrap(),
+                crate::utils::struct_to_csv_header(&conf.ogs_weight).unwrap(),
+                crate::utils::struct_to_csv_header(&conf.fidelities).unwrap(),
+            ]),
+            PhysicalTopology::ConfGridFiber(conf) => join_csv(&[
                 crate::utils::struct_to_csv_header(&conf.grid_params).unwrap(),
+                crate::utils::struct_to_csv_header(&conf.fiber_params).unwrap(),
                 crate::utils::struct_to_csv_header(&conf.sat_weight).unwrap(),
                 crate::utils::struct_to_csv_header(&conf.ogs_weight).unwrap(),
-                crate::utils::struct_to_csv_header(&conf.fidelities).unwrap()
-            ),
-            PhysicalTopology::ConfChainStatic(conf) => format!(
-                "{},{},{},{}",
+                crate::utils::struct_to_csv_header(&conf.fidelities).unwrap(),
+            ]),
+            PhysicalTopology::ConfChainStatic(conf) => join_csv(&[
                 crate::utils::struct_to_csv_header(&conf.chain_params).unwrap(),
                 crate::utils::struct_to_csv_header(&conf.sat_weight).unwrap(),
                 crate::utils::struct_to_csv_header(&conf.ogs_weight).unwrap(),
-                crate::utils::struct_to_csv_header(&conf.fidelities).unwrap()
-            ),
+                crate::utils::struct_to_csv_header(&conf.fidelities).unwrap(),
+            ]),
+            PhysicalTopology::ConfRandomGeometric(conf) => conf.header(),
+            PhysicalTopology::ConfWaxman(conf) => conf.header(),
+            PhysicalTopology::ConfBarabasiAlbert(conf) => conf.header(),
+            PhysicalTopology::ConfRing(conf) => conf.header(),
+            PhysicalTopology::ConfStar(conf) => conf.header(),
+            PhysicalTopology::ConfTree(conf) => conf.header(),
+            PhysicalTopology::ConfLattice(conf) => conf.header(),
+            PhysicalTopology::ConfImport(conf) => crate::utils::struct_to_csv_header(conf).unwrap(),
+        };
+        match self.extensions() {
+            Some(extensions) => join_csv(&[
+                header,
+                crate::utils::struct_to_csv_header(extensions).unwrap(),
+            ]),
+            None => header,
         }
     }
 
     fn to_csv(&self) -> String {
-        match &self {
-            PhysicalTopology::ConfGridStatic(conf) => format!(
-                "{},{},{},{}",
+        let values = match &self {
+            PhysicalTopology::ConfGridStatic(conf) => join_csv(&[
+                crate::utils::struct_to_csv(&conf.grid_params).unwrap(),
+                crate::utils::struct_to_csv(&conf.sat_weight).unwrap(),
+                crate::utils::struct_to_csv(&conf.ogs_weight).unwrap(),
+                crate::utils::struct_to_csv(&conf.fidelities).unwrap(),
+            ]),
+            PhysicalTopology::ConfGridFiber(conf) => join_csv(&[
                 crate::utils::struct_to_csv(&conf.grid_params).unwrap(),
+                crate::utils::struct_to_csv(&conf.fiber_params).unwrap(),
                 crate::utils::struct_to_csv(&conf.sat_weight).unwrap(),
                 crate::utils::struct_to_csv(&conf.ogs_weight).unwrap(),
-                crate::utils::struct_to_csv(&conf.fidelities).unwrap()
-            ),
-            PhysicalTopology::ConfChainStatic(conf) => format!(
-                "{},{},{},{}",
+                crate::utils::struct_to_csv(&conf.fidelities).unwrap(),
+            ]),
+            PhysicalTopology::ConfChainStatic(conf) => join_csv(&[
                 crate::utils::struct_to_csv(&conf.chain_params).unwrap(),
                 crate::utils::struct_to_csv(&conf.sat_weight).unwrap(),
                 crate::utils::struct_to_csv(&conf.ogs_weight).unwrap(),
-                crate::utils::struct_to_csv(&conf.fidelities).unwrap()
-            ),
+                crate::utils::struct_to_csv(&conf.fidelities).unwrap(),
+            ]),
+            PhysicalTopology::ConfRandomGeometric(conf) => conf.to_csv(),
+            PhysicalTopology::ConfWaxman(conf) => conf.to_csv(),
+            PhysicalTopology::ConfBarabasiAlbert(conf) => conf.to_csv(),
+            PhysicalTopology::ConfRing(conf) => conf.to_csv(),
+            PhysicalTopology::ConfStar(conf) => conf.to_csv(),
+            PhysicalTopology::ConfTree(conf) => conf.to_csv(),
+            PhysicalTopology::ConfLattice(conf) => conf.to_csv(),
+            PhysicalTopology::ConfImport(conf) => crate::utils::struct_to_csv(conf).unwrap(),
+        };
+        match self.extensions() {
+            Some(extensions) => {
+                join_csv(&[values, crate::utils::struct_to_csv(extensions).unwrap()])
+            }
+            None => values,
         }
     }
 }
@@ -141,6 +497,16 @@ impl crate::utils::CsvFriend for PhysicalTopology {
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct LogicalTopology {
     pub physical_to_logical_policy: crate::logical_topology::PhysicalToLogicalPolicy,
+    /// Maximum difference between the detection times of the two halves of
+    /// an EPR pair on a memoryless logical link for the pair to be usable,
+    /// in s. The detection times include the propagation latency from the
+    /// transmitter to each end-point.
+    #[serde(default = "default_coincidence_window")]
+    pub coincidence_window: f64,
+}
+
+fn default_coincidence_window() -> f64 {
+    1e-9
 }
 
 impl Default for LogicalTopology {
@@ -148,6 +514,7 @@ impl Default for LogicalTopology {
         Self {
             physical_to_logical_policy:
                 crate::logical_topology::PhysicalToLogicalPolicy::RandomGreedy,
+            coincidence_window: default_coincidence_window(),
         }
     }
 }
@@ -178,6 +545,32 @@ impl Default for SourceDestPairs {
 pub struct ConfPing {
     pub source_dest_pairs: SourceDestPairs,
     pub max_requests: u64,
+    /// Type of the EPR requests.
+    #[serde(default)]
+    pub request_type: crate::event::RequestType,
+    /// Number of EPR pairs per request.
+    #[serde(default = "default_number_of_pairs")]
+    pub number_of_pairs: u32,
+    /// Delivery of the EPR pairs of a request.
+    #[serde(default)]
+    pub delivery: crate::event::Delivery,
+    /// Deadline of the EPR requests, in s. None means no deadline.
+    #[serde(default)]
+    pub timeout: Option<f64>,
+    /// Maximum number of retries of the EPR requests. None means unlimited.
+    #[serde(default)]
+    pub max_retries: Option<u32>,
+    /// Minimum end-to-end fidelity of the EPR pairs. None means any.
+    #[serde(default)]
+    pub min_fidelity: Option<f64>,
+    /// Traffic classes assigned to the applications in turn. If empty,
+    /// all the applications are in class 0.
+    #[serde(default)]
+    pub classes: Vec<u32>,
+}
+
+fn default_number_of_pairs() -> u32 {
+    1
 }
 
 impl Default for ConfPing {
@@ -185,6 +578,13 @@ impl Default for ConfPing {
         Self {
             source_dest_pairs: SourceDestPairs::default(),
             max_requests: 1,
+            request_type: crate::event::RequestType::default(),
+            number_of_pairs: default_number_of_pairs(),
+            delivery: crate::event::Delivery::default(),
+            timeout: None,
+            max_retries: None,
+            min_fidelity: None,
+            classes: vec![],
         }
     }
 }
@@ -208,10 +608,50 @@ impl Default for ConfClientServer {
     }
 }
 
+/// Applications that periodically book EPR pairs for a future time window.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ConfReservation {
+    pub source_dest_pairs: SourceDestPairs,
+    /// Maximum number of reservations per application, 0 means infinite.
+    pub max_reservations: u64,
+    /// Number of EPR pairs per reservation.
+    pub number_of_pairs: u32,
+    /// Time between a reservation and the start of its window, in s.
+    pub lead_time: f64,
+    /// Duration of the time window of the reservations, in s.
+    pub window: f64,
+    /// Time between consecutive reservations of an application, in s.
+    pub interval: f64,
+}
+
+impl Default for ConfReservation {
+    fn default() -> Self {
+        Self {
+            source_dest_pairs: SourceDestPairs::default(),
+            max_reservations: 0,
+            number_of_pairs: 10,
+            lead_time: 1.0,
+            window: 1.0,
+            interval: 1.0,
+        }
+    }
+}
+
+/// Create-and-measure applications at both ends of every memoryless
+/// logical link.
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct ConfMeasure {
+    /// Maximum number of EPR pairs measured by every application, 0 means
+    /// infinite.
+    pub max_pairs: u64,
+}
+
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub enum Applications {
     ConfPing(ConfPing),
     ConfClientServer(ConfClientServer),
+    ConfMeasure(ConfMeasure),
+    ConfReservation(ConfReservation),
 }
 
 impl Default for Applications {
@@ -230,6 +670,116 @@ impl crate::utils::CsvFriend for Applications {
     }
 }
 
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct Faults {
+    /// Random failures of nodes and links.
+    pub random: Vec<crate::faults::RandomFaults>,
+    /// Outages of nodes and links at fixed times.
+    pub scripted: Vec<crate::faults::ScriptedFault>,
+    /// Recompute the paths to avoid the logical links that are down.
+    pub reroute: bool,
+}
+
+impl crate::utils::CsvFriend for Faults {
+    fn header(&self) -> String {
+        crate::utils::struct_to_csv_header(self).unwrap()
+    }
+
+    fn to_csv(&self) -> String {
+        crate::utils::struct_to_csv(self).unwrap()
+    }
+}
+
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct Weather {
+    /// Weather Markov chain over each OGS, if any.
+    pub clouds: Option<crate::weather::WeatherParams>,
+    /// Day/night cycle, if any.
+    pub daylight: Option<crate::weather::DaylightParams>,
+}
+
+impl crate::utils::CsvFriend for Weather {
+    fn header(&self) -> String {
+        crate::utils::struct_to_csv_header(self).unwrap()
+    }
+
+    fn to_csv(&self) -> String {
+        crate::utils::struct_to_csv(self).unwrap()
+    }
+}
+
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct Processing {
+    /// Order in which local operations wait for a processing unit.
+    pub queue_discipline: crate::processor::QueueDiscipline,
+}
+
+impl crate::utils::CsvFriend for Processing {
+    fn header(&self) -> String {
+        crate::utils::struct_to_csv_header(self).unwrap()
+    }
+
+    fn to_csv(&self) -> String {
+        crate::utils::struct_to_csv(self).unwrap()
+    }
+}
+
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct Scheduling {
+    /// Order in which the requests pending at the nodes are served.
+    pub policy: crate::request_scheduler::SchedulingPolicy,
+    /// Weights of the traffic classes, by class, with weighted fair queuing.
+    /// The classes without a weight have weight 1.
+    pub weights: Vec<f64>,
+    /// Per-node policies that override `policy`.
+    pub node_policies: Vec<crate::request_scheduler::NodePolicy>,
+}
+
+impl crate::utils::CsvFriend for Scheduling {
+    fn header(&self) -> String {
+        crate::utils::struct_to_csv_header(self).unwrap()
+    }
+
+    fn to_csv(&self) -> String {
+        crate::utils::struct_to_csv(self).unwrap()
+    }
+}
+
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct Admission {
+    /// Policy to accept or reject the requests of the applications at
+    /// their source node.
+    pub policy: crate::admission::AdmissionPolicy,
+}
+
+impl crate::utils::CsvFriend for Admission {
+    fn header(&self) -> String {
+        crate::utils::struct_to_csv_header(self).unwrap()
+    }
+
+    fn to_csv(&self) -> String {
+        crate::utils::struct_to_csv(self).unwrap()
+    }
+}
+
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct Memory {
+    /// Policy to allocate the memory qubits of a node to its logical links.
+    pub policy: crate::memory_pool::MemoryPolicy,
+    /// Memory qubits guaranteed to every logical link with `MinGuarantee`.
+    pub min_qubits: u32,
+}
+
+impl crate::utils::CsvFriend for Memory {
+    fn header(&self) -> String {
+        crate::utils::struct_to_csv_header(self).unwrap()
+    }
+
+    fn to_csv(&self) -> String {
+        crate::utils::struct_to_csv(self).unwrap()
+    }
+}
+
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct UserConfig {
     /// The duration of the simulation, in s.
@@ -244,6 +794,30 @@ pub struct UserConfig {
     pub logical_topology: LogicalTopology,
     /// The applications.
     pub applications: Applications,
+    /// The failures of nodes and links.
+    #[serde(default)]
+    pub faults: Faults,
+    /// The weather and daylight conditions over the OGSs.
+    #[serde(default)]
+    pub weather: Weather,
+    /// The classical network used by the control plane, if simulated hop
+    /// by hop.
+    pub classical: Option<crate::classical::ClassicalParams>,
+    /// The local operations in the nodes.
+    #[serde(default)]
+    pub processing: Processing,
+    /// The allocation of memory qubits in the nodes.
+    #[serde(default)]
+    pub memory: Memory,
+    /// The scheduling of the requests pending at the nodes.
+    #[serde(default)]
+    pub scheduling: Scheduling,
+    /// The admission control of the requests of the applications.
+    #[serde(default)]
+    pub admission: Admission,
+    /// The schedule of transmitters time-shared by several logical links,
+    /// required with a time-shared physical to logical policy.
+    pub time_sharing: Option<crate::time_sharing::TimeSharingParams>,
 }
 
 impl Default for UserConfig {
@@ -255,6 +829,14 @@ impl Default for UserConfig {
             physical_topology: PhysicalTopology::ConfGridStatic(ConfGridStatic::default()),
             logical_topology: LogicalTopology::default(),
             applications: Applications::default(),
+            faults: Faults::default(),
+            weather: Weather::default(),
+            classical: None,
+            processing: Processing::default(),
+            memory: Memory::default(),
+            scheduling: Scheduling::default(),
+            admission: Admission::default(),
+            time_sharing: None,
         }
     }
 }
@@ -262,20 +844,297 @@ impl Default for UserConfig {
 impl crate::utils::CsvFriend for UserConfig {
     fn header(&self) -> String {
         format!(
-            "duration,warmup_period,{},{},{}",
+            "duration,warmup_period,{},{},{},{},{},{},{},{},{},{},{}",
             self.physical_topology.header(),
             self.logical_topology.header(),
-            self.applications.header()
+            self.applications.header(),
+            self.faults.header(),
+            self.weather.header(),
+            crate::utils::struct_to_csv_header(Classical {
+                classical: &self.classical
+            })
+            .unwrap(),
+            self.processing.header(),
+            self.memory.header(),
+            self.scheduling.header(),
+            self.admission.header(),
+            crate::utils::struct_to_csv_header(TimeSharing {
+                time_sharing: &self.time_sharing
+            })
+            .unwrap()
         )
     }
     fn to_csv(&self) -> String {
         format!(
-            "{},{},{},{},{}",
+            "{},{},{},{},{},{},{},{},{},{},{},{},{}",
             self.duration,
             self.warmup_period,
             self.physical_topology.to_csv(),
             self.logical_topology.to_csv(),
-            self.applications.to_csv()
+            self.applications.to_csv(),
+            self.faults.to_csv(),
+            self.weather.to_csv(),
+            crate::utils::struct_to_csv(Classical {
+                classical: &self.classical
+            })
+            .unwrap(),
+            self.processing.to_csv(),
+            self.memory.to_csv(),
+            self.scheduling.to_csv(),
+            self.admission.to_csv(),
+            crate::utils::struct_to_csv(TimeSharing {
+                time_sharing: &self.time_sharing
+            })
+            .unwrap()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::CsvFriend;
+
+    use rand::SeedableRng;
+
+    use super::{
+        ConfChainStatic, ConfGridFiber, ConfGridStatic, ConfTerrestrial, PhysicalTopology,
+        UserConfig,
+    };
+
+    #[test]
+    fn test_user_config_physical_topology_csv() {
+        let mut conf = ConfGridStatic::default();
+        let num_fields = |conf: &ConfGridStatic| {
+            let physical_topology = PhysicalTopology::ConfGridStatic(conf.clone());
+            let header = physical_topology.header();
+            let values = physical_topology.to_csv();
+            assert_eq!(header.split(',').count(), values.split(',').count());
+            header.split(',').count()
+        };
+
+        let num_fields_no_relays = num_fields(&conf);
+        conf.ogs_relays.push(crate::physical_topology::OgsRelay {
+            ogs: 3,
+            swapping_success_prob: 0.5,
+            swapping_duration: 0.01,
+        });
+        assert_eq!(num_fields_no_relays, num_fields(&conf));
+
+        let physical_topology = PhysicalTopology::ConfGridStatic(conf)
+            .to_physical_topology(0)
+            .unwrap();
+        let ogs_relay = physical_topology.ogs_indices()[3];
+        assert!(physical_topology.relay_indices().contains(&ogs_relay));
+    }
+
+    #[test]
+    fn test_user_config_node_overrides() {
+        let mut conf = ConfChainStatic::default();
+        let csv = |conf: &ConfChainStatic| {
+            let physical_topology = PhysicalTopology::ConfChainStatic(conf.clone());
+            (physical_topology.header(), physical_topology.to_csv())
+        };
+
+        let (header_no_overrides, _) = csv(&conf);
+        conf.node_overrides
+            .push(crate::physical_topology::NodeOverride {
+                selector: crate::physical_topology::NodeSelector::Position(0),
+                weight: crate::physical_topology::NodeWeightOverride {
+                    memory_qubits: Some(99),
+                    ..Default::default()
+                },
+            });
+        let (header, values) = csv(&conf);
+        assert_eq!(header_no_overrides, header);
+        assert!(values.contains(",\"selector.Position=0 weight.memory_qubits=99\","));
+
+        let physical_topology = PhysicalTopology::ConfChainStatic(conf)
+            .to_physical_topology(0)
+            .unwrap();
+        let sat = physical_topology.sat_indices()[0];
+        assert_eq!(
+            99,
+            physical_topology
+                .graph()
+                .node_weight(sat.into())
+                .unwrap()
+                .memory_qubits
+        );
+    }
+
+    #[test]
+    fn test_user_config_grid_fiber() {
+        let conf = PhysicalTopology::ConfGridFiber(ConfGridFiber::default());
+        let header = conf.header();
+        assert_eq!(header.split(',').count(), conf.to_csv().split(',').count());
+        assert!(header.contains("fiber.attenuation"));
+
+        let physical_topology = conf.to_physical_topology(0).unwrap();
+        let ogs_indices = physical_topology.ogs_indices();
+        assert!(physical_topology
+            .fidelity(ogs_indices[0], ogs_indices[0], ogs_indices[1])
+            .is_ok());
+    }
+
+    #[test]
+    fn test_user_config_terrestrial() {
+        let confs = vec![
+            PhysicalTopology::ConfRandomGeometric(ConfTerrestrial::default()),
+            PhysicalTopology::ConfWaxman(ConfTerrestrial::default()),
+            PhysicalTopology::ConfBarabasiAlbert(ConfTerrestrial::default()),
+            PhysicalTopology::ConfRing(ConfTerrestrial::default()),
+            PhysicalTopology::ConfStar(ConfTerrestrial::default()),
+            PhysicalTopology::ConfTree(ConfTerrestrial::default()),
+            PhysicalTopology::ConfLattice(ConfTerrestrial::default()),
+        ];
+        for conf in confs {
+            let header = conf.header();
+            assert_eq!(header.split(',').count(), conf.to_csv().split(',').count());
+            assert!(header.contains("end_nodes.LowestDegree"));
+        }
+
+        let conf = PhysicalTopology::ConfLattice(ConfTerrestrial::default());
+        let physical_topology = conf.to_physical_topology(42).unwrap();
+        assert_eq!(2, physical_topology.ogs_indices().len());
+        let mut rng = rand::rngs::StdRng::seed_from_u64(42);
+        let logical_topology = crate::logical_topology::LogicalTopology::from_physical_topology(
+            &crate::logical_topology::PhysicalToLogicalPolicy::RandomGreedy,
+            &physical_topology,
+            &mut rng,
         )
+        .unwrap();
+        crate::logical_topology::is_valid(logical_topology.graph(), &physical_topology).unwrap();
+    }
+
+    #[test]
+    fn test_user_config_haps_users() {
+        let mut conf = ConfChainStatic::default();
+        conf.haps.push(crate::physical_topology::HapParams {
+            ogs: 0,
+            ground_to_hap_distance: 20000.0,
+            hap_to_orbit_distance: 980000.0,
+            hap_weight: crate::physical_topology::NodeWeight::default_hap(),
+        });
+        for ogs in 0..2 {
+            conf.ogs_relays.push(crate::physical_topology::OgsRelay {
+                ogs,
+                swapping_success_prob: 1.0,
+                swapping_duration: 0.01,
+            });
+            conf.users.push(crate::physical_topology::UserParams {
+                ogs,
+                num_users: 1,
+                last_mile_distance: 10000.0,
+                last_mile: Default::default(),
+                user_weight: crate::physical_topology::NodeWeight {
+                    memory_qubits: 2,
+                    detectors: 2,
+                    ..crate::physical_topology::NodeWeight::default_user()
+                },
+            });
+        }
+        let conf = PhysicalTopology::ConfChainStatic(conf);
+        let header = conf.header();
+        let values = conf.to_csv();
+        assert_eq!(header.split(',').count(), values.split(',').count());
+        assert!(header.split(',').any(|column| column == "haps"));
+        assert!(header.split(',').any(|column| column == "users"));
+        assert!(values.contains("hap_weight.node_type="));
+        assert_eq!(2, values.matches("last_mile.attenuation=").count());
+
+        let physical_topology = conf.to_physical_topology(0).unwrap();
+        assert_eq!(vec![3], physical_topology.hap_indices());
+        assert_eq!(vec![4, 5], physical_topology.end_node_indices());
+        let mut rng = rand::rngs::StdRng::seed_from_u64(42);
+        let logical_topology = crate::logical_topology::LogicalTopology::from_physical_topology(
+            &crate::logical_topology::PhysicalToLogicalPolicy::RandomGreedy,
+            &physical_topology,
+            &mut rng,
+        )
+        .unwrap();
+        crate::logical_topology::is_valid(logical_topology.graph(), &physical_topology).unwrap();
+    }
+
+    #[test]
+    fn test_user_config_backward_compatible() {
+        // Template generated before the fields added later were introduced.
+        let json = r#"{
+          "duration": 10.0,
+          "warmup_period": 1.0,
+          "series_ignore": [],
+          "physical_topology": {
+            "ConfGridStatic": {
+              "grid_params": {
+                "orbit_to_orbit_distance": 3000000.0,
+                "ground_to_orbit_distance": 1000000.0,
+                "num_orbits": 3,
+                "orbit_length": 4
+              },
+              "sat_weight": {
+                "node_type": "SAT",
+                "memory_qubits": 20,
+                "decay_rate": 1.0,
+                "swapping_success_prob": 0.95,
+                "swapping_duration": 0.001,
+                "correction_duration": 0.0,
+                "detectors": 10,
+                "transmitters": 10,
+                "capacity": 1000.0
+              },
+              "ogs_weight": {
+                "node_type": "OGS",
+                "memory_qubits": 100,
+                "decay_rate": 1.0,
+                "swapping_success_prob": 0.0,
+                "swapping_duration": 0.0,
+                "correction_duration": 0.001,
+                "detectors": 10,
+                "transmitters": 0,
+                "capacity": 0.0
+              },
+              "fidelities": {
+                "f_o": 1.0,
+                "f_g": 1.0,
+                "f_oo": 1.0,
+                "f_og": 1.0,
+                "f_gg": 1.0
+              }
+            }
+          },
+          "logical_topology": {
+            "physical_to_logical_policy": "RandomGreedy"
+          },
+          "applications": {
+            "ConfPing": {
+              "source_dest_pairs": {
+                "Random": 1
+              },
+              "max_requests": 1
+            }
+          }
+        }"#;
+        let user_config: UserConfig = serde_json::from_str(json).unwrap();
+        let default = UserConfig::default();
+        assert_eq!(
+            serde_json::to_value(&default.faults).unwrap(),
+            serde_json::to_value(&user_config.faults).unwrap()
+        );
+        assert_eq!(
+            serde_json::to_value(&default.scheduling).unwrap(),
+            serde_json::to_value(&user_config.scheduling).unwrap()
+        );
+        assert_eq!(
+            default.logical_topology.coincidence_window,
+            user_config.logical_topology.coincidence_window
+        );
+        match user_config.physical_topology {
+            PhysicalTopology::ConfGridStatic(conf) => {
+                assert!(conf.ogs_relays.is_empty());
+                assert_eq!(20, conf.sat_weight.memory_qubits);
+                assert_eq!(1.0, conf.sat_weight.read_efficiency);
+                assert!(!conf.sat_weight.memoryless);
+            }
+            _ => panic!("wrong physical topology"),
+        }
     }
 }
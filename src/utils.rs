@@ -38,6 +38,22 @@ pub fn fidelity(f_init: f64, decay_rate: f64, time: f64) -> f64 {
     0.25 + (f_init - 0.25) * (-decay_rate * time).exp()
 }
 
+/// Return the seed of the pseudo-random number generator of a component,
+/// derived from the seed of the simulation, the kind of the component, and
+/// its index among those of the same kind.
+///
+/// The seed is obtained by hashing its inputs with FNV-1a, so that it does
+/// not depend on the platform or compiler version.
+pub fn component_seed(seed: u64, component: &str, index: u64) -> u64 {
+    seed.to_le_bytes()
+        .iter()
+        .chain(component.as_bytes())
+        .chain(index.to_le_bytes().iter())
+        .fold(0xcbf29ce484222325, |hash, byte| {
+            (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+        })
+}
+
 pub fn open_output_file(
     path: &str,
     filename: &str,
@@ -96,17 +112,111 @@ pub fn struct_to_csv_header<T: Serialize>(s: T) -> anyhow::Result<String> {
 }
 
 fn struct_to_map<T: Serialize>(s: T) -> anyhow::Result<serde_json::Map<String, serde_json::Value>> {
-    let value = serde_json::to_value(s)?;
+    let mut value = serde_json::to_value(s)?;
+    join_arrays(&mut value)?;
     let mut fields = json_unflattening::flattening::flatten(&value)?;
     fields.sort_keys();
     Ok(fields.clone())
 }
 
+/// Replace every array with a single string, so that the number of CSV
+/// columns does not depend on the length of the vectors.
+/// The elements are separated by `;` and the fields of structured elements
+/// are written as space-separated `name=value` pairs.
+fn join_arrays(value: &mut serde_json::Value) -> anyhow::Result<()> {
+    match value {
+        serde_json::Value::Array(elements) => {
+            let mut joined = vec![];
+            for element in elements.iter_mut() {
+                join_arrays(element)?;
+                joined.push(match element {
+                    serde_json::Value::Object(_) => {
+                        json_unflattening::flattening::flatten(element)?
+                            .iter()
+                            .map(|(name, value)| format!("{}={}", name, scalar_to_string(value)))
+                            .collect::<Vec<String>>()
+                            .join(" ")
+                    }
+                    _ => scalar_to_string(element),
+                });
+            }
+            *value = serde_json::Value::String(joined.join(";"));
+        }
+        serde_json::Value::Object(fields) => {
+            for field in fields.values_mut() {
+                join_arrays(field)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Return a scalar JSON value as a string, without quotes.
+fn scalar_to_string(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(value) => value.clone(),
+        _ => value.to_string(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::utils::fidelity;
 
-    use super::{to_nanoseconds, to_seconds};
+    use super::{component_seed, struct_to_csv, struct_to_csv_header, to_nanoseconds, to_seconds};
+
+    #[test]
+    fn test_struct_to_csv_vectors() {
+        #[derive(serde::Serialize)]
+        struct Element {
+            id: u32,
+            name: String,
+        }
+        #[derive(serde::Serialize)]
+        struct Conf {
+            weights: Vec<f64>,
+            elements: Vec<Element>,
+        }
+        let element = |id| Element {
+            id,
+            name: format!("e{id}"),
+        };
+
+        // The vectors take one column each, regardless of their length.
+        let empty = Conf {
+            weights: vec![],
+            elements: vec![],
+        };
+        let full = Conf {
+            weights: vec![3.0, 1.5],
+            elements: vec![element(1), element(2)],
+        };
+        assert_eq!("elements,weights", struct_to_csv_header(&empty).unwrap());
+        assert_eq!("elements,weights", struct_to_csv_header(&full).unwrap());
+        assert_eq!(
+            "\"id=1 name=e1;id=2 name=e2\",\"3.0;1.5\"",
+            struct_to_csv(&full).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_component_seed() {
+        let seeds = [
+            component_seed(42, "node", 0),
+            component_seed(42, "node", 1),
+            component_seed(42, "generator", 0),
+            component_seed(43, "node", 0),
+            component_seed(42, "faults", 0),
+            component_seed(42, "weather", 0),
+        ];
+        for (i, a) in seeds.iter().enumerate() {
+            for b in &seeds[i + 1..] {
+                assert_ne!(a, b);
+            }
+        }
+        assert_eq!(seeds[0], component_seed(42, "node", 0));
+    }
 
     #[test]
     fn test_to_from_nanosecs() {
@@ -0,0 +1,450 @@
+// SPDX-FileCopyrightText: © 2025 Claudio Cicconetti <c.cicconetti@iit.cnr.it>
+// SPDX-License-Identifier: MIT
+
+use rand::SeedableRng;
+use rand_distr::Distribution;
+
+use crate::event::{Event, EventType, NetworkEventData, WeatherData};
+
+/// Sky conditions over an OGS.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum WeatherState {
+    /// Clear sky: the optical links are not affected.
+    Clear,
+    /// Cloud cover: the EPR pairs are generated with a lower success
+    /// probability and fidelity.
+    Cloudy,
+    /// Optical links blocked, e.g., by thick clouds or rain.
+    Blocked,
+}
+
+impl WeatherState {
+    const ALL: [WeatherState; 3] = [
+        WeatherState::Clear,
+        WeatherState::Cloudy,
+        WeatherState::Blocked,
+    ];
+
+    fn index(&self) -> usize {
+        match self {
+            WeatherState::Clear => 0,
+            WeatherState::Cloudy => 1,
+            WeatherState::Blocked => 2,
+        }
+    }
+}
+
+/// Continuous-time Markov chain of the weather, which evolves independently
+/// over each OGS.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct WeatherParams {
+    /// Mean time spent in the clear, cloudy, and blocked states, in s.
+    pub mean_durations: [f64; 3],
+    /// Probabilities to move to the clear, cloudy, and blocked states
+    /// (columns) when leaving each state (rows).
+    pub transitions: [[f64; 3]; 3],
+    /// Factor applied to the success probability of the EPR generation
+    /// with cloudy sky.
+    pub cloudy_success_factor: f64,
+    /// Visibility of the EPR pairs generated with cloudy sky, which are
+    /// mixed with white noise.
+    pub cloudy_visibility: f64,
+}
+
+impl Default for WeatherParams {
+    fn default() -> Self {
+        Self {
+            mean_durations: [3600.0, 1800.0, 600.0],
+            transitions: [[0.0, 0.8, 0.2], [0.7, 0.0, 0.3], [0.3, 0.7, 0.0]],
+            cloudy_success_factor: 0.5,
+            cloudy_visibility: 0.95,
+        }
+    }
+}
+
+impl WeatherParams {
+    fn valid(&self) -> anyhow::Result<()> {
+        for state in WeatherState::ALL {
+            let ndx = state.index();
+            anyhow::ensure!(
+                self.mean_durations[ndx] > 0.0,
+                "invalid mean duration of the {:?} state: {} <= 0",
+                state,
+                self.mean_durations[ndx]
+            );
+            let row = &self.transitions[ndx];
+            anyhow::ensure!(
+                row.iter().all(|p| *p >= 0.0) && row[ndx] == 0.0,
+                "invalid transition probabilities from the {:?} state: {:?}",
+                state,
+                row
+            );
+            anyhow::ensure!(
+                (row.iter().sum::<f64>() - 1.0).abs() < 1e-6,
+                "the transition probabilities from the {:?} state do not sum to 1: {:?}",
+                state,
+                row
+            );
+        }
+        valid_factors(self.cloudy_success_factor, self.cloudy_visibility)
+    }
+}
+
+/// Day/night cycle, common to all the OGSs, with daylight background noise.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct DaylightParams {
+    /// Duration of a full day, in s.
+    pub period: f64,
+    /// Fraction of the day with daylight.
+    pub day_fraction: f64,
+    /// Fraction of the day elapsed since sunrise at the beginning of the
+    /// simulation.
+    pub phase: f64,
+    /// Factor applied to the success probability of the EPR generation
+    /// during the day.
+    pub day_success_factor: f64,
+    /// Visibility of the EPR pairs generated during the day, which are
+    /// mixed with white noise.
+    pub day_visibility: f64,
+}
+
+impl Default for DaylightParams {
+    fn default() -> Self {
+        Self {
+            period: 86400.0,
+            day_fraction: 0.5,
+            phase: 0.0,
+            day_success_factor: 0.8,
+            day_visibility: 0.9,
+        }
+    }
+}
+
+impl DaylightParams {
+    fn valid(&self) -> anyhow::Result<()> {
+        anyhow::ensure!(
+            self.period > 0.0,
+            "invalid day period: {} <= 0",
+            self.period
+        );
+        anyhow::ensure!(
+            (0.0..=1.0).contains(&self.day_fraction),
+            "invalid day fraction: {}",
+            self.day_fraction
+        );
+        anyhow::ensure!(
+            (0.0..1.0).contains(&self.phase),
+            "invalid day phase: {}",
+            self.phase
+        );
+        valid_factors(self.day_success_factor, self.day_visibility)
+    }
+
+    /// Return true if there is daylight at the given time, in s.
+    pub fn is_day(&self, time: f64) -> bool {
+        (time / self.period + self.phase).fract() < self.day_fraction
+    }
+}
+
+fn valid_factors(success_factor: f64, visibility: f64) -> anyhow::Result<()> {
+    anyhow::ensure!(
+        (0.0..=1.0).contains(&success_factor),
+        "invalid success factor: {}",
+        success_factor
+    );
+    anyhow::ensure!(
+        (0.0..=1.0).contains(&visibility),
+        "invalid visibility: {}",
+        visibility
+    );
+    Ok(())
+}
+
+/// Weather conditions over a single OGS.
+#[derive(Debug)]
+struct Station {
+    /// Current state.
+    state: WeatherState,
+    /// Time of the last change of state, in ns.
+    since: u64,
+    /// Time spent in the blocked state since the measurements started, in ns.
+    blocked_time: u64,
+}
+
+/// Weather and daylight conditions over the OGSs, which modulate the
+/// generation of EPR pairs through the ground-to-space links.
+#[derive(Debug)]
+pub struct WeatherModel {
+    /// Weather Markov chain, if any.
+    weather: Option<WeatherParams>,
+    /// Day/night cycle, if any.
+    daylight: Option<DaylightParams>,
+    /// Conditions over the OGSs, by node identifier.
+    stations: std::collections::BTreeMap<u32, Station>,
+    /// Time when the measurements started, in ns.
+    start: u64,
+    /// Pseudo-random number generator.
+    rng: rand::rngs::StdRng,
+}
+
+impl Default for WeatherModel {
+    /// Return a model where the sky is always clear and dark.
+    fn default() -> Self {
+        Self {
+            weather: None,
+            daylight: None,
+            stations: std::collections::BTreeMap::new(),
+            start: 0,
+            rng: rand::rngs::StdRng::seed_from_u64(0),
+        }
+    }
+}
+
+impl WeatherModel {
+    /// Create a model for the given OGSs, where the sky is initially clear.
+    pub fn new(
+        weather: Option<WeatherParams>,
+        daylight: Option<DaylightParams>,
+        ogs_indices: &[u32],
+        seed: u64,
+    ) -> anyhow::Result<Self> {
+        if let Some(weather) = &weather {
+            weather.valid()?;
+        }
+        if let Some(daylight) = &daylight {
+            daylight.valid()?;
+        }
+        Ok(Self {
+            weather,
+            daylight,
+            stations: ogs_indices
+                .iter()
+                .map(|ogs_node_id| {
+                    (
+                        *ogs_node_id,
+                        Station {
+                            state: WeatherState::Clear,
+                            since: 0,
+                            blocked_time: 0,
+                        },
+                    )
+                })
+                .collect(),
+            start: 0,
+            rng: rand::rngs::StdRng::seed_from_u64(seed),
+        })
+    }
+
+    /// Return the first change of weather over each OGS.
+    pub fn initial(&mut self) -> Vec<Event> {
+        let ogs_node_ids = self.stations.keys().cloned().collect::<Vec<u32>>();
+        ogs_node_ids
+            .into_iter()
+            .filter_map(|ogs_node_id| self.next_change(ogs_node_id, WeatherState::Clear))
+            .collect()
+    }
+
+    /// Move the weather over an OGS to the next state.
+    ///
+    /// Return the previous and the new state, and the next change of weather.
+    pub fn change(
+        &mut self,
+        now: u64,
+        data: &WeatherData,
+    ) -> (WeatherState, WeatherState, Option<Event>) {
+        let weather = self
+            .weather
+            .as_ref()
+            .expect("weather change without a weather model");
+        let station = self
+            .stations
+            .get_mut(&data.ogs_node_id)
+            .unwrap_or_else(|| panic!("weather change of unknown OGS {}", data.ogs_node_id));
+        let prev = station.state;
+        let probs = weather.transitions[prev.index()];
+        let new = WeatherState::ALL[rand_distr::WeightedIndex::new(probs)
+            .expect("invalid weather transition probabilities")
+            .sample(&mut self.rng)];
+
+        if prev == WeatherState::Blocked {
+            station.blocked_time += now - station.since.max(self.start);
+        }
+        station.state = new;
+        station.since = now;
+
+        (prev, new, self.next_change(data.ogs_node_id, new))
+    }
+
+    /// Return the success probability factor and the visibility of the EPR
+    /// pairs generated through the free-space links of the given OGSs.
+    pub fn conditions(&self, now: u64, ogs_node_ids: &[u32]) -> (f64, f64) {
+        let mut success_factor = 1.0;
+        let mut visibility = 1.0;
+        for ogs_node_id in ogs_node_ids {
+            if let (Some(weather), Some(station)) = (&self.weather, self.stations.get(ogs_node_id))
+            {
+                if station.state == WeatherState::Cloudy {
+                    success_factor *= weather.cloudy_success_factor;
+                    visibility *= weather.cloudy_visibility;
+                }
+            }
+            if let Some(daylight) = &self.daylight {
+                if daylight.is_day(crate::utils::to_seconds(now)) {
+                    success_factor *= daylight.day_success_factor;
+                    visibility *= daylight.day_visibility;
+                }
+            }
+        }
+        (success_factor, visibility)
+    }
+
+    /// Return true if the optical links of the given OGS are blocked.
+    pub fn is_blocked(&self, ogs_node_id: u32) -> bool {
+        self.stations
+            .get(&ogs_node_id)
+            .is_some_and(|x| x.state == WeatherState::Blocked)
+    }
+
+    /// Return the fraction of OGSs that are currently reachable.
+    pub fn reachable(&self) -> f64 {
+        if self.stations.is_empty() {
+            return 1.0;
+        }
+        let num_blocked = self
+            .stations
+            .values()
+            .filter(|x| x.state == WeatherState::Blocked)
+            .count();
+        1.0 - num_blocked as f64 / self.stations.len() as f64
+    }
+
+    /// Start measuring the time each OGS is reachable.
+    pub fn start_measurements(&mut self, now: u64) {
+        self.start = now;
+        for station in self.stations.values_mut() {
+            station.blocked_time = 0;
+        }
+    }
+
+    /// Return the fraction of time each OGS has been reachable since the
+    /// measurements started.
+    pub fn reachability(&self, now: u64) -> Vec<(u32, f64)> {
+        let elapsed = now.saturating_sub(self.start);
+        self.stations
+            .iter()
+            .map(|(ogs_node_id, station)| {
+                let mut blocked_time = station.blocked_time;
+                if station.state == WeatherState::Blocked {
+                    blocked_time += now - station.since.max(self.start);
+                }
+                let fraction = if elapsed == 0 {
+                    1.0
+                } else {
+                    1.0 - blocked_time as f64 / elapsed as f64
+                };
+                (*ogs_node_id, fraction)
+            })
+            .collect()
+    }
+
+    fn next_change(&mut self, ogs_node_id: u32, state: WeatherState) -> Option<Event> {
+        self.weather.as_ref().map(|weather| {
+            Event::new(
+                rand_distr::Exp::new(1.0 / weather.mean_durations[state.index()])
+                    .expect("could not create an expo rv")
+                    .sample(&mut self.rng),
+                EventType::NetworkEvent(NetworkEventData::WeatherChange(WeatherData {
+                    ogs_node_id,
+                })),
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::event::WeatherData;
+
+    use super::{DaylightParams, WeatherModel, WeatherParams, WeatherState};
+
+    #[test]
+    fn test_weather_daylight() {
+        let daylight = DaylightParams {
+            period: 24.0,
+            day_fraction: 0.25,
+            phase: 0.5,
+            ..Default::default()
+        };
+        daylight.valid().unwrap();
+        assert!(!daylight.is_day(0.0));
+        assert!(daylight.is_day(12.0));
+        assert!(daylight.is_day(17.9));
+        assert!(!daylight.is_day(18.1));
+        assert!(daylight.is_day(36.0));
+
+        assert!(DaylightParams {
+            phase: 1.0,
+            ..Default::default()
+        }
+        .valid()
+        .is_err());
+        assert!(DaylightParams {
+            day_visibility: 1.1,
+            ..Default::default()
+        }
+        .valid()
+        .is_err());
+    }
+
+    #[test]
+    fn test_weather_model() {
+        assert!(WeatherModel::new(
+            Some(WeatherParams {
+                transitions: [[0.5, 0.5, 0.0], [1.0, 0.0, 0.0], [1.0, 0.0, 0.0]],
+                ..Default::default()
+            }),
+            None,
+            &[],
+            42
+        )
+        .is_err());
+
+        // Alternate between clear and blocked, without clouds.
+        let mut model = WeatherModel::new(
+            Some(WeatherParams {
+                transitions: [[0.0, 0.0, 1.0], [1.0, 0.0, 0.0], [1.0, 0.0, 0.0]],
+                ..Default::default()
+            }),
+            Some(DaylightParams {
+                period: 10.0,
+                ..Default::default()
+            }),
+            &[3, 7],
+            42,
+        )
+        .unwrap();
+        assert_eq!(2, model.initial().len());
+        assert_eq!(1.0, model.reachable());
+
+        let data = WeatherData { ogs_node_id: 3 };
+        let (prev, new, event) = model.change(1_000_000_000, &data);
+        assert_eq!(WeatherState::Clear, prev);
+        assert_eq!(WeatherState::Blocked, new);
+        assert!(event.is_some());
+        assert!(model.is_blocked(3));
+        assert!(!model.is_blocked(7));
+        assert_eq!(0.5, model.reachable());
+
+        model.start_measurements(2_000_000_000);
+        let (_, new, _) = model.change(5_000_000_000, &data);
+        assert_eq!(WeatherState::Clear, new);
+        assert_eq!(vec![(3, 0.5), (7, 1.0)], model.reachability(8_000_000_000));
+
+        // Daylight at the beginning of the day, darkness at the end.
+        let (success_factor, visibility) = model.conditions(1_000_000_000, &[3, 7]);
+        assert_float_eq::assert_f64_near!(0.64, success_factor);
+        assert_float_eq::assert_f64_near!(0.81, visibility);
+        assert_eq!((1.0, 1.0), model.conditions(6_000_000_000, &[3, 7]));
+        assert_eq!((1.0, 1.0), model.conditions(1_000_000_000, &[]));
+    }
+}